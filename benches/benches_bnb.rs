@@ -1,7 +1,10 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rust_coinselect::{
     algorithms::bnb::select_coin_bnb,
-    types::{CoinSelectionOpt, ExcessStrategy, OutputGroup, SelectionError, SelectionOutput},
+    types::{
+        CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        SelectionOutput,
+    },
 };
 
 fn benchmark_select_coin_bnb(c: &mut Criterion) {
@@ -11,54 +14,62 @@ fn benchmark_select_coin_bnb(c: &mut Criterion) {
             weight: 500,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 400,
             weight: 200,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 40000,
             weight: 300,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 25000,
             weight: 100,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 35000,
             weight: 150,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 600,
             weight: 250,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 30000,
             weight: 120,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 5000,
             weight: 50,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
     ];
 
     let options = CoinSelectionOpt {
         target_value: 5730,
-        target_feerate: 0.5, // Simplified feerate
+        target_feerate: FeeRate::from_sat_per_wu(0.5), // Simplified feerate
         long_term_feerate: None,
         min_absolute_fee: 0,
         base_weight: 10,
@@ -66,6 +77,8 @@ fn benchmark_select_coin_bnb(c: &mut Criterion) {
         change_cost: 10,
         min_change_value: 500,
         excess_strategy: ExcessStrategy::ToChange,
+        rank_strategy: RankStrategy::MinInputs,
+        ..Default::default()
     };
 
     let mut final_result: Option<Result<SelectionOutput, SelectionError>> = None;
@@ -1,7 +1,10 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rust_coinselect::{
     algorithms::fifo::select_coin_fifo,
-    types::{CoinSelectionOpt, ExcessStrategy, OutputGroup, SelectionError, SelectionOutput},
+    types::{
+        CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        SelectionOutput,
+    },
 };
 
 fn benchmark_select_coin_fifo(c: &mut Criterion) {
@@ -11,31 +14,36 @@ fn benchmark_select_coin_fifo(c: &mut Criterion) {
             weight: 100,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 2000,
             weight: 200,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 3000,
             weight: 300,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
     ];
 
     let options = CoinSelectionOpt {
         target_value: 2500,
-        target_feerate: 0.4,
-        long_term_feerate: Some(0.4),
+        target_feerate: FeeRate::from_sat_per_wu(0.4),
+        long_term_feerate: Some(FeeRate::from_sat_per_wu(0.4)),
         min_absolute_fee: 0,
         base_weight: 10,
         change_weight: 50,
         change_cost: 10,
         min_change_value: 500,
         excess_strategy: ExcessStrategy::ToChange,
+        rank_strategy: RankStrategy::MinInputs,
+        ..Default::default()
     };
 
     c.bench_function("select_coin_fifo", |b| {
@@ -0,0 +1,152 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rust_coinselect::{
+    selectcoin::{select_coin, SelectionAlgorithm},
+    types::{Algorithm, CoinSelectionOpt, FeeRate, OutputGroup, ScriptType},
+    utils::{effective_value, effective_values},
+};
+
+const POOL_SIZES: [usize; 3] = [5_000, 20_000, 100_000];
+
+const ALGORITHMS: [Algorithm; 11] = [
+    Algorithm::Blackjack,
+    Algorithm::BranchAndBound,
+    Algorithm::CoinGrinder,
+    Algorithm::FewestInputs,
+    Algorithm::Fifo,
+    Algorithm::Genetic,
+    Algorithm::Knapsack,
+    Algorithm::LowestLarger,
+    Algorithm::MinimumLinkage,
+    Algorithm::SimulatedAnnealing,
+    Algorithm::SingleRandomDraw,
+];
+
+/// Deterministic xorshift generator, so every run benches the same pools.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+/// A wallet-like pool of `size` UTXOs: values spread log-uniformly from 1k to 10M sats with a
+/// share of round amounts, mostly P2WPKH with some P2TR and legacy coins, a few unconfirmed ones
+/// and addresses reused now and then.
+fn synthetic_pool(size: usize) -> Vec<OutputGroup> {
+    let mut rng = Rng(0x5eed_0000 ^ size as u64);
+    (0..size)
+        .map(|index| {
+            // 10^3 .. 10^7 sats, i.e. four decades split in 1000 steps each.
+            let exponent = 3.0 + rng.below(4_000) as f64 / 1_000.0;
+            let mut value = 10f64.powf(exponent) as u64;
+            if rng.below(4) == 0 {
+                // Payments received often are round amounts.
+                value = (value / 10_000).max(1) * 10_000;
+            }
+            let script_type = match rng.below(10) {
+                0..=6 => ScriptType::P2wpkh,
+                7 | 8 => ScriptType::P2tr,
+                _ => ScriptType::P2pkh,
+            };
+            OutputGroup {
+                value,
+                weight: script_type.input_weight(),
+                input_count: 1,
+                creation_sequence: Some(index as u32),
+                confirmations: if rng.below(20) == 0 { 0 } else { 6 },
+                cluster: (rng.below(5) == 0).then(|| rng.below(size as u64 / 10 + 1) as u32),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// A payment of `target_value` sats at 5 sat/vB.
+fn options(target_value: u64) -> CoinSelectionOpt {
+    CoinSelectionOpt::builder()
+        .target_value(target_value)
+        .target_feerate(FeeRate::from_sat_per_vb(5.0))
+        .long_term_feerate(FeeRate::from_sat_per_vb(10.0))
+        .base_weight(ScriptType::P2wpkh.output_weight() + 40)
+        .build()
+        .unwrap()
+}
+
+/// A payment of 0.05 BTC, which the pools fund from a handful of their coins.
+fn small_payment() -> CoinSelectionOpt {
+    options(5_000_000)
+}
+
+/// A payment of 90% of what `inputs` are worth at the feerate of [`options`], which takes
+/// thousands of their coins: the deep case for the tree searches, which follow one branch per
+/// input spent.
+fn sweeping_payment(inputs: &[OutputGroup]) -> CoinSelectionOpt {
+    let feerate = options(1).target_feerate;
+    let spendable: u64 = inputs
+        .iter()
+        .map(|input| effective_value(input, feerate).max(0) as u64)
+        .sum();
+    options(spendable / 10 * 9)
+}
+
+fn benchmark_large_pools(c: &mut Criterion) {
+    bench_pools(c, "large_pools", |_| small_payment());
+    bench_pools(c, "large_pools_sweeping", sweeping_payment);
+}
+
+/// Benches every algorithm, and `select_coin`, funding `payment` from each of the pools.
+fn bench_pools(c: &mut Criterion, name: &str, payment: fn(&[OutputGroup]) -> CoinSelectionOpt) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for size in POOL_SIZES {
+        let inputs = synthetic_pool(size);
+        let options = payment(&inputs);
+        for algorithm in ALGORITHMS {
+            group.bench_with_input(
+                BenchmarkId::new(algorithm.name(), size),
+                &inputs,
+                |b, inputs| b.iter(|| black_box(algorithm.select(black_box(inputs), &options))),
+            );
+        }
+        group.bench_with_input(
+            BenchmarkId::new("select_coin", size),
+            &inputs,
+            |b, inputs| b.iter(|| black_box(select_coin(black_box(inputs), &options))),
+        );
+    }
+    group.finish();
+}
+
+/// The batched [`effective_values`] against computing the effective values one input at a time.
+fn benchmark_effective_values(c: &mut Criterion) {
+    let feerate = small_payment().target_feerate;
+    let mut group = c.benchmark_group("effective_values");
+    for size in POOL_SIZES {
+        let inputs = synthetic_pool(size);
+        group.bench_with_input(BenchmarkId::new("batched", size), &inputs, |b, inputs| {
+            b.iter(|| black_box(effective_values(black_box(inputs), feerate)))
+        });
+        group.bench_with_input(BenchmarkId::new("scalar", size), &inputs, |b, inputs| {
+            b.iter(|| {
+                black_box(
+                    black_box(inputs)
+                        .iter()
+                        .map(|input| effective_value(input, feerate))
+                        .collect::<Vec<_>>(),
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_large_pools, benchmark_effective_values);
+criterion_main!(benches);
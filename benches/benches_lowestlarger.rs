@@ -1,7 +1,10 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rust_coinselect::{
     algorithms::lowestlarger::select_coin_lowestlarger,
-    types::{CoinSelectionOpt, ExcessStrategy, OutputGroup, SelectionError, SelectionOutput},
+    types::{
+        CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        SelectionOutput,
+    },
 };
 
 fn benchmark_select_coin_lowestlarger(c: &mut Criterion) {
@@ -11,85 +14,99 @@ fn benchmark_select_coin_lowestlarger(c: &mut Criterion) {
             weight: 100,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 1500,
             weight: 200,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 3400,
             weight: 300,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 2200,
             weight: 150,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 1190,
             weight: 200,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 3300,
             weight: 100,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 1000,
             weight: 190,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 2000,
             weight: 210,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 3000,
             weight: 300,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 2250,
             weight: 250,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 190,
             weight: 220,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
         OutputGroup {
             value: 1750,
             weight: 170,
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         },
     ];
 
     let options = CoinSelectionOpt {
         target_value: 20000,
-        target_feerate: 0.4,
-        long_term_feerate: Some(0.4),
+        target_feerate: FeeRate::from_sat_per_wu(0.4),
+        long_term_feerate: Some(FeeRate::from_sat_per_wu(0.4)),
         min_absolute_fee: 0,
         base_weight: 10,
         change_weight: 50,
         change_cost: 10,
         min_change_value: 500,
         excess_strategy: ExcessStrategy::ToChange,
+        rank_strategy: RankStrategy::MinInputs,
+        ..Default::default()
     };
 
     let mut final_result: Option<Result<SelectionOutput, SelectionError>> = None;
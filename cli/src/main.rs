@@ -0,0 +1,173 @@
+//! `coinselect` reads a selection request as JSON on stdin and prints the best selection as JSON.
+//!
+//! The request holds the candidate `inputs` as serialized [`OutputGroup`]s and the `options` as a
+//! serialized [`CoinSelectionOpt`], see the README for an example. The printed selection holds
+//! the winning algorithm, the selected input indices, the fee, the change value and the waste.
+//!
+//! With `--csv <file>`, the inputs are instead loaded from a UTXO export in the format read by
+//! [`parse_utxos`], the request on stdin only needs the `options`, and the printed selection also
+//! lists the `txid:vout` outpoints to spend.
+//!
+//! On success the selection is printed and the exit code is 0. Otherwise an object with an
+//! `error` message is printed and the exit code is 1.
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use rust_coinselect::{
+    csv::{parse_utxos, CsvUtxo},
+    selectcoin::{change_value, select_coin},
+    types::{CoinSelectionOpt, OutputGroup},
+};
+use serde::{Deserialize, Serialize};
+
+/// A selection request read from stdin.
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    inputs: Vec<OutputGroup>,
+    options: CoinSelectionOpt,
+}
+
+/// The best selection, printed to stdout.
+#[derive(Debug, Serialize)]
+struct Response {
+    algorithm: String,
+    selected_inputs: Vec<usize>,
+    fee: u64,
+    change: u64,
+    waste: i64,
+    selected_value: u64,
+    selected_weight: u64,
+    /// `txid:vout` of the selected coins, when the inputs were loaded from a CSV.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    selected_outpoints: Vec<String>,
+}
+
+/// A failed request, printed to stdout.
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+fn run(request: &str, utxos: &[CsvUtxo]) -> Result<Response, String> {
+    let Request {
+        mut inputs,
+        options,
+    } = serde_json::from_str(request).map_err(|err| format!("Invalid request: {err}"))?;
+    if !utxos.is_empty() {
+        inputs = utxos.iter().map(|utxo| utxo.group.clone()).collect();
+    }
+    let mut ranked = select_coin(&inputs, &options).map_err(|err| err.to_string())?;
+    let (algorithm, output) = ranked.swap_remove(0);
+    Ok(Response {
+        algorithm: format!("{algorithm:?}"),
+        change: change_value(&output, &inputs, &options),
+        selected_outpoints: utxos
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| output.selected_inputs.contains(index))
+            .map(|(_, utxo)| format!("{}:{}", utxo.txid, utxo.vout))
+            .collect(),
+        selected_inputs: output.selected_inputs,
+        fee: output.fee,
+        waste: output.waste.0,
+        selected_value: output.selected_value,
+        selected_weight: output.selected_weight,
+    })
+}
+
+/// Loads the coins of the `--csv <file>` argument, if given.
+fn load_utxos() -> Result<Vec<CsvUtxo>, String> {
+    let mut args = env::args().skip(1);
+    match (args.next().as_deref(), args.next()) {
+        (None, _) => Ok(Vec::new()),
+        (Some("--csv"), Some(path)) => {
+            let csv =
+                fs::read_to_string(&path).map_err(|err| format!("Failed to read {path}: {err}"))?;
+            let utxos = parse_utxos(&csv).map_err(|err| format!("{path}: {err}"))?;
+            if utxos.is_empty() {
+                return Err(format!("{path}: no UTXOs"));
+            }
+            Ok(utxos)
+        }
+        _ => Err("Usage: coinselect [--csv <file>] < request.json".to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut request = String::new();
+    let result = load_utxos().and_then(|utxos| {
+        io::stdin()
+            .read_to_string(&mut request)
+            .map_err(|err| format!("Failed to read stdin: {err}"))?;
+        run(&request, &utxos)
+    });
+    let (json, code) = match result {
+        Ok(response) => (serde_json::to_string_pretty(&response), ExitCode::SUCCESS),
+        Err(error) => (
+            serde_json::to_string_pretty(&ErrorResponse { error }),
+            ExitCode::FAILURE,
+        ),
+    };
+    println!("{}", json.expect("responses serialize to JSON"));
+    code
+}
+
+#[cfg(test)]
+mod test {
+    use rust_coinselect::csv::parse_utxos;
+
+    use super::run;
+
+    const OPTIONS: &str = r#"{
+        "target_value": 100000,
+        "target_feerate": 500,
+        "long_term_feerate": null,
+        "min_absolute_fee": 0,
+        "base_weight": 400,
+        "change_weight": 124,
+        "change_cost": 100,
+        "min_change_value": 294,
+        "excess_strategy": "ToChange",
+        "rank_strategy": "MinWaste",
+        "max_duration": null,
+        "seed": null,
+        "min_confirmations": 0
+    }"#;
+
+    #[test]
+    fn test_run() {
+        let request = format!(
+            r#"{{"inputs": [{{"value": 20000, "weight": 272}}, {{"value": 150000, "weight": 272}}],
+                "options": {OPTIONS}}}"#
+        );
+        let response = run(&request, &[]).unwrap();
+        assert_eq!(response.selected_inputs, vec![1]);
+        assert_eq!(response.selected_value, 150_000);
+        assert_eq!(
+            response.change,
+            response.selected_value - 100_000 - response.fee
+        );
+    }
+
+    #[test]
+    fn test_run_errors() {
+        assert!(run("{}", &[]).unwrap_err().starts_with("Invalid request"));
+        let request =
+            format!(r#"{{"inputs": [{{"value": 20000, "weight": 272}}], "options": {OPTIONS}}}"#);
+        assert!(run(&request, &[]).unwrap_err().contains("nsufficient"));
+    }
+
+    #[test]
+    fn test_run_with_csv() {
+        let utxos = parse_utxos("aa11,0,20000,p2wpkh,6\nbb22,1,150000,p2wpkh,6").unwrap();
+        let request = format!(r#"{{"options": {OPTIONS}}}"#);
+        let response = run(&request, &utxos).unwrap();
+        assert_eq!(response.selected_inputs, vec![1]);
+        assert_eq!(response.selected_outpoints, vec!["bb22:1"]);
+    }
+}
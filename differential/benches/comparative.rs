@@ -0,0 +1,74 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rust_coinselect::selectcoin::select_coin;
+use rust_coinselect_differential::{bdk_selection, greedy_selection, waste, Problem, Rng};
+
+const POOL_SIZES: [usize; 3] = [10, 100, 1_000];
+
+/// Payments of a quarter of the pool at 5 sat/vB with a long-term feerate of 10 sat/vB, so that the
+/// selectors have to trade fewer inputs now against consolidating them later.
+fn scenario(size: usize) -> Problem {
+    let mut rng = Rng::new(0x5eed_0000 ^ size as u64);
+    let inputs = Problem::generate_pool(&mut rng, size);
+    let total: u64 = inputs.iter().map(|input| input.value).sum();
+    Problem {
+        inputs,
+        target_value: total / 4,
+        feerate: 1_250,
+        long_term_feerate: 2_500,
+    }
+}
+
+/// Prints the waste of each selector's choice, as this crate measures it, and how much more it
+/// wastes than `select_coin`, since criterion only reports runtimes.
+fn report_waste(size: usize, problem: &Problem) {
+    let options = problem.options();
+    let ours = select_coin(&problem.inputs, &options)
+        .ok()
+        .and_then(|outputs| outputs.iter().map(|(_, output)| output.waste.0).min());
+    let theirs =
+        bdk_selection(problem, &options).and_then(|selected| waste(problem, &options, &selected));
+    let greedy = greedy_selection(problem, &options)
+        .and_then(|selected| waste(problem, &options, &selected));
+    for (selector, result) in [
+        ("select_coin", ours),
+        ("bdk_coin_select", theirs),
+        ("greedy", greedy),
+    ] {
+        match (result, ours) {
+            (Some(waste), Some(ours)) => {
+                println!(
+                    "{size:>5} inputs  {selector:<16} waste {waste:>8}  ({:+})",
+                    waste - ours
+                )
+            }
+            _ => println!("{size:>5} inputs  {selector:<16} no selection"),
+        }
+    }
+}
+
+fn benchmark_comparative(c: &mut Criterion) {
+    let mut group = c.benchmark_group("comparative");
+    group.sample_size(10);
+    for size in POOL_SIZES {
+        let problem = scenario(size);
+        let options = problem.options();
+        report_waste(size, &problem);
+        group.bench_with_input(
+            BenchmarkId::new("select_coin", size),
+            &problem,
+            |b, problem| b.iter(|| black_box(select_coin(black_box(&problem.inputs), &options))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("bdk_coin_select", size),
+            &problem,
+            |b, problem| b.iter(|| black_box(bdk_selection(black_box(problem), &options))),
+        );
+        group.bench_with_input(BenchmarkId::new("greedy", size), &problem, |b, problem| {
+            b.iter(|| black_box(greedy_selection(black_box(problem), &options)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_comparative);
+criterion_main!(benches);
@@ -0,0 +1,287 @@
+//! Differential tests against [`bdk_coin_select`]: both selectors are fed the same problems, and
+//! must agree on which payments can be funded, and the best selection of this crate must not waste
+//! noticeably more than the one `bdk_coin_select`'s Branch and Bound finds.
+//!
+//! The two crates account for weight slightly differently, e.g. `bdk_coin_select` counts the
+//! varint of the input count and works with floating point feerates, so they are only compared
+//! beyond a [`tolerance`] of a few sats. Waste is always measured the way this crate measures it,
+//! so both selections are ranked by the same yardstick.
+
+use bdk_coin_select::{
+    metrics::LowestFee, Candidate, ChangePolicy, CoinSelector, DrainWeights, FeeRate as BdkFeeRate,
+    Target, TargetFee, TargetOutputs,
+};
+use rust_coinselect::{
+    types::{CoinSelectionOpt, FeeRate, OutputGroup, ScriptType},
+    utils::{calculate_fee, calculate_waste, effective_value},
+};
+
+/// Weight of the version, locktime, input and output counts, and the segwit marker and flag.
+const HEADER_WEIGHT: u64 = (4 + 4 + 1 + 1) * 4 + 2;
+
+/// The script type of the recipient and of the change.
+const RECIPIENT: ScriptType = ScriptType::P2wpkh;
+
+/// Rounds `bdk_coin_select`'s Branch and Bound may spend before falling back to its greedy pass.
+const BNB_ROUNDS: usize = 100_000;
+
+/// Deterministic xorshift generator, so every run checks the same problems.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A value in `low..=high`.
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next_u64() % (high - low + 1)
+    }
+}
+
+/// A payment to one P2WPKH recipient from a pool of P2WPKH and P2TR coins.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub inputs: Vec<OutputGroup>,
+    pub target_value: u64,
+    /// Target feerate, in sats per 1000 weight units.
+    pub feerate: u64,
+    /// Long-term feerate, in sats per 1000 weight units.
+    pub long_term_feerate: u64,
+}
+
+impl Problem {
+    /// A pool of 1 to 30 coins worth 1,000 sats to 0.02 BTC, and a payment of up to 120% of the
+    /// pool at 1 to 100 sat/vB, so that some payments cannot be funded.
+    pub fn generate(rng: &mut Rng) -> Self {
+        let pool_size = rng.range(1, 30) as usize;
+        let inputs = Self::generate_pool(rng, pool_size);
+        let total: u64 = inputs.iter().map(|input| input.value).sum();
+        Problem {
+            target_value: rng.range(1_000, total * 6 / 5),
+            feerate: rng.range(1, 100) * 250,
+            long_term_feerate: rng.range(1, 20) * 250,
+            inputs,
+        }
+    }
+
+    /// A pool of `size` P2WPKH and P2TR coins worth 1,000 sats to 0.02 BTC.
+    pub fn generate_pool(rng: &mut Rng, size: usize) -> Vec<OutputGroup> {
+        (0..size)
+            .map(|_| {
+                // Spread the values over orders of magnitude rather than uniformly.
+                let value = rng.range(1_000, 10_000) * 10u64.pow(rng.range(0, 2) as u32);
+                let script_type = if rng.range(0, 1) == 0 {
+                    ScriptType::P2wpkh
+                } else {
+                    ScriptType::P2tr
+                };
+                OutputGroup::from_script_type(value, script_type, 1)
+            })
+            .collect()
+    }
+
+    /// The payment as this crate's options, with a P2WPKH change output.
+    pub fn options(&self) -> CoinSelectionOpt {
+        CoinSelectionOpt::builder()
+            .target_value(self.target_value)
+            .target_feerate(FeeRate::from_sat_per_kwu(self.feerate))
+            .long_term_feerate(FeeRate::from_sat_per_kwu(self.long_term_feerate))
+            .base_weight(HEADER_WEIGHT + RECIPIENT.output_weight())
+            .change_script_type(RECIPIENT)
+            .build()
+            .expect("generated problems are valid")
+    }
+
+    /// The pool as `bdk_coin_select` candidates.
+    pub fn candidates(&self) -> Vec<Candidate> {
+        self.inputs
+            .iter()
+            .map(|input| Candidate {
+                value: input.value,
+                weight: input.weight,
+                input_count: input.input_count,
+                is_segwit: true,
+            })
+            .collect()
+    }
+
+    /// The payment as a `bdk_coin_select` target.
+    pub fn target(&self) -> Target {
+        Target {
+            fee: TargetFee::from_feerate(bdk_feerate(self.feerate)),
+            outputs: TargetOutputs::fund_outputs([(RECIPIENT.output_weight(), self.target_value)]),
+        }
+    }
+
+    /// The change output of `options` as a `bdk_coin_select` change policy.
+    pub fn change_policy(&self, options: &CoinSelectionOpt) -> ChangePolicy {
+        ChangePolicy::min_value(
+            DrainWeights {
+                output_weight: options.change_weight,
+                spend_weight: RECIPIENT.input_weight(),
+                n_outputs: 1,
+            },
+            options.min_change_value,
+        )
+    }
+
+    /// Effective value of the pool, less the target and the fee of the rest of the transaction.
+    pub fn surplus(&self, options: &CoinSelectionOpt) -> i64 {
+        let available: i64 = self
+            .inputs
+            .iter()
+            .map(|input| effective_value(input, options.target_feerate).max(0))
+            .sum();
+        available
+            - (self.target_value + calculate_fee(options.base_weight, options.target_feerate))
+                as i64
+    }
+}
+
+fn bdk_feerate(sat_per_kwu: u64) -> BdkFeeRate {
+    BdkFeeRate::from_sat_per_wu(sat_per_kwu as f32 / 1000.0)
+}
+
+/// Sats by which the two selectors may disagree: the fee of an input and of the change output,
+/// and the minimum change below which either may drop the change to the fee.
+pub fn tolerance(options: &CoinSelectionOpt) -> u64 {
+    calculate_fee(
+        RECIPIENT.input_weight() + options.change_weight,
+        options.target_feerate,
+    ) + options.min_change_value
+}
+
+/// Whether `bdk_coin_select` can fund the payment at all, spending the whole pool.
+pub fn bdk_feasible(problem: &Problem) -> bool {
+    let candidates = problem.candidates();
+    let mut selector = CoinSelector::new(&candidates);
+    selector.select_all();
+    selector.is_target_met(problem.target())
+}
+
+/// Waste, as this crate measures it, of the selection `bdk_coin_select` makes. `None` when it
+/// cannot fund the payment.
+pub fn bdk_waste(problem: &Problem, options: &CoinSelectionOpt) -> Option<i64> {
+    waste(problem, options, &bdk_selection(problem, options)?)
+}
+
+/// Indices of the inputs `bdk_coin_select` selects with its lowest-fee Branch and Bound, or its
+/// greedy pass when that finds nothing. `None` when it cannot fund the payment.
+pub fn bdk_selection(problem: &Problem, options: &CoinSelectionOpt) -> Option<Vec<usize>> {
+    let candidates = problem.candidates();
+    let mut selector = CoinSelector::new(&candidates);
+    let target = problem.target();
+    let metric = LowestFee {
+        target,
+        long_term_feerate: bdk_feerate(problem.long_term_feerate),
+        change_policy: problem.change_policy(options),
+    };
+    if selector.run_bnb(metric, BNB_ROUNDS).is_err() {
+        selector.sort_candidates_by_descending_value_pwu();
+        selector.select_until_target_met(target).ok()?;
+    }
+    Some(selector.selected_indices().iter().copied().collect())
+}
+
+/// Indices of the inputs a naive greedy selector picks: the largest first, until they cover the
+/// target and the fee of the transaction with its change output. `None` when the pool falls short.
+///
+/// This is the baseline the comparative benches measure both crates against.
+pub fn greedy_selection(problem: &Problem, options: &CoinSelectionOpt) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..problem.inputs.len()).collect();
+    order.sort_unstable_by_key(|&index| core::cmp::Reverse(problem.inputs[index].value));
+    // Effective values already pay for their inputs, so the rest of the transaction is left.
+    let required = options.target_value
+        + calculate_fee(
+            options.base_weight + options.change_weight,
+            options.target_feerate,
+        );
+    let (mut selected, mut value) = (Vec::new(), 0i64);
+    for index in order {
+        selected.push(index);
+        value += effective_value(&problem.inputs[index], options.target_feerate);
+        if value >= required as i64 {
+            return Some(selected);
+        }
+    }
+    None
+}
+
+/// Waste, as this crate measures it, of spending the inputs at `selected`. `None` when they do not
+/// fund the payment.
+pub fn waste(problem: &Problem, options: &CoinSelectionOpt, selected: &[usize]) -> Option<i64> {
+    let value: i64 = selected
+        .iter()
+        .map(|&index| effective_value(&problem.inputs[index], options.target_feerate))
+        .sum();
+    let weight = selected
+        .iter()
+        .map(|&index| problem.inputs[index].weight)
+        .sum();
+    let waste = calculate_waste(options, u64::try_from(value).ok()?, weight).ok()?;
+    Some(waste.total().0)
+}
+
+#[cfg(test)]
+mod test {
+    use rust_coinselect::selectcoin::select_coin;
+
+    use crate::{bdk_feasible, bdk_waste, tolerance, Problem, Rng};
+
+    const PROBLEMS: usize = 500;
+
+    fn problems() -> impl Iterator<Item = Problem> {
+        let mut rng = Rng::new(0x5eed);
+        (0..PROBLEMS).map(move |_| Problem::generate(&mut rng))
+    }
+
+    #[test]
+    fn test_feasibility_matches_bdk_coin_select() {
+        let mut divergences = Vec::new();
+        for problem in problems() {
+            let options = problem.options();
+            // Too close to call given the different weight accounting.
+            if problem.surplus(&options).unsigned_abs() <= tolerance(&options) {
+                continue;
+            }
+            // Any error counts as infeasible: a fundable pool no algorithm selects from is a
+            // divergence too.
+            let ours = select_coin(&problem.inputs, &options).is_ok();
+            if ours != bdk_feasible(&problem) {
+                divergences.push(problem);
+            }
+        }
+        assert!(divergences.is_empty(), "{divergences:#?}");
+    }
+
+    #[test]
+    fn test_waste_no_worse_than_bdk_coin_select() {
+        let mut divergences = Vec::new();
+        for problem in problems() {
+            let options = problem.options();
+            let (Ok(ours), Some(theirs)) = (
+                select_coin(&problem.inputs, &options),
+                bdk_waste(&problem, &options),
+            ) else {
+                continue;
+            };
+            let ours = ours
+                .iter()
+                .map(|(_, output)| output.waste.0)
+                .min()
+                .expect("a successful selection has an output");
+            if ours > theirs + tolerance(&options) as i64 {
+                divergences.push((ours, theirs, problem));
+            }
+        }
+        assert!(divergences.is_empty(), "{divergences:#?}");
+    }
+}
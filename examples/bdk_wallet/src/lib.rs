@@ -0,0 +1,108 @@
+/*  A `bdk_wallet::coin_selection::CoinSelectionAlgorithm` backed by rust-coinselect. BDK hands over
+the required and optional coins, the feerate, the amount to fund and the drain (change) script;
+the coins are converted into OutputGroups, every rust-coinselect algorithm runs through
+`select_coin`, and the best selection is mapped back to BDK's coins.
+*/
+
+use bdk_wallet::{
+    bitcoin::{Amount, FeeRate, Script, TxIn, TxOut, Weight},
+    coin_selection::{
+        decide_change, CoinSelectionAlgorithm, CoinSelectionResult, InsufficientFunds,
+    },
+    Utxo, WeightedUtxo,
+};
+use rand_core::RngCore;
+use rust_coinselect::{
+    selectcoin::select_coin,
+    types::{CoinSelectionOpt, OutputGroup, ScriptType, SelectionError},
+    utils::{calculate_fee, cost_of_change},
+};
+
+/// Coin selection for BDK wallets through [`select_coin`].
+#[derive(Debug, Clone, Copy)]
+pub struct RustCoinSelect {
+    /// Long-term feerate for the waste metric, `None` for the transaction's feerate.
+    pub long_term_feerate: Option<FeeRate>,
+    /// Weight of later spending the change output, for its cost in the waste metric.
+    pub change_spend_weight: Weight,
+}
+
+impl Default for RustCoinSelect {
+    fn default() -> Self {
+        RustCoinSelect {
+            long_term_feerate: None,
+            change_spend_weight: Weight::from_wu(ScriptType::P2wpkh.input_weight()),
+        }
+    }
+}
+
+impl CoinSelectionAlgorithm for RustCoinSelect {
+    fn coin_select<R: RngCore>(
+        &self,
+        required_utxos: Vec<WeightedUtxo>,
+        optional_utxos: Vec<WeightedUtxo>,
+        fee_rate: FeeRate,
+        target_amount: Amount,
+        drain_script: &Script,
+        _rand: &mut R,
+    ) -> Result<CoinSelectionResult, InsufficientFunds> {
+        let required_count = required_utxos.len();
+        let utxos: Vec<WeightedUtxo> = required_utxos.into_iter().chain(optional_utxos).collect();
+        let groups: Vec<OutputGroup> = utxos.iter().map(output_group).collect();
+        let available = Amount::from_sat(groups.iter().map(|group| group.value).sum());
+
+        // BDK's target already pays for the transaction without its inputs, so the options only
+        // account for the inputs and the change output.
+        let change_output = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: drain_script.into(),
+        };
+        let mut options = CoinSelectionOpt::builder()
+            .target_value(target_amount.to_sat().max(1))
+            .target_feerate(fee_rate)
+            .change_weight(change_output.weight().to_wu())
+            .min_change_value(drain_script.minimal_non_dust().to_sat())
+            .build()
+            .map_err(|_| insufficient_funds(target_amount, available))?;
+        if let Some(long_term_feerate) = self.long_term_feerate {
+            options.long_term_feerate = Some(long_term_feerate.into());
+        }
+        options.change_cost = cost_of_change(&options, self.change_spend_weight.to_wu());
+        options.preselected_inputs = (0..required_count).collect();
+
+        let selected_inputs = match select_coin(&groups, &options) {
+            Ok(ranked) => ranked[0].1.selected_inputs.clone(),
+            Err(SelectionError::InsufficientFunds { required, .. }) => {
+                return Err(insufficient_funds(Amount::from_sat(required), available))
+            }
+            Err(_) => return Err(insufficient_funds(target_amount, available)),
+        };
+
+        let selected_amount: u64 = selected_inputs.iter().map(|&i| groups[i].value).sum();
+        let selected_weight: u64 = selected_inputs.iter().map(|&i| groups[i].weight).sum();
+        let fee_amount = Amount::from_sat(calculate_fee(selected_weight, fee_rate.into()));
+        let remaining_amount = Amount::from_sat(selected_amount)
+            .checked_sub(target_amount + fee_amount)
+            .ok_or_else(|| insufficient_funds(target_amount + fee_amount, available))?;
+        let selected: Vec<Utxo> = selected_inputs
+            .into_iter()
+            .map(|index| utxos[index].utxo.clone())
+            .collect();
+
+        Ok(CoinSelectionResult {
+            selected,
+            fee_amount,
+            excess: decide_change(remaining_amount, fee_rate, drain_script),
+        })
+    }
+}
+
+/// A single-coin group weighing the coin's input and its satisfaction.
+fn output_group(weighted_utxo: &WeightedUtxo) -> OutputGroup {
+    let weight = TxIn::default().segwit_weight() + weighted_utxo.satisfaction_weight;
+    OutputGroup::from_bitcoin(weighted_utxo.utxo.txout().value, weight)
+}
+
+fn insufficient_funds(needed: Amount, available: Amount) -> InsufficientFunds {
+    InsufficientFunds { needed, available }
+}
@@ -11,8 +11,8 @@ use bitcoin::{
 };
 use rust_coinselect::{
     selectcoin::select_coin,
-    types::{CoinSelectionOpt, ExcessStrategy, OutputGroup},
-    utils::calculate_fee,
+    types::{CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, ScriptType},
+    utils::{calculate_fee, cost_of_change},
 };
 use std::str::FromStr;
 
@@ -104,25 +104,31 @@ fn main() {
     };
 
     // Prepare CoinSelectionOpt
-    let long_term_feerate = 10.0;
+    let long_term_feerate = FeeRate::from_sat_per_wu(10.0);
     let change_weight = change_output.weight().to_wu();
-    let change_cost = calculate_fee(change_weight, long_term_feerate);
     let target_weight = target_output.weight().to_wu();
 
     // Create coin selection options
-    let coin_selection_option = CoinSelectionOpt {
+    let mut coin_selection_option = CoinSelectionOpt {
         target_value: target,
-        target_feerate: 15.0,
+        target_feerate: FeeRate::from_sat_per_wu(15.0),
         long_term_feerate: Some(long_term_feerate),
         min_absolute_fee: 4000,
         // Total default: (16 + 2 + 4 + 4 + 1 + 16 = 43 WU + variable) WU
         // Source - https://docs.rs/bitcoin/latest/src/bitcoin/blockdata/transaction.rs.html#599-602
         base_weight: target_weight + 43,
         change_weight,
-        change_cost,
+        change_cost: 0,
         min_change_value: 100,
         excess_strategy: ExcessStrategy::ToChange,
+        rank_strategy: RankStrategy::MinInputs,
+        ..Default::default()
     };
+    // Creating the P2SH-P2WPKH change output now, and spending it later at the long-term feerate.
+    coin_selection_option.change_cost = cost_of_change(
+        &coin_selection_option,
+        ScriptType::P2shP2wpkh.input_weight(),
+    );
 
     // Mock values for each input
     let mock_input_values = vec![100_000, 3_000_000, 1_000_000, 500_000];
@@ -138,6 +144,7 @@ fn main() {
             weight: input.segwit_weight().to_wu(),
             input_count: 1,
             creation_sequence: None,
+            ..Default::default()
         })
         .collect();
 
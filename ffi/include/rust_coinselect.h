@@ -0,0 +1,127 @@
+#ifndef RUST_COINSELECT_H
+#define RUST_COINSELECT_H
+
+/* Generated with cbindgen from ffi/src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of a call into the library. Every [`SelectionError`] has its own code.
+typedef enum CsStatus {
+  CS_STATUS_OK = 0,
+  // A required pointer argument was null.
+  CS_STATUS_NULL_POINTER,
+  CS_STATUS_INSUFFICIENT_FUNDS,
+  CS_STATUS_NO_SOLUTION_FOUND,
+  CS_STATUS_NON_POSITIVE_TARGET,
+  CS_STATUS_NON_POSITIVE_FEE_RATE,
+  CS_STATUS_ABNORMALLY_HIGH_FEE_RATE,
+  CS_STATUS_ZERO_CHANGE_WEIGHT,
+  CS_STATUS_ZERO_MIN_CHANGE_VALUE,
+  CS_STATUS_PRESELECTED_INPUT_OUT_OF_RANGE,
+  CS_STATUS_PRESELECTED_INPUT_FROZEN,
+  CS_STATUS_CANCELLED,
+  CS_STATUS_ARITHMETIC_OVERFLOW,
+  CS_STATUS_PRESELECTED_INPUT_LOCKED,
+  CS_STATUS_INVALID_FEE_RATE_BAND,
+  CS_STATUS_ZERO_INPUT_COUNT,
+} CsStatus;
+
+// Where the excess of a selection goes, see [`ExcessStrategy`].
+typedef enum CsExcessStrategy {
+  CS_EXCESS_STRATEGY_TO_FEE,
+  CS_EXCESS_STRATEGY_TO_RECIPIENT,
+  CS_EXCESS_STRATEGY_TO_CHANGE,
+} CsExcessStrategy;
+
+// The algorithm that produced a selection, see [`Algorithm`].
+typedef enum CsAlgorithm {
+  CS_ALGORITHM_BLACKJACK,
+  CS_ALGORITHM_BRANCH_AND_BOUND,
+  CS_ALGORITHM_COIN_GRINDER,
+  CS_ALGORITHM_FEWEST_INPUTS,
+  CS_ALGORITHM_FIFO,
+  CS_ALGORITHM_GENETIC,
+  CS_ALGORITHM_KNAPSACK,
+  CS_ALGORITHM_LOWEST_LARGER,
+  CS_ALGORITHM_MINIMUM_LINKAGE,
+  CS_ALGORITHM_SIMULATED_ANNEALING,
+  CS_ALGORITHM_SINGLE_RANDOM_DRAW,
+  CS_ALGORITHM_CUSTOM,
+} CsAlgorithm;
+
+// An input candidate, see [`OutputGroup`].
+typedef struct CsOutputGroup {
+  // Total value of the UTXO(s), in sats.
+  uint64_t value;
+  // Total weight of spending the UTXO(s), in weight units.
+  uint64_t weight;
+  // Number of UTXOs in the group.
+  uintptr_t input_count;
+  // Relative creation sequence for FIFO selection, or a negative value for none.
+  int64_t creation_sequence;
+} CsOutputGroup;
+
+// Options of a selection, see [`CoinSelectionOpt`]. Feerates are in sats per 1000 weight units.
+typedef struct CsOptions {
+  uint64_t target_value;
+  uint64_t target_feerate_sat_per_kwu;
+  // Zero leaves the long-term feerate unset, so the target feerate is used.
+  uint64_t long_term_feerate_sat_per_kwu;
+  uint64_t min_absolute_fee;
+  uint64_t base_weight;
+  uint64_t change_weight;
+  uint64_t change_cost;
+  uint64_t min_change_value;
+  enum CsExcessStrategy excess_strategy;
+  // Prefer selections that need no change output, see [`CoinSelectionOpt::avoid_change`].
+  bool avoid_change;
+} CsOptions;
+
+// The best selection, see [`SelectionOutput`].
+//
+// `selected_inputs` is owned by the library and must be released with [`cs_selection_free`].
+typedef struct CsSelection {
+  // Indices of the selected inputs.
+  uintptr_t *selected_inputs;
+  // Number of entries in `selected_inputs`.
+  uintptr_t selected_len;
+  int64_t waste;
+  uint64_t fee;
+  uint64_t selected_value;
+  uint64_t selected_weight;
+  enum CsAlgorithm algorithm;
+} CsSelection;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Runs every algorithm over `inputs` and writes the best selection to `out`.
+//
+// On any status other than `CS_STATUS_OK`, `out` is left untouched.
+//
+// # Safety
+//
+// `inputs` must point to `inputs_len` valid groups (or may be null when `inputs_len` is zero),
+// and `options` and `out` must be valid pointers.
+enum CsStatus cs_select_coin(const struct CsOutputGroup *inputs,
+                             uintptr_t inputs_len,
+                             const struct CsOptions *options,
+                             struct CsSelection *out);
+
+// Releases the inputs held by a selection written by [`cs_select_coin`]. Freeing a selection
+// twice, or a null pointer, is a no-op.
+//
+// # Safety
+//
+// `selection` must be null or point to a selection written by [`cs_select_coin`].
+void cs_selection_free(struct CsSelection *selection);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUST_COINSELECT_H */
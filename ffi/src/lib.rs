@@ -0,0 +1,342 @@
+//! C bindings for [`rust_coinselect`].
+//!
+//! The bindings expose [`select_coin`] through plain `#[repr(C)]` structs and a status code per
+//! [`SelectionError`], so C and C++ wallets can embed the selector. The header in
+//! `include/rust_coinselect.h` is generated from this file with
+//! `cbindgen --config cbindgen.toml --output include/rust_coinselect.h`.
+
+use std::{ptr, slice};
+
+use rust_coinselect::{
+    selectcoin::select_coin,
+    types::{
+        Algorithm, CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy,
+        SelectionError, SelectionOutput,
+    },
+};
+
+/// Outcome of a call into the library. Every [`SelectionError`] has its own code.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer,
+    InsufficientFunds,
+    NoSolutionFound,
+    NonPositiveTarget,
+    NonPositiveFeeRate,
+    AbnormallyHighFeeRate,
+    ZeroChangeWeight,
+    ZeroMinChangeValue,
+    PreselectedInputOutOfRange,
+    PreselectedInputFrozen,
+    Cancelled,
+    ArithmeticOverflow,
+    PreselectedInputLocked,
+    InvalidFeeRateBand,
+    ZeroInputCount,
+}
+
+impl From<SelectionError> for CsStatus {
+    fn from(err: SelectionError) -> Self {
+        match err {
+            SelectionError::InsufficientFunds { .. } => CsStatus::InsufficientFunds,
+            SelectionError::NoSolutionFound { .. } => CsStatus::NoSolutionFound,
+            SelectionError::NonPositiveTarget => CsStatus::NonPositiveTarget,
+            SelectionError::NonPositiveFeeRate => CsStatus::NonPositiveFeeRate,
+            SelectionError::AbnormallyHighFeeRate => CsStatus::AbnormallyHighFeeRate,
+            SelectionError::ZeroChangeWeight => CsStatus::ZeroChangeWeight,
+            SelectionError::ZeroMinChangeValue => CsStatus::ZeroMinChangeValue,
+            SelectionError::PreselectedInputOutOfRange => CsStatus::PreselectedInputOutOfRange,
+            SelectionError::PreselectedInputFrozen => CsStatus::PreselectedInputFrozen,
+            SelectionError::Cancelled => CsStatus::Cancelled,
+            SelectionError::ArithmeticOverflow => CsStatus::ArithmeticOverflow,
+            SelectionError::PreselectedInputLocked => CsStatus::PreselectedInputLocked,
+            SelectionError::InvalidFeeRateBand => CsStatus::InvalidFeeRateBand,
+            SelectionError::ZeroInputCount => CsStatus::ZeroInputCount,
+        }
+    }
+}
+
+/// Where the excess of a selection goes, see [`ExcessStrategy`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsExcessStrategy {
+    ToFee,
+    ToRecipient,
+    ToChange,
+}
+
+/// The algorithm that produced a selection, see [`Algorithm`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsAlgorithm {
+    Blackjack,
+    BranchAndBound,
+    CoinGrinder,
+    FewestInputs,
+    Fifo,
+    Genetic,
+    Knapsack,
+    LowestLarger,
+    MinimumLinkage,
+    SimulatedAnnealing,
+    SingleRandomDraw,
+    Custom,
+}
+
+impl From<Algorithm> for CsAlgorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Blackjack => CsAlgorithm::Blackjack,
+            Algorithm::BranchAndBound => CsAlgorithm::BranchAndBound,
+            Algorithm::CoinGrinder => CsAlgorithm::CoinGrinder,
+            Algorithm::FewestInputs => CsAlgorithm::FewestInputs,
+            Algorithm::Fifo => CsAlgorithm::Fifo,
+            Algorithm::Genetic => CsAlgorithm::Genetic,
+            Algorithm::Knapsack => CsAlgorithm::Knapsack,
+            Algorithm::LowestLarger => CsAlgorithm::LowestLarger,
+            Algorithm::MinimumLinkage => CsAlgorithm::MinimumLinkage,
+            Algorithm::SimulatedAnnealing => CsAlgorithm::SimulatedAnnealing,
+            Algorithm::SingleRandomDraw => CsAlgorithm::SingleRandomDraw,
+            Algorithm::Custom(_) => CsAlgorithm::Custom,
+        }
+    }
+}
+
+/// An input candidate, see [`OutputGroup`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CsOutputGroup {
+    /// Total value of the UTXO(s), in sats.
+    pub value: u64,
+    /// Total weight of spending the UTXO(s), in weight units.
+    pub weight: u64,
+    /// Number of UTXOs in the group.
+    pub input_count: usize,
+    /// Relative creation sequence for FIFO selection, or a negative value for none.
+    pub creation_sequence: i64,
+}
+
+impl From<&CsOutputGroup> for OutputGroup {
+    fn from(group: &CsOutputGroup) -> Self {
+        OutputGroup {
+            value: group.value,
+            weight: group.weight,
+            input_count: group.input_count,
+            creation_sequence: u32::try_from(group.creation_sequence).ok(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Options of a selection, see [`CoinSelectionOpt`]. Feerates are in sats per 1000 weight units.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CsOptions {
+    pub target_value: u64,
+    pub target_feerate_sat_per_kwu: u64,
+    /// Zero leaves the long-term feerate unset, so the target feerate is used.
+    pub long_term_feerate_sat_per_kwu: u64,
+    pub min_absolute_fee: u64,
+    pub base_weight: u64,
+    pub change_weight: u64,
+    pub change_cost: u64,
+    pub min_change_value: u64,
+    pub excess_strategy: CsExcessStrategy,
+    /// Prefer selections that need no change output, see [`CoinSelectionOpt::avoid_change`].
+    pub avoid_change: bool,
+}
+
+impl From<&CsOptions> for CoinSelectionOpt {
+    fn from(options: &CsOptions) -> Self {
+        CoinSelectionOpt {
+            target_value: options.target_value,
+            target_feerate: FeeRate::from_sat_per_kwu(options.target_feerate_sat_per_kwu),
+            long_term_feerate: (options.long_term_feerate_sat_per_kwu != 0)
+                .then(|| FeeRate::from_sat_per_kwu(options.long_term_feerate_sat_per_kwu)),
+            min_absolute_fee: options.min_absolute_fee,
+            base_weight: options.base_weight,
+            change_weight: options.change_weight,
+            change_cost: options.change_cost,
+            min_change_value: options.min_change_value,
+            excess_strategy: match options.excess_strategy {
+                CsExcessStrategy::ToFee => ExcessStrategy::ToFee,
+                CsExcessStrategy::ToRecipient => ExcessStrategy::ToRecipient,
+                CsExcessStrategy::ToChange => ExcessStrategy::ToChange,
+            },
+            rank_strategy: RankStrategy::default(),
+            avoid_change: options.avoid_change,
+            ..Default::default()
+        }
+    }
+}
+
+/// The best selection, see [`SelectionOutput`].
+///
+/// `selected_inputs` is owned by the library and must be released with [`cs_selection_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct CsSelection {
+    /// Indices of the selected inputs.
+    pub selected_inputs: *mut usize,
+    /// Number of entries in `selected_inputs`.
+    pub selected_len: usize,
+    pub waste: i64,
+    pub fee: u64,
+    pub selected_value: u64,
+    pub selected_weight: u64,
+    pub algorithm: CsAlgorithm,
+}
+
+impl CsSelection {
+    fn new(algorithm: Algorithm, output: SelectionOutput) -> Self {
+        let selected_inputs = output.selected_inputs.into_boxed_slice();
+        let selected_len = selected_inputs.len();
+        CsSelection {
+            selected_inputs: Box::into_raw(selected_inputs) as *mut usize,
+            selected_len,
+            waste: output.waste.0,
+            fee: output.fee,
+            selected_value: output.selected_value,
+            selected_weight: output.selected_weight,
+            algorithm: algorithm.into(),
+        }
+    }
+}
+
+/// Runs every algorithm over `inputs` and writes the best selection to `out`.
+///
+/// On any status other than `CS_STATUS_OK`, `out` is left untouched.
+///
+/// # Safety
+///
+/// `inputs` must point to `inputs_len` valid groups (or may be null when `inputs_len` is zero),
+/// and `options` and `out` must be valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn cs_select_coin(
+    inputs: *const CsOutputGroup,
+    inputs_len: usize,
+    options: *const CsOptions,
+    out: *mut CsSelection,
+) -> CsStatus {
+    if (inputs.is_null() && inputs_len != 0) || options.is_null() || out.is_null() {
+        return CsStatus::NullPointer;
+    }
+    let inputs: Vec<OutputGroup> = if inputs_len == 0 {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(inputs, inputs_len)
+            .iter()
+            .map(OutputGroup::from)
+            .collect()
+    };
+    let options = CoinSelectionOpt::from(&*options);
+    match select_coin(&inputs, &options) {
+        Ok(mut ranked) => {
+            let (algorithm, output) = ranked.swap_remove(0);
+            out.write(CsSelection::new(algorithm, output));
+            CsStatus::Ok
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// Releases the inputs held by a selection written by [`cs_select_coin`]. Freeing a selection
+/// twice, or a null pointer, is a no-op.
+///
+/// # Safety
+///
+/// `selection` must be null or point to a selection written by [`cs_select_coin`].
+#[no_mangle]
+pub unsafe extern "C" fn cs_selection_free(selection: *mut CsSelection) {
+    let Some(selection) = selection.as_mut() else {
+        return;
+    };
+    if !selection.selected_inputs.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            selection.selected_inputs,
+            selection.selected_len,
+        )));
+    }
+    selection.selected_inputs = ptr::null_mut();
+    selection.selected_len = 0;
+}
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+
+    use super::*;
+
+    fn options(target_value: u64) -> CsOptions {
+        CsOptions {
+            target_value,
+            target_feerate_sat_per_kwu: 500,
+            long_term_feerate_sat_per_kwu: 0,
+            min_absolute_fee: 0,
+            base_weight: 400,
+            change_weight: 124,
+            change_cost: 100,
+            min_change_value: 294,
+            excess_strategy: CsExcessStrategy::ToChange,
+            avoid_change: false,
+        }
+    }
+
+    fn group(value: u64) -> CsOutputGroup {
+        CsOutputGroup {
+            value,
+            weight: 272,
+            input_count: 1,
+            creation_sequence: -1,
+        }
+    }
+
+    #[test]
+    fn test_select_coin_through_ffi() {
+        let inputs = [group(20_000), group(150_000), group(30_000)];
+        let mut out = CsSelection {
+            selected_inputs: ptr::null_mut(),
+            selected_len: 0,
+            waste: 0,
+            fee: 0,
+            selected_value: 0,
+            selected_weight: 0,
+            algorithm: CsAlgorithm::Custom,
+        };
+        let status =
+            unsafe { cs_select_coin(inputs.as_ptr(), inputs.len(), &options(100_000), &mut out) };
+        assert_eq!(status, CsStatus::Ok);
+        let selected = unsafe { slice::from_raw_parts(out.selected_inputs, out.selected_len) };
+        let value: u64 = selected.iter().map(|&i| inputs[i].value).sum();
+        assert_eq!(value, out.selected_value);
+        assert!(out.selected_value >= 100_000 + out.fee);
+
+        unsafe {
+            cs_selection_free(&mut out);
+            cs_selection_free(&mut out);
+        }
+        assert!(out.selected_inputs.is_null());
+    }
+
+    #[test]
+    fn test_ffi_errors() {
+        let inputs = [group(20_000)];
+        let mut out = std::mem::MaybeUninit::<CsSelection>::uninit();
+        let status = unsafe {
+            cs_select_coin(
+                inputs.as_ptr(),
+                inputs.len(),
+                &options(100_000),
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, CsStatus::InsufficientFunds);
+        let status = unsafe { cs_select_coin(ptr::null(), 1, &options(100), out.as_mut_ptr()) };
+        assert_eq!(status, CsStatus::NullPointer);
+        let status = unsafe { cs_select_coin(inputs.as_ptr(), 1, &options(0), out.as_mut_ptr()) };
+        assert_eq!(status, CsStatus::NonPositiveTarget);
+    }
+}
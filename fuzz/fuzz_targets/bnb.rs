@@ -0,0 +1,31 @@
+//! Branch and Bound only returns changeless selections: their effective value covers the target
+//! and the fee of the transaction without a change output, exceeding it by at most the cost of
+//! change.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coinselect::{algorithms::bnb::select_coin_bnb, utils::calculate_fee};
+use rust_coinselect_fuzz::{check_selection, selected_values, Selection, MAX_TRIES};
+
+fuzz_target!(|selection: Selection| {
+    let inputs = selection.inputs();
+    let options = selection.options();
+    let Ok(output) = select_coin_bnb(&inputs, &options) else {
+        return;
+    };
+    check_selection(&output, &inputs, &options);
+
+    let metrics = output.metrics.expect("BnB reports its search metrics");
+    assert!(metrics.tries <= MAX_TRIES, "{} tries", metrics.tries);
+
+    let base_fee =
+        calculate_fee(options.base_weight, options.target_feerate).max(options.min_absolute_fee);
+    let target = (options.target_value + base_fee) as i64;
+    let value: i64 = selected_values(&output, &inputs, &options).iter().sum();
+    assert!(value >= target, "{value} sats fall short of {target}");
+    assert!(
+        value <= target + options.change_cost as i64,
+        "{value} sats exceed {target} by more than the cost of change"
+    );
+});
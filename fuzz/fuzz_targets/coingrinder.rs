@@ -0,0 +1,34 @@
+//! CoinGrinder stops adding inputs as soon as a selection covers the target, so none of its inputs
+//! can be dropped, and it stays within its search budget.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coinselect::algorithms::coingrinder::select_coin_coingrinder;
+use rust_coinselect_fuzz::{
+    check_selection, funding_target, selected_values, Selection, MAX_TRIES,
+};
+
+fuzz_target!(|selection: Selection| {
+    let inputs = selection.inputs();
+    let options = selection.options();
+    let Ok(output) = select_coin_coingrinder(&inputs, &options) else {
+        return;
+    };
+    check_selection(&output, &inputs, &options);
+
+    let metrics = output
+        .metrics
+        .expect("CoinGrinder reports its search metrics");
+    assert!(metrics.tries <= MAX_TRIES, "{} tries", metrics.tries);
+
+    let target = funding_target(&options);
+    let values = selected_values(&output, &inputs, &options);
+    let total: i64 = values.iter().sum();
+    assert!(total >= target, "{total} sats fall short of {target}");
+    let smallest = values.iter().min().expect("a selection spends an input");
+    assert!(
+        total - smallest < target,
+        "the {smallest} sat input is not needed"
+    );
+});
@@ -0,0 +1,39 @@
+//! FIFO spends the oldest spendable inputs first and stops as soon as they cover the target.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coinselect::algorithms::fifo::select_coin_fifo;
+use rust_coinselect_fuzz::{candidates, check_selection, funding_target, Selection};
+
+fuzz_target!(|selection: Selection| {
+    let inputs = selection.inputs();
+    let options = selection.options();
+    let Ok(output) = select_coin_fifo(&inputs, &options) else {
+        return;
+    };
+    check_selection(&output, &inputs, &options);
+
+    // Oldest first, the inputs without a creation sequence last in their original order.
+    let mut oldest_first = candidates(&inputs, &options);
+    oldest_first.sort_by_key(|&(index, _)| {
+        let sequence = inputs[index].creation_sequence;
+        (sequence.is_none(), sequence, index)
+    });
+    let selected = &output.selected_inputs;
+    assert!(
+        selected
+            .iter()
+            .zip(&oldest_first)
+            .all(|(selected, (oldest, _))| selected == oldest),
+        "{selected:?} are not the oldest inputs"
+    );
+    let before_last: i64 = oldest_first[..selected.len() - 1]
+        .iter()
+        .map(|&(_, value)| value)
+        .sum();
+    assert!(
+        before_last < funding_target(&options),
+        "the newest selected input is not needed"
+    );
+});
@@ -0,0 +1,42 @@
+//! Knapsack covers the target, returns the same selection for the same seed, and when it spends a
+//! single input worth at least the target plus the minimum change, that input is the lowest larger.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coinselect::algorithms::knapsack::select_coin_knapsack;
+use rust_coinselect_fuzz::{
+    candidates, check_selection, funding_target, selected_values, Selection,
+};
+
+fuzz_target!(|selection: Selection| {
+    let inputs = selection.inputs();
+    let options = selection.options();
+    let Ok(output) = select_coin_knapsack(&inputs, &options) else {
+        return;
+    };
+    check_selection(&output, &inputs, &options);
+
+    let replayed = select_coin_knapsack(&inputs, &options).expect("the same seed succeeds again");
+    assert_eq!(
+        replayed.selected_inputs, output.selected_inputs,
+        "the same seed selected differently"
+    );
+
+    let target = funding_target(&options);
+    let values = selected_values(&output, &inputs, &options);
+    let total: i64 = values.iter().sum();
+    assert!(total >= target, "{total} sats fall short of {target}");
+
+    let larger = target + options.min_change_value as i64;
+    if let [value] = values[..] {
+        if value >= larger {
+            for (index, other) in candidates(&inputs, &options) {
+                assert!(
+                    other < larger || other >= value,
+                    "input {index} worth {other} sats is a lower larger than {value}"
+                );
+            }
+        }
+    }
+});
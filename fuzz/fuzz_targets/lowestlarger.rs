@@ -0,0 +1,46 @@
+//! Lowest Larger either spends the smallest input that covers the target on its own, or smaller
+//! inputs accumulated smallest first, none of which covers the target alone and without the
+//! largest of which the others fall short.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coinselect::algorithms::lowestlarger::select_coin_lowestlarger;
+use rust_coinselect_fuzz::{
+    candidates, check_selection, funding_target, selected_values, Selection,
+};
+
+fuzz_target!(|selection: Selection| {
+    let inputs = selection.inputs();
+    let options = selection.options();
+    let Ok(output) = select_coin_lowestlarger(&inputs, &options) else {
+        return;
+    };
+    check_selection(&output, &inputs, &options);
+
+    let target = funding_target(&options);
+    let values = selected_values(&output, &inputs, &options);
+    if let [value] = values[..] {
+        assert!(value >= target, "{value} sats fall short of {target}");
+        for (index, other) in candidates(&inputs, &options) {
+            let within_cap = options
+                .max_input_count
+                .is_none_or(|max| inputs[index].input_count <= max);
+            assert!(
+                other < target || other >= value || !within_cap,
+                "input {index} worth {other} sats is a lower larger than {value}"
+            );
+        }
+    } else {
+        assert!(
+            values.iter().all(|&value| value < target),
+            "an accumulated input covers the target alone"
+        );
+        let total: i64 = values.iter().sum();
+        let largest = values.iter().max().expect("a selection spends an input");
+        assert!(
+            total - largest < target,
+            "the {largest} sat input is not needed"
+        );
+    }
+});
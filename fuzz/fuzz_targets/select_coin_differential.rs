@@ -0,0 +1,39 @@
+//! Runs every algorithm on the same pool and payment, checks each successful selection, and checks
+//! that the selection `select_coin` ranks first is at least as good as any other one.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coinselect::selectcoin::select_coin_detailed;
+use rust_coinselect_fuzz::{check_no_panic, check_selection, rank_key, Selection};
+
+fuzz_target!(|selection: Selection| {
+    let inputs = selection.inputs();
+    let options = selection.options();
+    let Ok(report) = select_coin_detailed(&inputs, &options) else {
+        return;
+    };
+
+    for outcome in &report.outcomes {
+        check_no_panic(&outcome.result);
+        if let Ok(output) = &outcome.result {
+            check_selection(output, &inputs, &options);
+        }
+    }
+
+    let winner = &report.outcomes[0];
+    assert_eq!(report.winner, winner.algorithm);
+    let best = rank_key(winner, &inputs, &options);
+    for outcome in report
+        .outcomes
+        .iter()
+        .filter(|outcome| outcome.result.is_ok())
+    {
+        assert!(
+            best <= rank_key(outcome, &inputs, &options),
+            "{:?} ranks ahead of the winner {:?}",
+            outcome.algorithm,
+            report.winner
+        );
+    }
+});
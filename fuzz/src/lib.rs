@@ -0,0 +1,264 @@
+//! Structured inputs and invariant checks shared by the fuzz targets.
+
+use std::collections::HashSet;
+
+use arbitrary::Arbitrary;
+use rust_coinselect::{
+    types::{
+        AlgorithmOutcome, CoinSelectionOpt, Constraint, ExcessStrategy, FeeRate, OutputGroup,
+        RankStrategy, SelectionError, SelectionOutput, COINBASE_MATURITY,
+    },
+    utils::{calculate_fee, effective_value},
+};
+
+/// Inputs beyond this are dropped, so that every run stays quick.
+const MAX_INPUTS: usize = 128;
+
+/// Search budget of the tree searches, well below the default for the same reason.
+pub const MAX_TRIES: u32 = 10_000;
+
+/// A pool and a payment to fund from it, within ranges that keep the arithmetic realistic: values
+/// up to ~43 BTC and feerates up to ~16 sat/vB.
+#[derive(Debug, Arbitrary)]
+pub struct Selection {
+    inputs: Vec<Input>,
+    target_value: u32,
+    target_feerate: u16,
+    long_term_feerate: Option<u16>,
+    min_absolute_fee: u16,
+    base_weight: u16,
+    change_weight: u8,
+    change_cost: u16,
+    min_change_value: u16,
+    excess_strategy: u8,
+    rank_strategy: u8,
+    min_confirmations: u8,
+    max_input_count: Option<u8>,
+    current_height: Option<u8>,
+    avoid_change: bool,
+    seed: u64,
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    value: u32,
+    weight: u16,
+    input_count: u8,
+    creation_sequence: Option<u16>,
+    cluster: Option<u8>,
+    ancestor_weight: u16,
+    ancestor_fee: u16,
+    confirmations: u8,
+    frozen: bool,
+    signing_cost: u16,
+    self_transfer: bool,
+    coinbase: bool,
+    available_height: Option<u8>,
+}
+
+impl Selection {
+    /// The pool as [`OutputGroup`]s.
+    pub fn inputs(&self) -> Vec<OutputGroup> {
+        self.inputs
+            .iter()
+            .take(MAX_INPUTS)
+            .map(|input| OutputGroup {
+                value: input.value.into(),
+                weight: input.weight.into(),
+                input_count: input.input_count.max(1).into(),
+                creation_sequence: input.creation_sequence.map(u32::from),
+                cluster: input.cluster.map(u32::from),
+                ancestor_weight: input.ancestor_weight.into(),
+                ancestor_fee: input.ancestor_fee.into(),
+                confirmations: input.confirmations.into(),
+                frozen: input.frozen,
+                signing_cost: input.signing_cost.into(),
+                weight_variants: Vec::new(),
+                self_transfer: input.self_transfer,
+                coinbase: input.coinbase,
+                available_height: input.available_height.map(u32::from),
+                labels: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// The payment as [`CoinSelectionOpt`], with feerates in sats per 1000 weight units.
+    pub fn options(&self) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value: self.target_value.into(),
+            target_feerate: FeeRate::from_sat_per_kwu(self.target_feerate.into()),
+            long_term_feerate: self
+                .long_term_feerate
+                .map(|feerate| FeeRate::from_sat_per_kwu(feerate.into())),
+            min_absolute_fee: self.min_absolute_fee.into(),
+            base_weight: self.base_weight.into(),
+            change_weight: self.change_weight.into(),
+            change_cost: self.change_cost.into(),
+            min_change_value: self.min_change_value.into(),
+            excess_strategy: match self.excess_strategy % 3 {
+                0 => ExcessStrategy::ToChange,
+                1 => ExcessStrategy::ToFee,
+                _ => ExcessStrategy::ToRecipient,
+            },
+            rank_strategy: match self.rank_strategy % 5 {
+                0 => RankStrategy::MinInputs,
+                1 => RankStrategy::MinWaste,
+                2 => RankStrategy::MinChange,
+                3 => RankStrategy::MinFee,
+                _ => RankStrategy::MinWeight,
+            },
+            seed: Some(self.seed),
+            min_confirmations: self.min_confirmations.into(),
+            max_input_count: self.max_input_count.map(usize::from),
+            avoid_change: self.avoid_change,
+            max_tries: Some(MAX_TRIES),
+            current_height: self.current_height.map(u32::from),
+            ..Default::default()
+        }
+    }
+}
+
+/// Asserts what every successful selection guarantees, whichever algorithm made it: its indices
+/// are in range and distinct, it only spends eligible inputs worth more than dust, within the input
+/// cap, it covers the target and its fee, and the fee is at least the minimum absolute fee.
+pub fn check_selection(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) {
+    let mut seen = HashSet::new();
+    for &index in &output.selected_inputs {
+        assert!(index < inputs.len(), "index {index} out of range");
+        assert!(seen.insert(index), "index {index} selected twice");
+        assert!(!inputs[index].frozen, "frozen input {index} selected");
+        assert!(
+            inputs[index].confirmations >= options.min_confirmations,
+            "input {index} lacks confirmations"
+        );
+    }
+    let candidates = candidates(inputs, options);
+    assert!(
+        output
+            .selected_inputs
+            .iter()
+            .all(|index| candidates.iter().any(|(candidate, _)| candidate == index)),
+        "dust input selected"
+    );
+    let input_count: usize = output
+        .selected_inputs
+        .iter()
+        .map(|&index| inputs[index].input_count)
+        .sum();
+    assert!(
+        options.max_input_count.is_none_or(|max| input_count <= max),
+        "{input_count} inputs over the cap"
+    );
+    let selected_value: u64 = output
+        .selected_inputs
+        .iter()
+        .map(|&index| inputs[index].value)
+        .sum();
+    assert_eq!(output.selected_value, selected_value);
+    assert!(
+        selected_value >= options.target_value + output.fee,
+        "{selected_value} sats do not cover the target and the {} sat fee",
+        output.fee
+    );
+    assert!(
+        output.fee >= options.min_absolute_fee,
+        "fee below the minimum"
+    );
+}
+
+/// Fails on an algorithm that panicked, which the wrapper API would otherwise report as a mere
+/// failure.
+pub fn check_no_panic(result: &Result<SelectionOutput, SelectionError>) {
+    assert_ne!(
+        result.as_ref().err(),
+        Some(&SelectionError::NoSolutionFound {
+            constraint: Constraint::AlgorithmPanicked
+        }),
+        "algorithm panicked"
+    );
+}
+
+/// Sort key of a successful outcome under the documented ranking of `select_coin`: changeless
+/// selections first with `avoid_change`, then the criteria of the rank strategy in order.
+pub fn rank_key(
+    outcome: &AlgorithmOutcome,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> (bool, i64, i64, i64) {
+    let output = outcome.result.as_ref().expect("ranking a failed outcome");
+    let input_count: usize = output
+        .selected_inputs
+        .iter()
+        .map(|&index| inputs[index].input_count)
+        .sum();
+    let input_count = input_count as i64;
+    let signing_cost: u64 = output
+        .selected_inputs
+        .iter()
+        .map(|&index| inputs[index].signing_cost)
+        .sum();
+    let waste = output.waste.0 + signing_cost as i64;
+    let change_value = outcome.change_value.expect("successful outcome") as i64;
+    let excess = output
+        .selected_value
+        .saturating_sub(options.target_value + output.fee);
+    let changeless = change_value == 0 && excess <= options.change_cost;
+    let (first, second, third) = match options.rank_strategy {
+        RankStrategy::MinInputs => (input_count, output.selected_inputs.len() as i64, waste),
+        RankStrategy::MinWaste => (waste, input_count, 0),
+        RankStrategy::MinChange => (change_value, waste, input_count),
+        RankStrategy::MinFee => (output.fee as i64, waste, input_count),
+        RankStrategy::MinWeight => (output.selected_weight as i64, waste, input_count),
+    };
+    (options.avoid_change && !changeless, first, second, third)
+}
+
+/// The inputs an algorithm may select, as `(index, effective value)`: eligible ones, neither
+/// immature coinbase nor timelocked, whose effective value at the target feerate reaches the dust
+/// threshold.
+pub fn candidates(inputs: &[OutputGroup], options: &CoinSelectionOpt) -> Vec<(usize, i64)> {
+    let dust_threshold = options.dust_threshold.unwrap_or(options.min_change_value);
+    inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| !input.frozen && input.confirmations >= options.min_confirmations)
+        .filter(|(_, input)| !input.coinbase || input.confirmations >= COINBASE_MATURITY)
+        .filter(|(_, input)| {
+            input.available_height.is_none_or(|available_height| {
+                options
+                    .current_height
+                    .is_some_and(|current_height| current_height >= available_height)
+            })
+        })
+        .map(|(index, input)| (index, effective_value(input, options.target_feerate)))
+        .filter(|&(_, value)| value > 0 && value as u64 >= dust_threshold)
+        .collect()
+}
+
+/// Effective value the selections with change must reach: the target plus the fee of the rest of
+/// the transaction, its change output included.
+pub fn funding_target(options: &CoinSelectionOpt) -> i64 {
+    let base_fee = calculate_fee(
+        options.base_weight + options.change_weight,
+        options.target_feerate,
+    )
+    .max(options.min_absolute_fee);
+    (options.target_value + base_fee) as i64
+}
+
+/// Effective values of the inputs `output` selected.
+pub fn selected_values(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Vec<i64> {
+    output
+        .selected_inputs
+        .iter()
+        .map(|&index| effective_value(&inputs[index], options.target_feerate))
+        .collect()
+}
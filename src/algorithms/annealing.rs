@@ -0,0 +1,393 @@
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+
+use crate::{
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, candidate_score, check_cancelled, exceeds_max_input_count, exp,
+        funding_target, insufficient_funds, select_with_preselected, selection_output,
+        unfunded_error, Deadline, SelectionContext, SplitMix64,
+    },
+};
+
+/// Temperature schedule and budget for [`select_coin_annealing_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnnealingParams {
+    /// Starting temperature, in satoshis of waste. A move that worsens waste by `delta` sats is
+    /// accepted with probability `exp(-delta / temperature)`.
+    pub initial_temperature: f64,
+    /// Factor the temperature is multiplied by after every step (geometric cooling), in `(0, 1)`.
+    pub cooling_rate: f64,
+    /// Maximum number of perturbation steps.
+    pub max_steps: u32,
+    /// Optional wall-clock budget. Once exhausted, the best selection seen so far is returned.
+    /// [`CoinSelectionOpt::max_duration`] is honoured as well; whichever runs out first wins.
+    pub time_budget: Option<Duration>,
+    /// Seed of the pseudo random generator, so that runs are reproducible.
+    pub seed: u64,
+}
+
+impl Default for AnnealingParams {
+    fn default() -> Self {
+        AnnealingParams {
+            initial_temperature: 1_000.0,
+            cooling_rate: 0.999,
+            max_steps: 20_000,
+            time_budget: None,
+            seed: 0,
+        }
+    }
+}
+
+/// Performs coin selection via simulated annealing with the default [`AnnealingParams`], seeded
+/// from [`CoinSelectionOpt::seed`] when it is set.
+pub fn select_coin_annealing(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    select_coin_annealing_with(inputs, options, &seeded_params(options))
+}
+
+/// [`select_coin_annealing`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_annealing_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    anneal(inputs, options, &seeded_params(options), context)
+}
+
+/// The default [`AnnealingParams`], seeded from [`CoinSelectionOpt::seed`] when it is set.
+fn seeded_params(options: &CoinSelectionOpt) -> AnnealingParams {
+    let defaults = AnnealingParams::default();
+    AnnealingParams {
+        seed: options.seed.unwrap_or(defaults.seed),
+        ..defaults
+    }
+}
+
+/// Performs coin selection via simulated annealing.
+///
+/// The search starts from the greedy largest-first selection and repeatedly perturbs it, either by
+/// flipping a single input in or out or by swapping a selected input for an unselected one. Moves
+/// that would no longer cover the target or would break [`CoinSelectionOpt::max_input_count`] are
+/// rejected. Improving moves are always taken; worsening moves are taken with a probability that
+/// shrinks as the temperature cools, which lets the search climb out of local minima that trap the
+/// greedy algorithms. The lowest-waste state visited is returned, or the best-scoring one under
+/// [`CoinSelectionOpt::selection_metric`] when it is set.
+///
+/// Unlike BnB, the amount of work is bounded by `max_steps` regardless of the pool shape, which
+/// makes this a good fit for mid-sized pools where exhaustive search runs out of tries.
+pub fn select_coin_annealing_with(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &AnnealingParams,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, |inputs, options| {
+            select_coin_annealing_with(inputs, options, params)
+        });
+    }
+    anneal(
+        inputs,
+        options,
+        params,
+        &SelectionContext::new(inputs, options)?,
+    )
+}
+
+fn anneal(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &AnnealingParams,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    let original_inputs = inputs;
+    // Largest first, so the greedy starting point below is the largest-first selection.
+    let inputs = &context.inputs;
+    // Effective values already net out per-input fees, so the target only needs the base fee.
+    let target = funding_target(options, true);
+
+    // Greedy starting point: largest-first until the target is covered.
+    let mut selected = vec![false; inputs.len()];
+    let mut value: u64 = 0;
+    let mut weight: u64 = 0;
+    let mut input_count: usize = 0;
+    for (position, input) in inputs.iter().enumerate() {
+        if value >= target || exceeds_max_input_count(options, input_count + input.input_count) {
+            break;
+        }
+        selected[position] = true;
+        value += input.value;
+        weight += input.weight;
+        input_count += input.input_count;
+    }
+    if value < target {
+        return Err(unfunded_error(options, insufficient_funds_error));
+    }
+
+    // An infeasible candidate ranks last, so the search moves off one as soon as it can.
+    let mut score = candidate_score(original_inputs, inputs, &selected, value, weight, options)?
+        .unwrap_or(i64::MAX);
+    let mut best_selected = selected.clone();
+    let mut best_score = score;
+
+    let mut rng = SplitMix64::new(params.seed);
+    let mut temperature = params.initial_temperature;
+    let budget = Deadline::after(params.time_budget);
+    let deadline = Deadline::start(options);
+
+    for _ in 0..params.max_steps {
+        check_cancelled(options)?;
+        if budget.has_passed() || deadline.has_passed() {
+            break;
+        }
+
+        // Perturb: flip one input, and half of the time also flip one from the opposite side.
+        let first = rng.below(inputs.len());
+        let second = rng.below(inputs.len());
+        let pair = [first, second];
+        let moves = if rng.next_u64() & 1 == 1 && selected[first] != selected[second] {
+            &pair[..]
+        } else {
+            &pair[..1]
+        };
+
+        let mut new_value = value;
+        let mut new_weight = weight;
+        let mut new_input_count = input_count;
+        for &position in moves {
+            if selected[position] {
+                new_value -= inputs[position].value;
+                new_weight -= inputs[position].weight;
+                new_input_count -= inputs[position].input_count;
+            } else {
+                new_value += inputs[position].value;
+                new_weight += inputs[position].weight;
+                new_input_count += inputs[position].input_count;
+            }
+        }
+        if new_value < target || exceeds_max_input_count(options, new_input_count) {
+            continue;
+        }
+
+        for &position in moves {
+            selected[position] = !selected[position];
+        }
+        let new_score = candidate_score(
+            original_inputs,
+            inputs,
+            &selected,
+            new_value,
+            new_weight,
+            options,
+        )?
+        .unwrap_or(i64::MAX);
+        let delta = new_score.saturating_sub(score) as f64;
+        if delta <= 0.0 || rng.next_f64() < exp(-delta / temperature) {
+            value = new_value;
+            weight = new_weight;
+            input_count = new_input_count;
+            score = new_score;
+            if score < best_score {
+                best_score = score;
+                best_selected.clone_from(&selected);
+            }
+        } else {
+            // Rejected: undo the move.
+            for &position in moves {
+                selected[position] = !selected[position];
+            }
+        }
+        temperature *= params.cooling_rate;
+    }
+
+    let mut selected_inputs = Vec::new();
+    let mut accumulated_value: u64 = 0;
+    let mut accumulated_weight: u64 = 0;
+    for (input, _) in inputs
+        .iter()
+        .zip(&best_selected)
+        .filter(|(_, &is_selected)| is_selected)
+    {
+        selected_inputs.push(input.index);
+        accumulated_value += input.value;
+        accumulated_weight += input.weight;
+    }
+    let (fee, waste) = calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
+    selection_output(options, inputs, selected_inputs, fee, waste, true, None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::{
+        algorithms::annealing::{
+            select_coin_annealing, select_coin_annealing_with, AnnealingParams,
+        },
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+            SelectionMetric, SelectionOutput,
+        },
+    };
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value,
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
+            min_absolute_fee: 0,
+            base_weight: 0,
+            change_weight: 0,
+            change_cost: 20,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToFee,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_annealing_improves_on_greedy_start() {
+        // Largest-first picks 10000 + 6000 and burns 5000 of excess; 6000 + 5000 is exact.
+        let inputs = vec![
+            OutputGroup {
+                value: 10_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 6_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 5_000,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_annealing(&inputs, &setup_options(11_000)).unwrap();
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![1, 2]);
+        assert_eq!(result.waste.0, 0);
+    }
+
+    #[test]
+    fn test_annealing_optimizes_selection_metric() {
+        /// Consolidation objective: the more groups spent, the better.
+        #[derive(Debug)]
+        struct MostInputs;
+
+        impl SelectionMetric for MostInputs {
+            fn score(
+                &self,
+                output: &SelectionOutput,
+                _: &[OutputGroup],
+                _: &CoinSelectionOpt,
+            ) -> i64 {
+                -(output.selected_inputs.len() as i64)
+            }
+        }
+
+        let inputs = vec![
+            OutputGroup {
+                value: 10_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 6_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 5_000,
+                ..Default::default()
+            },
+        ];
+        let options = CoinSelectionOpt {
+            selection_metric: Some(Arc::new(MostInputs)),
+            ..setup_options(11_000)
+        };
+        let result = select_coin_annealing(&inputs, &options).unwrap();
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![0, 1, 2]);
+    }
+
+    // Deadlines need the clock of the `std` feature.
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_annealing_stops_at_max_duration() {
+        // With no time at all the greedy starting point is returned unchanged.
+        let inputs = vec![
+            OutputGroup {
+                value: 10_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 6_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 5_000,
+                ..Default::default()
+            },
+        ];
+        let mut options = setup_options(11_000);
+        options.max_duration = Some(std::time::Duration::ZERO);
+        let result = select_coin_annealing(&inputs, &options).unwrap();
+        assert_eq!(result.selected_inputs, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_annealing_is_reproducible() {
+        let inputs: Vec<_> = (1..=40u64)
+            .map(|i| OutputGroup {
+                value: i * 1_337 % 9_001 + 600,
+                ..Default::default()
+            })
+            .collect();
+        let options = setup_options(50_000);
+        let params = AnnealingParams {
+            seed: 42,
+            ..Default::default()
+        };
+        let first = select_coin_annealing_with(&inputs, &options, &params).unwrap();
+        let second = select_coin_annealing_with(&inputs, &options, &params).unwrap();
+        assert_eq!(first.selected_inputs, second.selected_inputs);
+        assert_eq!(first.waste, second.waste);
+    }
+
+    #[test]
+    fn test_annealing_uses_options_seed() {
+        let inputs: Vec<_> = (1..=40u64)
+            .map(|i| OutputGroup {
+                value: i * 1_337 % 9_001 + 600,
+                ..Default::default()
+            })
+            .collect();
+        let mut options = setup_options(50_000);
+        options.seed = Some(42);
+        let params = AnnealingParams {
+            seed: 42,
+            ..Default::default()
+        };
+        let seeded = select_coin_annealing(&inputs, &options).unwrap();
+        let explicit = select_coin_annealing_with(&inputs, &options, &params).unwrap();
+        assert_eq!(seeded.selected_inputs, explicit.selected_inputs);
+        assert_eq!(seeded.waste, explicit.waste);
+    }
+
+    #[test]
+    fn test_annealing_insufficient_funds() {
+        let inputs = vec![OutputGroup {
+            value: 1_000,
+            ..Default::default()
+        }];
+        let result = select_coin_annealing(&inputs, &setup_options(10_000));
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
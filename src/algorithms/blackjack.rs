@@ -0,0 +1,186 @@
+use alloc::vec::Vec;
+
+use crate::{
+    types::{CoinSelectionOpt, Constraint, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, exceeds_max_input_count, funding_target, insufficient_funds,
+        select_with_preselected, selection_output, SelectionContext, TOO_MANY_INPUTS,
+    },
+};
+
+/// Performs coin selection using the "blackjack" accumulative strategy.
+///
+/// Inputs are walked once in descending effective value order, and an input is only taken if the
+/// running total stays within `target + cost_of_change`, i.e. it never "busts". The walk stops as
+/// soon as the total reaches the target. Like BnB this looks for a *changeless* solution, so the
+/// target is not padded by the change output; unlike BnB it never backtracks, which makes it an
+/// O(n log n) alternative for pools where a near-exact match is easy to reach.
+///
+/// Returns [`SelectionError::InsufficientFunds`] when the inputs cannot reach the target at all, and
+/// [`SelectionError::NoSolutionFound`] when the walk ends short of the target, naming
+/// [`Constraint::MaxInputCount`] if [`CoinSelectionOpt::max_input_count`] made it skip a coin.
+pub fn select_coin_blackjack(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, false, select_coin_blackjack);
+    }
+    select_coin_blackjack_with_context(inputs, options, &SelectionContext::new(inputs, options)?)
+}
+
+/// [`select_coin_blackjack`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_blackjack_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    // Largest coins first.
+    let inputs = &context.inputs;
+    let target = funding_target(options, false);
+    let upper_bound = target + options.change_cost;
+
+    if context.total_value() < target {
+        return Err(insufficient_funds_error);
+    }
+
+    let mut accumulated_value: u64 = 0;
+    let mut accumulated_weight: u64 = 0;
+    let mut accumulated_input_count: usize = 0;
+    let mut selected_inputs: Vec<usize> = Vec::new();
+    let mut capped = false;
+    for input in inputs {
+        if accumulated_value + input.value > upper_bound {
+            // Taking this coin would bust; a smaller one may still fit.
+            continue;
+        }
+        if exceeds_max_input_count(options, accumulated_input_count + input.input_count) {
+            // Too many inputs; a group with fewer of them may still fit.
+            capped = true;
+            continue;
+        }
+        accumulated_input_count += input.input_count;
+        accumulated_value += input.value;
+        accumulated_weight += input.weight;
+        selected_inputs.push(input.index);
+        if accumulated_value >= target {
+            break;
+        }
+    }
+
+    if accumulated_value < target && capped {
+        return Err(TOO_MANY_INPUTS);
+    }
+    if accumulated_value < target {
+        return Err(SelectionError::NoSolutionFound {
+            constraint: Constraint::Changeless,
+        });
+    }
+
+    let (fee, waste) = calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
+    // No change output is created, so its fee is not paid.
+    selection_output(options, inputs, selected_inputs, fee, waste, false, None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::blackjack::select_coin_blackjack,
+        types::{
+            CoinSelectionOpt, Constraint, ExcessStrategy, FeeRate, OutputGroup, RankStrategy,
+            SelectionError,
+        },
+    };
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value,
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
+            min_absolute_fee: 0,
+            base_weight: 0,
+            change_weight: 50,
+            change_cost: 20,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_blackjack_skips_busting_coins() {
+        // 40000 busts the 25000 target; 20000 + 5000 lands on it exactly.
+        let inputs = vec![
+            OutputGroup {
+                value: 40_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 20_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 8_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 5_000,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_blackjack(&inputs, &setup_options(25_000)).unwrap();
+        assert_eq!(result.selected_inputs, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_blackjack_accepts_excess_within_tolerance() {
+        let inputs = vec![
+            OutputGroup {
+                value: 10_015,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_000,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_blackjack(&inputs, &setup_options(10_000)).unwrap();
+        assert_eq!(result.selected_inputs, vec![0]);
+    }
+
+    #[test]
+    fn test_blackjack_no_close_match() {
+        let inputs = vec![
+            OutputGroup {
+                value: 30_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 20_000,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_blackjack(&inputs, &setup_options(25_000));
+        assert_eq!(
+            result.unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::Changeless
+            }
+        );
+    }
+
+    #[test]
+    fn test_blackjack_insufficient_funds() {
+        let inputs = vec![OutputGroup {
+            value: 1_000,
+            ..Default::default()
+        }];
+        let result = select_coin_blackjack(&inputs, &setup_options(25_000));
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
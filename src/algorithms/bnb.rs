@@ -1,17 +1,23 @@
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
 use crate::{
     types::{
-        CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput, WasteMetric, TOTAL_TRIES,
+        CoinSelectionOpt, Constraint, FeeRate, OutputGroup, SearchMetrics, SelectionError,
+        SelectionOutput,
     },
     utils::{
-        calculate_fee, calculate_fee_and_waste, insufficient_funds, prepare_output_groups,
-        PreparedOutputGroup,
+        calculate_fee, calculate_fee_and_waste, check_cancelled, configured_long_term_feerate,
+        exceeds_max_input_count, funding_target, insufficient_funds, long_term_feerate, max_tries,
+        select_with_preselected, selection_output, target_feerate, Deadline, SelectionContext,
+        TOO_MANY_INPUTS,
     },
 };
 
 /// Performs coin selection via the Branch and Bound algorithm.
 ///
 /// Update from Bitcoin Core's BNB: Instead of explicitly backtracking and re-testing omission branches, the search tracks the next candidate to explore
-/// and *shifts* directly to it, and it uses a precomputed `lookahead` of the remaining effective
+/// and *shifts* directly to it, and it uses precomputed prefix sums of the remaining effective
 /// value at each depth to prune dead branches early. Successive candidates with identical effective
 /// value to a just-omitted one are skipped, since they would only re-derive an already-seen set.
 ///
@@ -22,188 +28,538 @@ use crate::{
 /// values are used throughout, so an input's spend fee is accounted for exactly once. Among all
 /// solutions in range, the one with the least waste is returned.
 ///
+/// The search is iterative: the current selection doubles as an explicit stack of the branches
+/// taken, so the stack use stays constant however deep the search goes on large pools.
+///
 /// Returns [`SelectionError::InsufficientFunds`] when the inputs cannot reach the target at all, and
-/// [`SelectionError::NoSolutionFound`] when no in-range (changeless) combination exists.
+/// [`SelectionError::NoSolutionFound`] when no in-range (changeless) combination exists, naming
+/// [`Constraint::MaxInputCount`] if branches were cut by [`CoinSelectionOpt::max_input_count`].
 pub fn select_coin_bnb(
     inputs: &[OutputGroup],
     options: &CoinSelectionOpt,
 ) -> Result<SelectionOutput, SelectionError> {
-    let insufficient_funds_error = insufficient_funds(inputs, options);
-    let mut inputs = prepare_output_groups(inputs, options)?;
-    let base_fee =
-        calculate_fee(options.base_weight, options.target_feerate).max(options.min_absolute_fee);
-    let actual_target = options.target_value + base_fee;
-    let cost_of_change = options.change_cost;
-
-    // Sort by descending effective value (largest first exploration).
-    inputs.sort_by(|a, b| b.value.cmp(&a.value));
-
-    // `lookahead[i]` is the total effective value of all candidates *after* index `i` : i.e. the
-    // value still reachable from depth `i`. Used to cut branches that can no longer hit the target.
-    let mut lookahead = vec![0u64; inputs.len()];
-    let mut total_available: u64 = 0;
-    for index in (0..inputs.len()).rev() {
-        lookahead[index] = total_available;
-        total_available += inputs[index].value;
-    }
-
-    if total_available < actual_target {
-        return Err(insufficient_funds_error);
-    }
-
-    // At high feerates, including more inputs only increases waste, which enables an extra pruning
-    // branch. Mirrors Core's `is_feerate_high`.
-    let is_feerate_high = options
-        .long_term_feerate
-        .is_some_and(|long_term_feerate| options.target_feerate > long_term_feerate);
-
-    let mut current_selection: Vec<usize> = Vec::with_capacity(inputs.len());
-    let mut current_amount: u64 = 0;
-    let mut current_waste: i64 = 0;
-
-    let mut best_selection: Option<Vec<usize>> = None;
-    let mut best_waste: i64 = i64::MAX;
-
-    let mut next_utxo: usize = 0;
-    let mut tries = TOTAL_TRIES;
-    let mut is_done = false;
-
-    while !is_done {
-        // EXPLORE: add `next_utxo` to the current selection.
-        let candidate = &inputs[next_utxo];
-        current_amount += candidate.value;
-        current_waste += calculate_fee(candidate.weight, options.target_feerate) as i64
-            - calculate_fee(
-                candidate.weight,
-                options.long_term_feerate.unwrap_or(options.target_feerate),
-            ) as i64;
-        current_selection.push(next_utxo);
-        next_utxo += 1;
-
-        tries -= 1;
-        if tries == 0 {
-            break;
-        }
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, false, select_coin_bnb);
+    }
+    select_coin_bnb_with_context(inputs, options, &SelectionContext::new(inputs, options)?)
+}
+
+/// [`select_coin_bnb`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_bnb_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let search = BnbSearch::new(options, context);
+    // The context holds the inputs by descending effective value (largest first exploration).
+    if context.total_value() < search.actual_target {
+        return Err(insufficient_funds(inputs, options));
+    }
+    let mut tries = max_tries(options);
+    let subtree = search.run(&mut Vec::new(), 0, 0, &mut tries, &Sequential)?;
+    search.finish(subtree)
+}
+
+/// Performs coin selection via the Branch and Bound algorithm, exploring the search tree on all
+/// available cores.
+///
+/// The top of the tree is split into subtrees, one per combination of the first few inputs taken,
+/// which worker threads pick up one after the other. The workers share the waste of the best
+/// selection found so far, so that each prunes with the others' results, and draw their tries from
+/// the common [`CoinSelectionOpt::max_tries`] budget. The subtrees cover exactly the tree
+/// [`select_coin_bnb`] walks, so a search that completes within its budget returns the same
+/// selection, only sooner on wide pools. When the budget runs out, which selection is returned
+/// depends on how the threads were scheduled.
+#[cfg(feature = "parallel")]
+pub fn select_coin_bnb_parallel(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    let threads = std::thread::available_parallelism().map_or(1, usize::from);
+    select_coin_bnb_parallel_with_threads(inputs, options, threads)
+}
+
+#[cfg(feature = "parallel")]
+fn select_coin_bnb_parallel_with_threads(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    threads: usize,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, false, |inputs, options| {
+            select_coin_bnb_parallel_with_threads(inputs, options, threads)
+        });
+    }
+    let context = SelectionContext::new(inputs, options)?;
+    let search = BnbSearch::new(options, &context);
+    if context.total_value() < search.actual_target {
+        return Err(insufficient_funds(inputs, options));
+    }
+    search.finish(parallel::search(&search, threads)?)
+}
+
+/// The fixed parameters of one Branch and Bound search.
+struct BnbSearch<'a> {
+    options: &'a CoinSelectionOpt,
+    /// The candidates, by descending effective value.
+    context: &'a SelectionContext,
+    actual_target: u64,
+    cost_of_change: u64,
+    /// At high feerates, including more inputs only increases waste, which enables an extra
+    /// pruning branch. Mirrors Core's `is_feerate_high`.
+    is_feerate_high: bool,
+    long_term_feerate: FeeRate,
+    deadline: Deadline,
+}
+
+/// What the search does after adding a candidate.
+enum Step {
+    /// Even adding every remaining candidate cannot reach the target.
+    Cut,
+    /// No deeper selection can help; `capped` if that is down to the input count.
+    Shift { capped: bool },
+    /// In range: a changeless solution with this waste. Nothing deeper can improve on it.
+    Solution(i64),
+    /// Keep exploring deeper.
+    Explore,
+}
+
+/// Running totals of the current selection.
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    amount: u64,
+    waste: i64,
+    input_count: usize,
+}
+
+/// Where a subtree search draws more tries from, and the best waste other subtrees have found.
+trait Shared {
+    /// Hands out more tries once the local ones are spent, or 0 when the budget is exhausted.
+    fn refill(&self) -> u32;
+    /// Waste of the best selection any subtree has found so far.
+    fn best_waste(&self) -> i64;
+    /// Reports the waste of a selection this subtree found.
+    fn publish(&self, waste: i64);
+}
 
-        // EVALUATE: decide whether to keep exploring, SHIFT to the omission branch, or CUT.
-        let last = *current_selection.last().unwrap();
-        let mut should_shift = false;
-        let mut should_cut = false;
-
-        if current_amount + lookahead[last] < actual_target {
-            // Even adding every remaining candidate cannot reach the target: CUT this subtree.
-            should_cut = true;
-        } else if current_amount > actual_target + cost_of_change {
-            // Overshot the window: no deeper selection helps, SHIFT to the next branch.
-            should_shift = true;
-        } else if is_feerate_high && current_waste > best_waste {
-            // Already wasteful and adding inputs only makes it worse: SHIFT.
-            should_shift = true;
-        } else if current_amount >= actual_target {
-            // In range: a valid changeless solution. Record it if it improves on the best.
-            should_shift = true;
-            let excess = current_amount - actual_target;
-            let waste = current_waste + excess as i64;
-            if waste <= best_waste {
-                best_waste = waste;
-                best_selection = Some(current_selection.clone());
+/// A single search walking the whole tree on its own.
+struct Sequential;
+
+impl Shared for Sequential {
+    fn refill(&self) -> u32 {
+        0
+    }
+
+    fn best_waste(&self) -> i64 {
+        i64::MAX
+    }
+
+    fn publish(&self, _waste: i64) {}
+}
+
+/// The outcome of searching (part of) the tree.
+#[derive(Debug, Default)]
+struct Subtree {
+    /// Waste and candidate positions of the best selection found.
+    best: Option<(i64, Vec<usize>)>,
+    tries: u32,
+    pruned: u32,
+    exhausted: bool,
+    capped: bool,
+}
+
+impl Subtree {
+    /// Records `selection` if it beats the best one: less waste first, then the one found later
+    /// in depth-first order, i.e. the lexicographically larger positions. An improvement is copied
+    /// into the allocation of the previous best, so only the first solution allocates.
+    fn record(&mut self, waste: i64, selection: &[usize]) {
+        match &mut self.best {
+            Some((best_waste, best))
+                if (waste, Reverse(selection)) < (*best_waste, Reverse(&best[..])) =>
+            {
+                *best_waste = waste;
+                best.clear();
+                best.extend_from_slice(selection);
             }
+            Some(_) => {}
+            None => self.best = Some((waste, selection.to_vec())),
         }
-        // Otherwise: keep exploring deeper (the loop adds `next_utxo` next iteration).
-
-        // A CUT is a SHIFT preceded by also dropping the last candidate (it leads nowhere).
-        if should_cut {
-            deselect_last(
-                &inputs,
-                options,
-                &mut current_selection,
-                &mut current_amount,
-                &mut current_waste,
-            )?;
-            should_shift = true;
+    }
+
+    /// Folds the outcome of another part of the tree into this one.
+    #[cfg(feature = "parallel")]
+    fn merge(&mut self, other: Subtree) {
+        if let Some((waste, selection)) = &other.best {
+            self.record(*waste, selection);
+        }
+        self.tries += other.tries;
+        self.pruned += other.pruned;
+        self.exhausted |= other.exhausted;
+        self.capped |= other.capped;
+    }
+}
+
+impl<'a> BnbSearch<'a> {
+    fn new(options: &'a CoinSelectionOpt, context: &'a SelectionContext) -> Self {
+        BnbSearch {
+            options,
+            context,
+            actual_target: funding_target(options, false),
+            cost_of_change: options.change_cost,
+            is_feerate_high: configured_long_term_feerate(options)
+                .is_some_and(|long_term_feerate| target_feerate(options) > long_term_feerate),
+            long_term_feerate: long_term_feerate(options),
+            deadline: Deadline::start(options),
         }
+    }
+
+    /// Waste the candidate at `position` adds by being spent now rather than later.
+    fn waste_of(&self, position: usize) -> i64 {
+        let weight = self.context.inputs[position].weight;
+        calculate_fee(weight, target_feerate(self.options)) as i64
+            - calculate_fee(weight, self.long_term_feerate) as i64
+    }
+
+    fn add(&self, totals: &mut Totals, position: usize) {
+        let candidate = &self.context.inputs[position];
+        totals.amount += candidate.value;
+        totals.input_count += candidate.input_count;
+        totals.waste += self.waste_of(position);
+    }
+
+    fn remove(&self, totals: &mut Totals, position: usize) {
+        let candidate = &self.context.inputs[position];
+        totals.amount -= candidate.value;
+        totals.input_count -= candidate.input_count;
+        totals.waste -= self.waste_of(position);
+    }
 
-        while should_shift {
-            // No selected candidate left to omit: the whole search space is exhausted.
-            if current_selection.is_empty() {
-                is_done = true;
+    /// EVALUATE: decides whether to keep exploring past `last`, the candidate just added, SHIFT to
+    /// the omission branch, or CUT. `best_waste` bounds the high feerate pruning.
+    fn evaluate(&self, totals: &Totals, last: usize, best_waste: i64) -> Step {
+        // The value still reachable after `last` cuts branches that can no longer hit the target.
+        if totals.amount + self.context.remaining_value(last + 1) < self.actual_target {
+            Step::Cut
+        } else if exceeds_max_input_count(self.options, totals.input_count) {
+            // Too many inputs already, and going deeper only adds more.
+            Step::Shift { capped: true }
+        } else if totals.amount > self.actual_target + self.cost_of_change {
+            // Overshot the window: no deeper selection helps.
+            Step::Shift { capped: false }
+        } else if self.is_feerate_high && totals.waste > best_waste {
+            // Already wasteful and adding inputs only makes it worse.
+            Step::Shift { capped: false }
+        } else if totals.amount >= self.actual_target {
+            let excess = totals.amount - self.actual_target;
+            Step::Solution(totals.waste + excess as i64)
+        } else {
+            Step::Explore
+        }
+    }
+
+    /// Searches the subtree below `current_selection`: starting by adding `next_utxo` to it, and
+    /// never backtracking past its first `floor` candidates. The whole tree is
+    /// `run(&mut vec![], 0, 0, ..)`. The selection doubles as the stack of the search, which the
+    /// caller can reuse from one subtree to the next.
+    fn run(
+        &self,
+        current_selection: &mut Vec<usize>,
+        mut next_utxo: usize,
+        floor: usize,
+        tries: &mut u32,
+        shared: &impl Shared,
+    ) -> Result<Subtree, SelectionError> {
+        let inputs = &self.context.inputs;
+        let mut totals = Totals::default();
+        for &position in current_selection.iter() {
+            self.add(&mut totals, position);
+        }
+        let mut subtree = Subtree::default();
+        let mut is_done = false;
+
+        while !is_done {
+            check_cancelled(self.options)?;
+            // EXPLORE: add `next_utxo` to the current selection.
+            self.add(&mut totals, next_utxo);
+            current_selection.push(next_utxo);
+            next_utxo += 1;
+
+            subtree.tries += 1;
+            *tries -= 1;
+            if *tries == 0 {
+                *tries = shared.refill();
+            }
+            if *tries == 0 || self.deadline.has_passed_at(*tries) {
+                subtree.exhausted = true;
                 break;
             }
-            // Move to the omission branch: explore the candidate after the last selected one, and
-            // drop that last selected candidate from the running totals.
-            next_utxo = current_selection.last().unwrap() + 1;
-            deselect_last(
-                &inputs,
-                options,
-                &mut current_selection,
-                &mut current_amount,
-                &mut current_waste,
-            )?;
-            should_shift = false;
-
-            // Skip candidates identical in effective value to the just-omitted one (clones): trying
-            // them would only re-derive a set whose waste we have already considered. If we run off
-            // the end of the inputs, there is no fresh branch here, so SHIFT again (backtrack further).
-            loop {
-                if next_utxo >= inputs.len() {
-                    should_shift = true;
+
+            let last = *current_selection.last().unwrap();
+            let best_waste = subtree
+                .best
+                .as_ref()
+                .map_or(i64::MAX, |(waste, _)| *waste)
+                .min(shared.best_waste());
+            let mut should_shift = true;
+            match self.evaluate(&totals, last, best_waste) {
+                // A CUT is a SHIFT preceded by also dropping the last candidate (it leads nowhere).
+                Step::Cut => {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        depth = current_selection.len(),
+                        amount = totals.amount,
+                        "cut: target out of reach"
+                    );
+                    subtree.pruned += 1;
+                    current_selection.pop();
+                    self.remove(&mut totals, last);
+                }
+                Step::Shift { capped } => subtree.capped |= capped,
+                Step::Solution(waste) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(depth = current_selection.len(), waste, "solution");
+                    subtree.record(waste, current_selection);
+                    shared.publish(waste);
+                }
+                // Keep exploring deeper (the loop adds `next_utxo` next iteration).
+                Step::Explore => should_shift = false,
+            }
+
+            while should_shift {
+                // No selected candidate left to omit: the whole subtree is exhausted.
+                if current_selection.len() <= floor {
+                    is_done = true;
                     break;
                 }
-                if inputs[next_utxo - 1].value == inputs[next_utxo].value {
-                    next_utxo += 1;
-                    continue;
+                // Move to the omission branch: explore the candidate after the last selected one,
+                // and drop that last selected candidate from the running totals.
+                let omitted = current_selection.pop().unwrap();
+                self.remove(&mut totals, omitted);
+                next_utxo = omitted + 1;
+                should_shift = false;
+
+                // Skip candidates identical in effective value to the just-omitted one (clones):
+                // trying them would only re-derive a set whose waste we have already considered. If
+                // we run off the end of the inputs, there is no fresh branch here, so SHIFT again
+                // (backtrack further).
+                loop {
+                    if next_utxo >= inputs.len() {
+                        should_shift = true;
+                        break;
+                    }
+                    if inputs[next_utxo - 1].value == inputs[next_utxo].value {
+                        next_utxo += 1;
+                        continue;
+                    }
+                    break;
                 }
-                break;
             }
         }
+        Ok(subtree)
     }
 
-    let selected_pool_indices = match best_selection {
-        Some(s) => s,
-        None => return Err(SelectionError::NoSolutionFound),
-    };
+    /// Turns the outcome of the search into the selection of the best solution, or the reason
+    /// there is none.
+    fn finish(&self, subtree: Subtree) -> Result<SelectionOutput, SelectionError> {
+        let options = self.options;
+        let inputs = &self.context.inputs;
+        let selected_pool_indices = match subtree.best {
+            Some((_, selection)) => selection,
+            None if subtree.exhausted => {
+                return Err(SelectionError::NoSolutionFound {
+                    constraint: Constraint::SearchLimit,
+                })
+            }
+            None if subtree.capped => return Err(TOO_MANY_INPUTS),
+            None => {
+                return Err(SelectionError::NoSolutionFound {
+                    constraint: Constraint::Changeless,
+                })
+            }
+        };
 
-    let selected_inputs: Vec<usize> = selected_pool_indices
-        .iter()
-        .map(|&i| inputs[i].index)
-        .collect();
-
-    // Recompute the reported waste from the concrete selection using the shared waste function so
-    // the metric is comparable with the other algorithms.
-    let accumulated_value: u64 = selected_pool_indices.iter().map(|&i| inputs[i].value).sum();
-    let accumulated_weight: u64 = selected_pool_indices
-        .iter()
-        .map(|&i| inputs[i].weight)
-        .sum();
-    let (fee, waste) = calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
-    let fee_bnb = fee.saturating_sub(calculate_fee(options.change_weight, options.target_feerate));
-
-    Ok(SelectionOutput {
-        selected_inputs,
-        waste: WasteMetric(waste),
-        fee: fee_bnb,
-    })
+        let selected_inputs: Vec<usize> = selected_pool_indices
+            .iter()
+            .map(|&i| inputs[i].index)
+            .collect();
+
+        // Recompute the reported waste from the concrete selection using the shared waste function
+        // so the metric is comparable with the other algorithms.
+        let accumulated_value: u64 = selected_pool_indices.iter().map(|&i| inputs[i].value).sum();
+        let accumulated_weight: u64 = selected_pool_indices
+            .iter()
+            .map(|&i| inputs[i].weight)
+            .sum();
+        let (fee, waste) = calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
+        // BnB only returns changeless solutions, so the change output's fee is not paid.
+        selection_output(
+            options,
+            inputs,
+            selected_inputs,
+            fee,
+            waste,
+            false,
+            Some(SearchMetrics {
+                tries: subtree.tries,
+                pruned: subtree.pruned,
+            }),
+        )
+    }
 }
 
-/// Removes the most recently selected candidate, undoing its contribution to the running totals.
-fn deselect_last(
-    inputs: &[PreparedOutputGroup],
-    options: &CoinSelectionOpt,
-    current_selection: &mut Vec<usize>,
-    current_amount: &mut u64,
-    current_waste: &mut i64,
-) -> Result<(), SelectionError> {
-    let last = current_selection
-        .pop()
-        .expect("deselect_last on empty selection");
-    let candidate = &inputs[last];
-    *current_amount -= candidate.value;
-    *current_waste -= calculate_fee(candidate.weight, options.target_feerate) as i64
-        - calculate_fee(
-            candidate.weight,
-            options.long_term_feerate.unwrap_or(options.target_feerate),
-        ) as i64;
-    Ok(())
+#[cfg(feature = "parallel")]
+mod parallel {
+    use alloc::vec::Vec;
+    use std::{
+        sync::atomic::{AtomicI64, AtomicU32, AtomicUsize, Ordering},
+        thread,
+    };
+
+    use super::{BnbSearch, Shared, Step, Subtree, Totals};
+    use crate::{types::SelectionError, utils::max_tries};
+
+    /// Tries a worker takes from the shared budget at a time.
+    const TRIES_PER_REFILL: u32 = 1 << 12;
+
+    /// The search tree is split until there are this many subtrees per thread, so that threads
+    /// finishing early find more work.
+    const SUBTREES_PER_THREAD: usize = 8;
+
+    /// How deep the split goes at most, bounding the subtrees enumerated up front.
+    const MAX_SPLIT_DEPTH: usize = 12;
+
+    struct Pool {
+        tries: AtomicU32,
+        best_waste: AtomicI64,
+    }
+
+    impl Shared for Pool {
+        fn refill(&self) -> u32 {
+            self.tries
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |tries| {
+                    Some(tries - tries.min(TRIES_PER_REFILL))
+                })
+                .map_or(0, |tries| tries.min(TRIES_PER_REFILL))
+        }
+
+        fn best_waste(&self) -> i64 {
+            self.best_waste.load(Ordering::Relaxed)
+        }
+
+        fn publish(&self, waste: i64) {
+            self.best_waste.fetch_min(waste, Ordering::Relaxed);
+        }
+    }
+
+    /// Searches the whole tree of `search` on `threads` threads.
+    pub(super) fn search(search: &BnbSearch, threads: usize) -> Result<Subtree, SelectionError> {
+        let threads = threads.max(1);
+        let mut outcome = Subtree::default();
+        // The selections of every subtree, `depth` positions each, back to back in one arena.
+        let mut subtrees = Vec::new();
+        let mut depth = 0;
+        while depth < MAX_SPLIT_DEPTH {
+            depth += 1;
+            outcome = Subtree::default();
+            subtrees.clear();
+            split(
+                search,
+                &mut Vec::new(),
+                Totals::default(),
+                depth,
+                &mut subtrees,
+                &mut outcome,
+            );
+            // Stop once there is enough work to go around, or nothing is left below this depth.
+            if subtrees.len() / depth >= threads * SUBTREES_PER_THREAD
+                || subtrees.is_empty()
+                || outcome.tries >= max_tries(search.options)
+            {
+                break;
+            }
+        }
+
+        let pool = Pool {
+            tries: AtomicU32::new(max_tries(search.options).saturating_sub(outcome.tries)),
+            best_waste: AtomicI64::new(outcome.best.as_ref().map_or(i64::MAX, |(waste, _)| *waste)),
+        };
+        let next = AtomicUsize::new(0);
+        let results: Vec<Result<Subtree, SelectionError>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut found = Subtree::default();
+                        let mut tries = pool.refill();
+                        // One search stack per worker, reused for every subtree it takes.
+                        let mut stack = Vec::with_capacity(search.context.inputs.len());
+                        while let Some(selection) = subtrees
+                            .chunks_exact(depth)
+                            .nth(next.fetch_add(1, Ordering::Relaxed))
+                        {
+                            if tries == 0 {
+                                found.exhausted = true;
+                                break;
+                            }
+                            let (&last, fixed) = selection.split_last().unwrap();
+                            stack.clear();
+                            stack.extend_from_slice(fixed);
+                            found.merge(search.run(&mut stack, last, depth, &mut tries, &pool)?);
+                            if found.exhausted {
+                                break;
+                            }
+                        }
+                        Ok(found)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("BnB worker panicked"))
+                .collect()
+        });
+        for result in results {
+            outcome.merge(result?);
+        }
+        Ok(outcome)
+    }
+
+    /// Walks the tree down to `depth` candidates taken, with the same clone skipping and pruning
+    /// as [`BnbSearch::run`]. The selections still worth exploring at that depth are appended to
+    /// the `subtrees` arena; what is decided above it is recorded in `outcome`.
+    fn split(
+        search: &BnbSearch,
+        selection: &mut Vec<usize>,
+        totals: Totals,
+        depth: usize,
+        subtrees: &mut Vec<usize>,
+        outcome: &mut Subtree,
+    ) {
+        let inputs = &search.context.inputs;
+        let first = selection.last().map_or(0, |last| last + 1);
+        for position in first..inputs.len() {
+            // After omitting the previous candidate, its clones are skipped.
+            if position > first && inputs[position - 1].value == inputs[position].value {
+                continue;
+            }
+            let mut totals = totals;
+            search.add(&mut totals, position);
+            selection.push(position);
+            outcome.tries += 1;
+            match search.evaluate(&totals, position, i64::MAX) {
+                Step::Cut => {
+                    // Smaller candidates cannot reach the target either.
+                    outcome.pruned += 1;
+                    selection.pop();
+                    break;
+                }
+                Step::Shift { capped } => outcome.capped |= capped,
+                Step::Solution(waste) => outcome.record(waste, selection),
+                Step::Explore if selection.len() == depth => subtrees.extend_from_slice(selection),
+                Step::Explore => split(search, selection, totals, depth, subtrees, outcome),
+            }
+            selection.pop();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -211,7 +567,8 @@ mod test {
     use crate::{
         algorithms::bnb::select_coin_bnb,
         types::{
-            basic_output_group, CoinSelectionOpt, ExcessStrategy, OutputGroup, SelectionError,
+            CoinSelectionOpt, Constraint, ExcessStrategy, FeeRate, OutputGroup, RankStrategy,
+            SelectionError, TOTAL_TRIES,
         },
     };
 
@@ -220,24 +577,47 @@ mod test {
     fn setup_output_groups() -> Vec<OutputGroup> {
         [80_000u64, 40_000, 20_000, 10_000, 5_000]
             .into_iter()
-            .map(|value| basic_output_group(value, 0))
+            .map(|value| OutputGroup {
+                value,
+                ..Default::default()
+            })
             .collect()
     }
 
     fn setup_options(target_value: u64) -> CoinSelectionOpt {
         CoinSelectionOpt {
             target_value,
-            target_feerate: 1.0,
-            long_term_feerate: Some(1.0),
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
             min_absolute_fee: 0,
             base_weight: 0,
             change_weight: 50,
             change_cost: 20,
             min_change_value: 500,
             excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
         }
     }
 
+    #[test]
+    fn test_bnb_changeless_fee_respects_min_absolute_fee() {
+        // The 50 sat change output is left out, but the fee may not drop below the minimum.
+        let options = CoinSelectionOpt {
+            min_absolute_fee: 100,
+            ..setup_options(65_000)
+        };
+        let result = select_coin_bnb(
+            &[OutputGroup {
+                value: 65_100,
+                ..Default::default()
+            }],
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result.fee, 100);
+    }
+
     #[test]
     fn test_bnb_finds_exact_changeless_solution() {
         // Target 65000 is uniquely hit by 40000 + 20000 + 5000 (indices 1, 2, 4) among these coins.
@@ -245,6 +625,9 @@ mod test {
         let options = setup_options(65_000);
         let result = select_coin_bnb(&inputs, &options).expect("a solution should exist");
 
+        let metrics = result.metrics.expect("BnB reports search metrics");
+        assert!(metrics.tries > 0 && metrics.tries <= TOTAL_TRIES);
+
         let mut selected = result.selected_inputs;
         selected.sort();
         assert_eq!(selected, vec![1, 2, 4]);
@@ -258,12 +641,63 @@ mod test {
         let options = setup_options(63_000);
         let result = select_coin_bnb(&inputs, &options);
         assert!(
-            matches!(result, Err(SelectionError::NoSolutionFound)),
+            matches!(
+                result,
+                Err(SelectionError::NoSolutionFound {
+                    constraint: Constraint::Changeless
+                })
+            ),
             "expected NoSolutionFound, got {:?}",
             result
         );
     }
 
+    #[test]
+    fn test_bnb_respects_max_tries() {
+        let inputs = setup_output_groups();
+        let options = CoinSelectionOpt {
+            max_tries: Some(2),
+            ..setup_options(65_000)
+        };
+        assert_eq!(
+            select_coin_bnb(&inputs, &options).unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::SearchLimit
+            }
+        );
+
+        let options = CoinSelectionOpt {
+            max_tries: Some(1_000),
+            ..setup_options(65_000)
+        };
+        let result = select_coin_bnb(&inputs, &options).unwrap();
+        assert!(result.metrics.unwrap().tries <= 1_000);
+    }
+
+    #[test]
+    fn test_bnb_respects_max_input_count() {
+        // 65000 needs three coins; with at most two it has no changeless match left.
+        let inputs = setup_output_groups();
+        let mut options = setup_options(65_000);
+        options.max_input_count = Some(2);
+        let result = select_coin_bnb(&inputs, &options);
+        assert!(
+            matches!(
+                result,
+                Err(SelectionError::NoSolutionFound {
+                    constraint: Constraint::MaxInputCount
+                })
+            ),
+            "expected MaxInputCount, got {:?}",
+            result
+        );
+
+        options.target_value = 60_000;
+        let mut selected = select_coin_bnb(&inputs, &options).unwrap().selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![1, 2]);
+    }
+
     #[test]
     fn test_bnb_insufficient_funds() {
         let inputs = setup_output_groups();
@@ -291,7 +725,10 @@ mod test {
     fn test_bnb_handles_clones() {
         let inputs: Vec<OutputGroup> = [10_000u64, 10_000, 10_000, 10_000, 7_000]
             .into_iter()
-            .map(|value| basic_output_group(value, 0))
+            .map(|value| OutputGroup {
+                value,
+                ..Default::default()
+            })
             .collect();
         let options = setup_options(30_000); // 10000 * 3
         let result = select_coin_bnb(&inputs, &options).expect("a solution should exist");
@@ -304,6 +741,29 @@ mod test {
         assert_eq!(value, 30_000);
     }
 
+    #[test]
+    fn test_bnb_deep_search_runs_on_small_stack() {
+        // Reaching the target takes all but the smallest input, so the search goes 20_000 levels
+        // deep; a recursive search would overflow the 256 KiB stack.
+        let inputs: Vec<OutputGroup> = (0..20_000u64)
+            .map(|value| OutputGroup {
+                value: 1_000 + value,
+                ..Default::default()
+            })
+            .collect();
+        let total: u64 = inputs.iter().map(|input| input.value).sum();
+        let options = setup_options(total - 1_000);
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || select_coin_bnb(&inputs, &options))
+            .unwrap()
+            .join()
+            .expect("BnB must not overflow the stack");
+        let result = result.unwrap();
+        assert_eq!(result.selected_inputs.len(), 19_999);
+        assert!(!result.selected_inputs.contains(&0));
+    }
+
     /// Brute-force cross-check: for many small input sets, BnB must return a selection whose summed
     /// effective value is inside `[target, target + cost_of_change]` with the minimum BnB-internal
     /// waste over *all* such subsets, and must report NoSolutionFound exactly when none exist.
@@ -321,13 +781,18 @@ mod test {
         for values in value_sets {
             let inputs: Vec<OutputGroup> = values
                 .iter()
-                .map(|&value| basic_output_group(value, 0))
+                .map(|&value| OutputGroup {
+                    value,
+                    ..Default::default()
+                })
                 .collect();
 
             for target in 1u64..=120 {
                 let mut options = setup_options(target);
-                options.change_cost = 5; // cost_of_change window width
-                options.min_change_value = 0;
+                // Width of the cost_of_change window.
+                options.change_cost = 5;
+                // Low enough that no input is filtered out as uneconomic.
+                options.min_change_value = 1;
 
                 // Brute force: minimum excess over all subsets summing into the window.
                 let n = inputs.len();
@@ -361,7 +826,7 @@ mod test {
                             "target {target}, values {values:?}: BnB excess {excess} != brute force {brute_best:?}"
                         );
                     }
-                    Err(SelectionError::NoSolutionFound) => {
+                    Err(SelectionError::NoSolutionFound { .. }) => {
                         assert!(
                             brute_best.is_none(),
                             "target {target}, values {values:?}: BnB found nothing but brute force did"
@@ -379,4 +844,60 @@ mod test {
             }
         }
     }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_bnb_parallel_matches_sequential() {
+        use crate::algorithms::bnb::select_coin_bnb_parallel_with_threads;
+
+        // Varied weights make the waste differ between selections of the same value, and clones
+        // exercise the skipping at the split.
+        let inputs: Vec<OutputGroup> = (0..18u64)
+            .map(|i| OutputGroup {
+                value: 1_000 + (i * 7_919) % 4_000 + (i % 3) * 500,
+                weight: 40 + i * 13 % 90,
+                ..Default::default()
+            })
+            .chain([
+                OutputGroup {
+                    value: 2_500,
+                    weight: 60,
+                    ..Default::default()
+                },
+                OutputGroup {
+                    value: 2_500,
+                    weight: 60,
+                    ..Default::default()
+                },
+            ])
+            .collect();
+        for high_feerate in [false, true] {
+            for target in (5_000..40_000).step_by(1_733) {
+                let mut options = setup_options(target);
+                options.change_cost = 150;
+                options.min_change_value = 1;
+                // Both searches must complete for their results to match.
+                options.max_tries = Some(u32::MAX);
+                if high_feerate {
+                    options.long_term_feerate = Some(FeeRate::from_sat_per_wu(0.5));
+                }
+                let sequential = select_coin_bnb(&inputs, &options);
+                for threads in [1, 2, 3, 8] {
+                    let parallel =
+                        select_coin_bnb_parallel_with_threads(&inputs, &options, threads);
+                    match (&sequential, &parallel) {
+                        (Ok(sequential), Ok(parallel)) => {
+                            assert_eq!(sequential.selected_inputs, parallel.selected_inputs);
+                            assert_eq!(sequential.waste, parallel.waste);
+                        }
+                        (sequential, parallel) => assert_eq!(
+                            sequential.as_ref().unwrap_err(),
+                            parallel.as_ref().unwrap_err(),
+                            "target {target}, {threads} threads"
+                        ),
+                    }
+                }
+            }
+        }
+    }
 }
@@ -1,10 +1,11 @@
+use alloc::{vec, vec::Vec};
+
 use crate::{
-    types::{
-        CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput, WasteMetric, TOTAL_TRIES,
-    },
+    types::{CoinSelectionOpt, OutputGroup, SearchMetrics, SelectionError, SelectionOutput},
     utils::{
-        calculate_fee, calculate_fee_and_waste, insufficient_funds, prepare_output_groups,
-        PreparedOutputGroup,
+        calculate_fee_and_waste, check_cancelled, exceeds_max_input_count, funding_target,
+        insufficient_funds, max_tries, select_with_preselected, selection_output, unfunded_error,
+        Deadline, SelectionContext,
     },
 };
 
@@ -17,16 +18,42 @@ struct BestSelection {
 }
 
 impl BestSelection {
-    fn is_better_than(&self, other: &Self) -> bool {
-        self.weight < other.weight
-            || (self.weight == other.weight && self.value < other.value)
-            || (self.weight == other.weight
-                && self.value == other.value
-                && self.input_count < other.input_count)
-            || (self.weight == other.weight
-                && self.value == other.value
-                && self.input_count == other.input_count
-                && self.selected < other.selected)
+    /// Records the selection if it beats `best`: less weight, then less value, fewer inputs and
+    /// lower indices. The search only copies `selected` on an improvement, reusing the allocation
+    /// of the previous best, so leaves that lose cost no allocation.
+    fn record(
+        best: &mut Option<Self>,
+        selected: &[usize],
+        value: u64,
+        weight: u64,
+        input_count: usize,
+    ) {
+        match best {
+            Some(best)
+                if (weight, value, input_count, selected)
+                    < (
+                        best.weight,
+                        best.value,
+                        best.input_count,
+                        &best.selected[..],
+                    ) =>
+            {
+                best.selected.clear();
+                best.selected.extend_from_slice(selected);
+                best.value = value;
+                best.weight = weight;
+                best.input_count = input_count;
+            }
+            Some(_) => {}
+            None => {
+                *best = Some(BestSelection {
+                    selected: selected.to_vec(),
+                    value,
+                    weight,
+                    input_count,
+                })
+            }
+        }
     }
 }
 
@@ -36,164 +63,209 @@ impl BestSelection {
 /// for a selection that covers the target and total fee, then minimizes selected input weight. This
 /// avoids the old least-change behavior where many tiny inputs could be linked together merely to
 /// shave the change amount down by a few sats.
+///
+/// The search is iterative, so its stack use stays constant however many inputs the target takes.
 pub fn select_coin_coingrinder(
     inputs: &[OutputGroup],
     options: &CoinSelectionOpt,
 ) -> Result<SelectionOutput, SelectionError> {
-    let insufficient_funds_error = insufficient_funds(inputs, options);
-    let mut inputs = prepare_output_groups(inputs, options)?;
-
-    inputs.sort_by(|a, b| {
-        b.value
-            .cmp(&a.value)
-            .then_with(|| a.weight.cmp(&b.weight))
-            .then_with(|| a.index.cmp(&b.index))
-    });
-
-    let mut remaining_value = vec![0u64; inputs.len() + 1];
-    for index in (0..inputs.len()).rev() {
-        remaining_value[index] = remaining_value[index + 1] + inputs[index].value;
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, select_coin_coingrinder);
     }
+    select_coin_coingrinder_with_context(inputs, options, &SelectionContext::new(inputs, options)?)
+}
+
+/// [`select_coin_coingrinder`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_coingrinder_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
 
     let mut best = None;
-    let mut selected = Vec::new();
-    let mut tries = TOTAL_TRIES;
-    let base_fee = calculate_fee(
-        options.base_weight + options.change_weight,
-        options.target_feerate,
-    )
-    .max(options.min_absolute_fee);
+    let max_tries = max_tries(options);
+    let mut tries = max_tries;
+    let mut pruned: u32 = 0;
+    let deadline = Deadline::start(options);
+    let target = funding_target(options, true);
 
     search(
-        &inputs,
-        &remaining_value,
-        0,
-        0,
-        0,
-        0,
-        &mut selected,
+        context,
         options,
-        base_fee,
+        target,
+        &deadline,
         &mut best,
         &mut tries,
+        &mut pruned,
     )?;
 
-    let best = best.ok_or(insufficient_funds_error)?;
+    let best = best.ok_or_else(|| unfunded_error(options, insufficient_funds_error))?;
     let (fee, waste) = calculate_fee_and_waste(options, best.value, best.weight)?;
-
-    Ok(SelectionOutput {
-        selected_inputs: best.selected,
-        waste: WasteMetric(waste),
+    selection_output(
+        options,
+        &context.inputs,
+        best.selected,
         fee,
-    })
+        waste,
+        true,
+        Some(SearchMetrics {
+            tries: max_tries - tries,
+            pruned,
+        }),
+    )
 }
 
-#[allow(clippy::too_many_arguments)]
-fn search(
-    inputs: &[PreparedOutputGroup],
-    remaining_value: &[u64],
+/// A branch still to explore: deciding on the candidate at `index`, with the totals of the first
+/// `selected_len` entries of the selection.
+struct Frame {
     index: usize,
     value: u64,
     weight: u64,
     input_count: usize,
-    selected: &mut Vec<usize>,
+    selected_len: usize,
+}
+
+/// Depth-first search over include/omit decisions, trying the inclusion of each candidate first.
+///
+/// The pending omission branches are kept on an explicit stack rather than the call stack, so
+/// pools needing thousands of inputs do not overflow it.
+fn search(
+    context: &SelectionContext,
     options: &CoinSelectionOpt,
-    base_fee: u64,
+    target: u64,
+    deadline: &Deadline,
     best: &mut Option<BestSelection>,
     tries: &mut u32,
+    pruned: &mut u32,
 ) -> Result<(), SelectionError> {
-    if *tries == 0 || index >= inputs.len() {
-        return Ok(());
-    }
-    if value + remaining_value[index] < options.target_value + base_fee {
-        return Ok(());
-    }
-    if best.as_ref().is_some_and(|best| weight > best.weight) {
-        return Ok(());
-    }
+    let inputs = &context.inputs;
+    let mut selected = Vec::new();
+    let mut stack = vec![Frame {
+        index: 0,
+        value: 0,
+        weight: 0,
+        input_count: 0,
+        selected_len: 0,
+    }];
 
-    *tries -= 1;
-
-    let candidate = &inputs[index];
-    let new_value = value + candidate.value;
-    let new_weight = weight + candidate.weight;
-    let new_input_count = input_count + candidate.input_count;
-    selected.push(candidate.index);
-
-    let required_value = options.target_value + base_fee;
-    if new_value >= required_value {
-        let candidate_best = BestSelection {
-            selected: selected.clone(),
-            value: new_value,
-            weight: new_weight,
-            input_count: new_input_count,
-        };
-        if best
-            .as_ref()
-            .is_none_or(|current| candidate_best.is_better_than(current))
+    while let Some(frame) = stack.pop() {
+        if *tries == 0 {
+            break;
+        }
+        let Frame {
+            index,
+            value,
+            weight,
+            input_count,
+            selected_len,
+        } = frame;
+        if index >= inputs.len() {
+            continue;
+        }
+        // Everything past `selected_len` was taken in a branch explored since.
+        selected.truncate(selected_len);
+        if value + context.remaining_value(index) < target
+            || best.as_ref().is_some_and(|best| weight > best.weight)
         {
-            *best = Some(candidate_best);
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                depth = selected.len(),
+                value,
+                weight,
+                "pruned: target out of reach or heavier than the best"
+            );
+            *pruned += 1;
+            continue;
         }
-    } else {
-        search(
-            inputs,
-            remaining_value,
-            index + 1,
-            new_value,
-            new_weight,
-            new_input_count,
-            selected,
-            options,
-            base_fee,
-            best,
-            tries,
-        )?;
-    }
-    selected.pop();
 
-    search(
-        inputs,
-        remaining_value,
-        index + 1,
-        value,
-        weight,
-        input_count,
-        selected,
-        options,
-        base_fee,
-        best,
-        tries,
-    )
+        *tries -= 1;
+        check_cancelled(options)?;
+        if deadline.has_passed_at(*tries) {
+            // Out of time: stop like an exhausted try budget, keeping the best found so far.
+            *tries = 0;
+        }
+
+        // The omission branch, explored once the inclusion branch is done.
+        stack.push(Frame {
+            index: index + 1,
+            ..frame
+        });
+
+        let candidate = &inputs[index];
+        let new_value = value + candidate.value;
+        let new_weight = weight + candidate.weight;
+        let new_input_count = input_count + candidate.input_count;
+        selected.push(candidate.index);
+
+        if exceeds_max_input_count(options, new_input_count) {
+            // Over the input cap, and spending more inputs cannot bring it back under.
+            #[cfg(feature = "tracing")]
+            tracing::trace!(depth = selected.len(), "pruned: over the input cap");
+            *pruned += 1;
+        } else if new_value >= target {
+            BestSelection::record(best, &selected, new_value, new_weight, new_input_count);
+        } else {
+            stack.push(Frame {
+                index: index + 1,
+                value: new_value,
+                weight: new_weight,
+                input_count: new_input_count,
+                selected_len: selected.len(),
+            });
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
         algorithms::coingrinder::select_coin_coingrinder,
-        types::{basic_output_group, CoinSelectionOpt, ExcessStrategy, SelectionError},
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        },
     };
 
     fn setup_options(target_value: u64) -> CoinSelectionOpt {
         CoinSelectionOpt {
             target_value,
-            target_feerate: 1.0,
-            long_term_feerate: Some(1.0),
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
             min_absolute_fee: 0,
             base_weight: 0,
             change_weight: 50,
             change_cost: 20,
             min_change_value: 100,
             excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
         }
     }
 
     #[test]
     fn test_coingrinder_prefers_lower_weight_over_lower_change() {
         let inputs = vec![
-            basic_output_group(10_500, 100),
-            basic_output_group(4_000, 80),
-            basic_output_group(3_500, 80),
-            basic_output_group(3_000, 80),
+            OutputGroup {
+                value: 10_500,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 4_000,
+                weight: 80,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_500,
+                weight: 80,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_000,
+                weight: 80,
+                ..Default::default()
+            },
         ];
 
         let result = select_coin_coingrinder(&inputs, &setup_options(10_000)).unwrap();
@@ -203,9 +275,21 @@ mod test {
     #[test]
     fn test_coingrinder_uses_multiple_inputs_when_needed() {
         let inputs = vec![
-            basic_output_group(6_000, 90),
-            basic_output_group(5_000, 90),
-            basic_output_group(2_000, 50),
+            OutputGroup {
+                value: 6_000,
+                weight: 90,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 5_000,
+                weight: 90,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_000,
+                weight: 50,
+                ..Default::default()
+            },
         ];
 
         let result = select_coin_coingrinder(&inputs, &setup_options(10_000)).unwrap();
@@ -214,9 +298,36 @@ mod test {
         assert_eq!(selected, vec![0, 1]);
     }
 
+    #[test]
+    fn test_coingrinder_deep_search_runs_on_small_stack() {
+        // Reaching the target takes all but one input, so the search goes 20_000 levels deep; a
+        // recursive search would overflow the 256 KiB stack.
+        let inputs: Vec<OutputGroup> = (0..20_000u64)
+            .map(|value| OutputGroup {
+                value: 1_000 + value,
+                ..Default::default()
+            })
+            .collect();
+        let total: u64 = inputs.iter().map(|input| input.value).sum();
+        // With the change fee and minimum change on top, the target leaves out at most one coin.
+        let options = setup_options(total - 2_000);
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || select_coin_coingrinder(&inputs, &options))
+            .unwrap()
+            .join()
+            .expect("CoinGrinder must not overflow the stack");
+        let result = result.unwrap();
+        assert_eq!(result.selected_inputs.len(), 19_999);
+    }
+
     #[test]
     fn test_coingrinder_insufficient_funds() {
-        let inputs = vec![basic_output_group(1_000, 100)];
+        let inputs = vec![OutputGroup {
+            value: 1_000,
+            weight: 100,
+            ..Default::default()
+        }];
 
         let result = select_coin_coingrinder(&inputs, &setup_options(10_000));
         assert!(matches!(
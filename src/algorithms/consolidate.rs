@@ -0,0 +1,217 @@
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::{
+    types::{CoinSelectionOpt, Constraint, FeeRate, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, exceeds_max_input_count, funding_target, insufficient_funds,
+        prepare_output_groups, select_with_preselected, selection_output, target_feerate,
+        PreparedOutputGroup, TOO_MANY_INPUTS,
+    },
+};
+
+/// Parameters controlling when and how much [`select_coin_consolidate`] sweeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConsolidationParams {
+    /// Consolidation only runs while `target_feerate` is strictly below this feerate. Above it,
+    /// sweeping extra inputs costs more now than it is expected to save later.
+    pub max_feerate: FeeRate,
+    /// Upper bound on the summed weight of all selected inputs.
+    pub max_input_weight: u64,
+}
+
+/// Performs a low-feerate consolidation: funds the payment and then sweeps as many small,
+/// economically spendable inputs as fit under the weight budget.
+///
+/// The payment is first covered largest-first so that it consumes as little of the budget as
+/// possible. The remaining budget is then filled smallest-first, which maximizes the number of
+/// UTXOs removed from the pool, up to [`CoinSelectionOpt::max_input_count`]. Only inputs whose
+/// effective value clears [`CoinSelectionOpt::dust_threshold`] are considered spendable, so dust
+/// is never swept.
+///
+/// Returns [`SelectionError::NoSolutionFound`] when `target_feerate` is not below
+/// [`ConsolidationParams::max_feerate`] or when the payment alone does not fit the weight budget
+/// or the input count limit, and [`SelectionError::InsufficientFunds`] when the inputs cannot
+/// cover the target at all.
+pub fn select_coin_consolidate(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &ConsolidationParams,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, |inputs, options| {
+            select_coin_consolidate(inputs, options, params)
+        });
+    }
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    let mut inputs = prepare_output_groups(inputs, options)?;
+    if target_feerate(options) >= params.max_feerate {
+        return Err(SelectionError::NoSolutionFound {
+            constraint: Constraint::MaxFeeRate,
+        });
+    }
+    // Effective values already net out per-input fees, so the target only needs the base fee.
+    let target = funding_target(options, true);
+
+    if inputs.iter().map(|input| input.value).sum::<u64>() < target {
+        return Err(insufficient_funds_error);
+    }
+
+    // Fund the payment largest-first, keeping as much of the weight budget free as possible.
+    inputs.sort_by_key(|input| (Reverse(input.value), input.weight, input.index));
+    let mut accumulated_value: u64 = 0;
+    let mut accumulated_weight: u64 = 0;
+    let mut accumulated_input_count: usize = 0;
+    let mut funded = 0;
+    for input in &inputs {
+        if accumulated_value >= target {
+            break;
+        }
+        accumulated_value += input.value;
+        accumulated_weight += input.weight;
+        accumulated_input_count += input.input_count;
+        funded += 1;
+    }
+    if exceeds_max_input_count(options, accumulated_input_count) {
+        return Err(TOO_MANY_INPUTS);
+    }
+    if accumulated_weight > params.max_input_weight {
+        return Err(SelectionError::NoSolutionFound {
+            constraint: Constraint::MaxWeight,
+        });
+    }
+    let mut selected_inputs: Vec<usize> = inputs[..funded].iter().map(|i| i.index).collect();
+
+    // Sweep the smallest remaining coins into the leftover budget.
+    let mut remaining: Vec<&PreparedOutputGroup> = inputs[funded..].iter().collect();
+    remaining.sort_by_key(|input| (input.value, input.weight, input.index));
+    for input in remaining {
+        if accumulated_weight + input.weight > params.max_input_weight
+            || exceeds_max_input_count(options, accumulated_input_count + input.input_count)
+        {
+            continue;
+        }
+        accumulated_value += input.value;
+        accumulated_weight += input.weight;
+        accumulated_input_count += input.input_count;
+        selected_inputs.push(input.index);
+    }
+
+    let (fee, waste) = calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
+    selection_output(options, &inputs, selected_inputs, fee, waste, true, None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::consolidate::{select_coin_consolidate, ConsolidationParams},
+        types::{
+            CoinSelectionOpt, Constraint, ExcessStrategy, FeeRate, OutputGroup, RankStrategy,
+            SelectionError,
+        },
+    };
+
+    fn setup_output_groups() -> Vec<OutputGroup> {
+        vec![
+            OutputGroup {
+                value: 50_000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1_500,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 600,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 30_000,
+                weight: 100,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value,
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(5.0)),
+            min_absolute_fee: 0,
+            base_weight: 10,
+            change_weight: 50,
+            change_cost: 10,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    fn setup_params(max_input_weight: u64) -> ConsolidationParams {
+        ConsolidationParams {
+            max_feerate: FeeRate::from_sat_per_wu(2.0),
+            max_input_weight,
+        }
+    }
+
+    #[test]
+    fn test_consolidate_sweeps_small_coins() {
+        let inputs = setup_output_groups();
+        let options = setup_options(40_000);
+        let result = select_coin_consolidate(&inputs, &options, &setup_params(10_000)).unwrap();
+        // The 50k coin funds the payment; everything else spendable is swept along with it.
+        // The 600 sat coin is only worth 500 after its fee, which still clears min_change_value.
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_consolidate_respects_weight_budget() {
+        let inputs = setup_output_groups();
+        let options = setup_options(40_000);
+        let result = select_coin_consolidate(&inputs, &options, &setup_params(300)).unwrap();
+        // One slot funds the payment, the two smallest spendable coins fill the rest.
+        assert_eq!(result.selected_inputs, vec![0, 4, 2]);
+    }
+
+    #[test]
+    fn test_consolidate_skipped_above_threshold() {
+        let inputs = setup_output_groups();
+        let mut options = setup_options(40_000);
+        options.target_feerate = FeeRate::from_sat_per_wu(2.0);
+        let result = select_coin_consolidate(&inputs, &options, &setup_params(10_000));
+        assert_eq!(
+            result.unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::MaxFeeRate
+            }
+        );
+    }
+
+    #[test]
+    fn test_consolidate_insufficient_funds() {
+        let inputs = setup_output_groups();
+        let options = setup_options(1_000_000);
+        let result = select_coin_consolidate(&inputs, &options, &setup_params(10_000));
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
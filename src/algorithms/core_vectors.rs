@@ -0,0 +1,310 @@
+//! Bitcoin Core's coin selection test vectors, run through the matching parts of this crate.
+//!
+//! The vectors are transcribed from `bnb_search_test` and `waste_test` in Core's
+//! `src/wallet/test/coinselector_tests.cpp`. Core states its Branch and Bound cases without fees,
+//! so they run here on weightless coins, whose effective values equal their values, and
+//! `change_cost` plays the part of Core's `cost_of_change`. Like Core's `EquivalentResult`, selections are compared as
+//! multisets of values.
+//!
+//! Intentional divergences:
+//!
+//! - Core's knapsack and Single Random Draw vectors have no counterpart, as they depend on the
+//!   draws of Core's own random generator. This crate's Single Random Draw also keeps the best of
+//!   several draws.
+//! - Feerates must be positive here, so the Branch and Bound cases run at 1 sat/kWU, which only
+//!   charges the unused change output a sat. The dust filter is disabled.
+//! - Core's waste cases pass `change_cost = 0` to mean "no change". Here that is
+//!   [`ExcessStrategy::ToFee`], and the per-input fees are set through a weight of 1000 WU at
+//!   feerates equal to the fee in sats.
+
+use crate::{
+    algorithms::bnb::select_coin_bnb,
+    types::{CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError},
+    utils::calculate_waste,
+};
+
+const CENT: u64 = 1_000_000;
+const COIN: u64 = 100_000_000;
+
+/// A `bnb_search_test` case: the values in the pool, the target and cost of change, and the values
+/// of the expected selection, or `None` where Core expects no solution.
+struct BnbVector {
+    name: &'static str,
+    pool: &'static [u64],
+    target: u64,
+    cost_of_change: u64,
+    expected: Option<&'static [u64]>,
+}
+
+const POOL_1_TO_4: &[u64] = &[CENT, 2 * CENT, 3 * CENT, 4 * CENT];
+const POOL_1_TO_5: &[u64] = &[CENT, 2 * CENT, 3 * CENT, 4 * CENT, 5 * CENT];
+
+const BNB_VECTORS: &[BnbVector] = &[
+    BnbVector {
+        name: "select 1 cent",
+        pool: POOL_1_TO_4,
+        target: CENT,
+        cost_of_change: CENT / 2,
+        expected: Some(&[CENT]),
+    },
+    BnbVector {
+        name: "select 2 cent",
+        pool: POOL_1_TO_4,
+        target: 2 * CENT,
+        cost_of_change: CENT / 2,
+        expected: Some(&[2 * CENT]),
+    },
+    BnbVector {
+        name: "select 5 cent",
+        pool: POOL_1_TO_4,
+        target: 5 * CENT,
+        cost_of_change: CENT / 2,
+        expected: Some(&[3 * CENT, 2 * CENT]),
+    },
+    BnbVector {
+        name: "select 11 cent, not possible",
+        pool: POOL_1_TO_4,
+        target: 11 * CENT,
+        cost_of_change: CENT / 2,
+        expected: None,
+    },
+    BnbVector {
+        name: "cost of change greater than the excess",
+        pool: POOL_1_TO_4,
+        target: 9 * CENT / 10,
+        cost_of_change: CENT / 2,
+        expected: Some(&[CENT]),
+    },
+    BnbVector {
+        name: "cost of change less than the excess",
+        pool: POOL_1_TO_4,
+        target: 9 * CENT / 10,
+        cost_of_change: 0,
+        expected: None,
+    },
+    BnbVector {
+        name: "select 10 cent",
+        pool: POOL_1_TO_5,
+        target: 10 * CENT,
+        cost_of_change: CENT / 2,
+        expected: Some(&[4 * CENT, 3 * CENT, 2 * CENT, CENT]),
+    },
+    BnbVector {
+        name: "select 0.25 cent, not possible",
+        pool: POOL_1_TO_5,
+        target: CENT / 4,
+        cost_of_change: CENT / 2,
+        expected: None,
+    },
+];
+
+fn bnb_options(target: u64, cost_of_change: u64) -> CoinSelectionOpt {
+    CoinSelectionOpt {
+        target_value: target,
+        target_feerate: FeeRate::from_sat_per_kwu(1),
+        long_term_feerate: Some(FeeRate::from_sat_per_kwu(1)),
+        min_absolute_fee: 0,
+        base_weight: 0,
+        change_weight: 1,
+        change_cost: cost_of_change,
+        min_change_value: 1,
+        excess_strategy: ExcessStrategy::ToChange,
+        rank_strategy: RankStrategy::MinWaste,
+        dust_threshold: Some(0),
+        ..Default::default()
+    }
+}
+
+fn pool(values: impl IntoIterator<Item = u64>) -> Vec<OutputGroup> {
+    values
+        .into_iter()
+        .map(|value| OutputGroup {
+            value,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Values of the BnB selection from `inputs`, sorted, or its error.
+fn bnb_values(
+    inputs: &[OutputGroup],
+    target: u64,
+    cost_of_change: u64,
+) -> Result<Vec<u64>, SelectionError> {
+    let output = select_coin_bnb(inputs, &bnb_options(target, cost_of_change))?;
+    let mut values: Vec<u64> = output
+        .selected_inputs
+        .iter()
+        .map(|&index| inputs[index].value)
+        .collect();
+    values.sort_unstable();
+    Ok(values)
+}
+
+/// Core's `make_hard_case`: pairs of coins whose only exact solution lies deep in the search tree.
+fn make_hard_case(utxos: u32) -> (Vec<OutputGroup>, u64) {
+    let mut values = Vec::new();
+    let mut target = 0;
+    for i in 0..utxos {
+        target += 1 << (utxos + i);
+        values.push(1 << (utxos + i));
+        values.push((1 << (utxos + i)) + (1 << (utxos - 1 - i)));
+    }
+    (pool(values), target)
+}
+
+#[test]
+fn test_core_bnb_vectors() {
+    for vector in BNB_VECTORS {
+        let result = bnb_values(
+            &pool(vector.pool.iter().copied()),
+            vector.target,
+            vector.cost_of_change,
+        );
+        match vector.expected {
+            Some(expected) => {
+                let mut expected = expected.to_vec();
+                expected.sort_unstable();
+                assert_eq!(result, Ok(expected), "{}", vector.name);
+            }
+            None => assert!(result.is_err(), "{}: {result:?}", vector.name),
+        }
+    }
+}
+
+#[test]
+fn test_core_bnb_iteration_exhaustion() {
+    // 17 pairs exhaust the 100,000 tries before the solution; 14 pairs do not.
+    let (inputs, target) = make_hard_case(17);
+    assert!(bnb_values(&inputs, target, 1).is_err());
+    let (inputs, target) = make_hard_case(14);
+    assert!(bnb_values(&inputs, target, 1).is_ok());
+}
+
+#[test]
+fn test_core_bnb_same_value_bailout() {
+    // Skipping clones of an omitted coin keeps 50,000 equal 5 cent coins from exhausting the search.
+    let values = [7 * CENT, 7 * CENT, 7 * CENT, 7 * CENT, 2 * CENT]
+        .into_iter()
+        .chain(core::iter::repeat_n(5 * CENT, 50_000));
+    let result = bnb_values(&pool(values), 30 * CENT, 5_000).unwrap();
+    // Like Core, only the total is checked: without fees, 4 * 7 + 2 and 6 * 5 cents waste as
+    // little, and the later solution wins.
+    assert_eq!(result.iter().sum::<u64>(), 30 * CENT);
+}
+
+#[test]
+fn test_core_bnb_pool_above_target() {
+    // Every coin of 5 to 19 cents overshoots a 1 cent target by more than the cost of change.
+    let inputs = pool((5..20).map(|cents| cents * CENT));
+    for _ in 0..100 {
+        assert!(bnb_values(&inputs, CENT, 2 * CENT).is_err());
+    }
+}
+
+/// A `waste_test` case over two inputs of 1 and 2 COIN, each paying `fee` now and
+/// `long_term_fee` when spent later.
+struct WasteVector {
+    name: &'static str,
+    fee: u64,
+    long_term_fee: u64,
+    target: u64,
+    change_cost: Option<u64>,
+    expected: i64,
+}
+
+const FEE: u64 = 100;
+const CHANGE_COST: u64 = 125;
+const FEE_DIFF: u64 = 40;
+const IN_AMOUNT: u64 = 3 * COIN;
+const TARGET: u64 = 2 * COIN;
+const EXCESS: u64 = IN_AMOUNT - FEE * 2 - TARGET;
+const EXACT_TARGET: u64 = IN_AMOUNT - FEE * 2;
+
+const WASTE_VECTORS: &[WasteVector] = &[
+    WasteVector {
+        name: "with change: cost of change and the fee difference",
+        fee: FEE,
+        long_term_fee: FEE - FEE_DIFF,
+        target: TARGET,
+        change_cost: Some(CHANGE_COST),
+        expected: (FEE_DIFF * 2 + CHANGE_COST) as i64,
+    },
+    WasteVector {
+        name: "without change: excess and the fee difference",
+        fee: FEE,
+        long_term_fee: FEE - FEE_DIFF,
+        target: TARGET,
+        change_cost: None,
+        expected: (FEE_DIFF * 2 + EXCESS) as i64,
+    },
+    WasteVector {
+        name: "with change and fee equal to the long term fee: cost of change",
+        fee: FEE,
+        long_term_fee: FEE,
+        target: TARGET,
+        change_cost: Some(CHANGE_COST),
+        expected: CHANGE_COST as i64,
+    },
+    WasteVector {
+        name: "without change and fee equal to the long term fee: excess",
+        fee: FEE,
+        long_term_fee: FEE,
+        target: TARGET,
+        change_cost: None,
+        expected: EXCESS as i64,
+    },
+    WasteVector {
+        name: "with change and fee below the long term fee",
+        fee: FEE,
+        long_term_fee: FEE + FEE_DIFF,
+        target: TARGET,
+        change_cost: Some(CHANGE_COST),
+        expected: CHANGE_COST as i64 - (FEE_DIFF * 2) as i64,
+    },
+    WasteVector {
+        name: "without change and fee below the long term fee",
+        fee: FEE,
+        long_term_fee: FEE + FEE_DIFF,
+        target: TARGET,
+        change_cost: None,
+        expected: EXCESS as i64 - (FEE_DIFF * 2) as i64,
+    },
+    WasteVector {
+        name: "negative waste: exact target without change",
+        fee: FEE,
+        long_term_fee: FEE + FEE_DIFF,
+        target: EXACT_TARGET,
+        change_cost: None,
+        expected: -2 * FEE_DIFF as i64,
+    },
+    WasteVector {
+        name: "negative waste: change costs less than the fee savings",
+        fee: FEE,
+        long_term_fee: FEE + FEE_DIFF,
+        target: EXACT_TARGET - (FEE_DIFF * 2 - 1),
+        change_cost: Some(FEE_DIFF * 2 - 1),
+        expected: -1,
+    },
+];
+
+#[test]
+fn test_core_waste_vectors() {
+    for vector in WASTE_VECTORS {
+        let options = CoinSelectionOpt {
+            target_value: vector.target,
+            target_feerate: FeeRate::from_sat_per_kwu(vector.fee),
+            long_term_feerate: Some(FeeRate::from_sat_per_kwu(vector.long_term_fee)),
+            change_weight: 0,
+            change_cost: vector.change_cost.unwrap_or(0),
+            excess_strategy: match vector.change_cost {
+                Some(_) => ExcessStrategy::ToChange,
+                None => ExcessStrategy::ToFee,
+            },
+            ..bnb_options(vector.target, 0)
+        };
+        // Two inputs of 1000 WU, i.e. `fee` each at the target feerate.
+        let waste = calculate_waste(&options, IN_AMOUNT - 2 * vector.fee, 2_000).unwrap();
+        assert_eq!(waste.total().unwrap().0, vector.expected, "{}", vector.name);
+    }
+}
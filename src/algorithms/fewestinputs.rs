@@ -0,0 +1,360 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    types::{CoinSelectionOpt, OutputGroup, SearchMetrics, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, check_cancelled, exceeds_max_input_count, funding_target,
+        insufficient_funds, max_tries, select_with_preselected, selection_output, unfunded_error,
+        Deadline, SelectionContext,
+    },
+};
+
+/// Best selection found so far, ranked by real UTXO count first and waste second.
+#[derive(Debug, Clone)]
+struct BestSelection {
+    selected: Vec<usize>,
+    input_count: usize,
+    waste: i64,
+    value: u64,
+    weight: u64,
+}
+
+/// Immutable search state shared by every branch.
+struct SearchContext<'a> {
+    /// The candidates by descending effective value, with their prefix sums.
+    selection: &'a SelectionContext,
+    target: u64,
+    options: &'a CoinSelectionOpt,
+    deadline: Deadline,
+}
+
+/// Performs coin selection that minimizes the number of inputs first and waste second.
+///
+/// The number of inputs is the summed [`OutputGroup::input_count`] of the selection, i.e. the
+/// number of UTXOs that have to be signed. This is the objective for hardware wallets and other
+/// signers that pay a per-input latency, where a few sats of extra waste are a good trade for one
+/// fewer signing round-trip.
+///
+/// A depth-first branch and bound over the inputs in descending effective value order is used. A
+/// branch is cut once it cannot beat the current best input count: with `s` inputs left to spend,
+/// it can at most add the `s` largest remaining effective values. The search is bounded by
+/// [`CoinSelectionOpt::max_tries`]; the largest-first selection is always found first, so a result
+/// is returned whenever the inputs cover the target. The search is iterative, so its stack use
+/// stays constant however many inputs the target takes.
+pub fn select_coin_fewest_inputs(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, select_coin_fewest_inputs);
+    }
+    select_coin_fewest_inputs_with_context(
+        inputs,
+        options,
+        &SelectionContext::new(inputs, options)?,
+    )
+}
+
+/// [`select_coin_fewest_inputs`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_fewest_inputs_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    // Effective values already net out per-input fees, so the target only needs the base fee.
+    let target = funding_target(options, true);
+
+    let search_context = SearchContext {
+        selection: context,
+        target,
+        options,
+        deadline: Deadline::start(options),
+    };
+    let mut best = None;
+    let max_tries = max_tries(options);
+    let mut tries = max_tries;
+    let mut pruned: u32 = 0;
+    search(&search_context, &mut best, &mut tries, &mut pruned)?;
+
+    let best = best.ok_or_else(|| unfunded_error(options, insufficient_funds_error))?;
+    let (fee, waste) = calculate_fee_and_waste(options, best.value, best.weight)?;
+    selection_output(
+        options,
+        &context.inputs,
+        best.selected,
+        fee,
+        waste,
+        true,
+        Some(SearchMetrics {
+            tries: max_tries - tries,
+            pruned,
+        }),
+    )
+}
+
+/// A branch still to explore: deciding on the candidate at `position`, with the totals of the
+/// first `selected_len` entries of the selection.
+struct Frame {
+    position: usize,
+    value: u64,
+    weight: u64,
+    input_count: usize,
+    selected_len: usize,
+}
+
+/// Depth-first search over include/omit decisions, trying the inclusion of each candidate first.
+///
+/// The pending omission branches are kept on an explicit stack rather than the call stack, so
+/// pools needing thousands of inputs do not overflow it.
+fn search(
+    context: &SearchContext,
+    best: &mut Option<BestSelection>,
+    tries: &mut u32,
+    pruned: &mut u32,
+) -> Result<(), SelectionError> {
+    let inputs = &context.selection.inputs;
+    let prefix_value = &context.selection.prefix_value;
+    let mut selected = Vec::new();
+    let mut stack = vec![Frame {
+        position: 0,
+        value: 0,
+        weight: 0,
+        input_count: 0,
+        selected_len: 0,
+    }];
+
+    while let Some(frame) = stack.pop() {
+        if *tries == 0 {
+            break;
+        }
+        let Frame {
+            position,
+            value,
+            weight,
+            input_count,
+            selected_len,
+        } = frame;
+        if position >= inputs.len() {
+            continue;
+        }
+        // Everything past `selected_len` was taken in a branch explored since.
+        selected.truncate(selected_len);
+        // Every extension spends at least one more input, and at most `slots` of them keep the
+        // count no worse than the best; those can add no more than the `slots` largest remaining
+        // values.
+        let slots = best
+            .as_ref()
+            .map_or(inputs.len(), |best| {
+                best.input_count.saturating_sub(input_count)
+            })
+            .min(inputs.len() - position);
+        let reachable = prefix_value[position + slots] - prefix_value[position];
+        if value + reachable < context.target {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                position,
+                input_count,
+                value,
+                "pruned: target out of reach within the best input count"
+            );
+            *pruned += 1;
+            continue;
+        }
+
+        *tries -= 1;
+        check_cancelled(context.options)?;
+        if context.deadline.has_passed_at(*tries) {
+            // Out of time: stop like an exhausted try budget, keeping the best found so far.
+            *tries = 0;
+        }
+
+        // The omission branch, explored once the inclusion branch is done.
+        stack.push(Frame {
+            position: position + 1,
+            ..frame
+        });
+
+        let candidate = &inputs[position];
+        let new_value = value + candidate.value;
+        let new_weight = weight + candidate.weight;
+        let new_input_count = input_count + candidate.input_count;
+        let admissible = !exceeds_max_input_count(context.options, new_input_count)
+            && best
+                .as_ref()
+                .is_none_or(|best| new_input_count <= best.input_count);
+        if !admissible {
+            continue;
+        }
+
+        selected.push(candidate.index);
+        if new_value >= context.target {
+            // Spending more inputs can only raise the count, so do not descend any further.
+            let (_, waste) = calculate_fee_and_waste(context.options, new_value, new_weight)?;
+            match best {
+                Some(best) if (new_input_count, waste) < (best.input_count, best.waste) => {
+                    // Copied into the previous best's allocation rather than a fresh clone.
+                    best.selected.clone_from(&selected);
+                    best.input_count = new_input_count;
+                    best.waste = waste;
+                    best.value = new_value;
+                    best.weight = new_weight;
+                }
+                Some(_) => {}
+                None => {
+                    *best = Some(BestSelection {
+                        selected: selected.clone(),
+                        input_count: new_input_count,
+                        waste,
+                        value: new_value,
+                        weight: new_weight,
+                    })
+                }
+            }
+        } else {
+            stack.push(Frame {
+                position: position + 1,
+                value: new_value,
+                weight: new_weight,
+                input_count: new_input_count,
+                selected_len: selected.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::fewestinputs::select_coin_fewest_inputs,
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        },
+    };
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value,
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
+            min_absolute_fee: 0,
+            base_weight: 0,
+            change_weight: 0,
+            change_cost: 20,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToFee,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fewest_inputs_trades_waste_for_input_count() {
+        // 6000 + 5000 is an exact match, but the single 20000 coin needs only one input.
+        let inputs = vec![
+            OutputGroup {
+                value: 6_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 5_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 20_000,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_fewest_inputs(&inputs, &setup_options(11_000)).unwrap();
+        assert_eq!(result.selected_inputs, vec![2]);
+    }
+
+    #[test]
+    fn test_fewest_inputs_breaks_ties_by_waste() {
+        // Both 9000 + 3000 and 7000 + 4000 (and more) use two inputs; only the latter is exact.
+        let inputs = vec![
+            OutputGroup {
+                value: 9_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 7_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 4_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_000,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_fewest_inputs(&inputs, &setup_options(11_000)).unwrap();
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![1, 2]);
+        assert_eq!(result.waste.0, 0);
+    }
+
+    #[test]
+    fn test_fewest_inputs_counts_grouped_utxos() {
+        // The 12000 group bundles three UTXOs, so two single coins are fewer inputs to sign.
+        let inputs = vec![
+            OutputGroup {
+                value: 12_000,
+                input_count: 3,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 6_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 5_000,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_fewest_inputs(&inputs, &setup_options(11_000)).unwrap();
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_fewest_inputs_deep_search_runs_on_small_stack() {
+        // Reaching the target takes all but the smallest input, so the search goes 20_000 levels
+        // deep; a recursive search would overflow the 256 KiB stack.
+        let inputs: Vec<OutputGroup> = (0..20_000u64)
+            .map(|value| OutputGroup {
+                value: 1_000 + value,
+                ..Default::default()
+            })
+            .collect();
+        let total: u64 = inputs.iter().map(|input| input.value).sum();
+        let options = setup_options(total - 1_000);
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || select_coin_fewest_inputs(&inputs, &options))
+            .unwrap()
+            .join()
+            .expect("Fewest Inputs must not overflow the stack");
+        let result = result.unwrap();
+        assert_eq!(result.selected_inputs.len(), 19_999);
+        assert!(!result.selected_inputs.contains(&0));
+    }
+
+    #[test]
+    fn test_fewest_inputs_insufficient_funds() {
+        let inputs = vec![OutputGroup {
+            value: 1_000,
+            ..Default::default()
+        }];
+        let result = select_coin_fewest_inputs(&inputs, &setup_options(10_000));
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
@@ -1,6 +1,11 @@
+use alloc::vec::Vec;
+
 use crate::{
-    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput, WasteMetric},
-    utils::{calculate_fee, calculate_fee_and_waste, insufficient_funds, prepare_output_groups},
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, exceeds_max_input_count, funding_target, insufficient_funds,
+        select_with_preselected, selection_output, unfunded_error, SelectionContext,
+    },
 };
 
 /// Performs coin selection using the First-In-First-Out (FIFO) algorithm.
@@ -10,36 +15,43 @@ use crate::{
 pub fn select_coin_fifo(
     inputs: &[OutputGroup],
     options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, select_coin_fifo);
+    }
+    select_coin_fifo_with_context(inputs, options, &SelectionContext::new(inputs, options)?)
+}
+
+/// [`select_coin_fifo`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_fifo_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
 ) -> Result<SelectionOutput, SelectionError> {
     let insufficient_funds_error = insufficient_funds(inputs, options);
-    let inputs = prepare_output_groups(inputs, options)?;
+    let inputs = &context.inputs;
     let mut accumulated_value: u64 = 0;
     let mut accumulated_weight: u64 = 0;
+    let mut accumulated_input_count: usize = 0;
     let mut selected_inputs: Vec<usize> = Vec::new();
-    let base_fee = calculate_fee(
-        options.base_weight + options.change_weight,
-        options.target_feerate,
-    )
-    .max(options.min_absolute_fee);
     // Effective values already net out per-input fees, so the target only needs the base fee.
-    let target = options.target_value + base_fee;
-
-    // Sorting the inputs vector based on creation_sequence
-    let mut sorted_inputs: Vec<_> = inputs
-        .iter()
-        .filter(|og| og.creation_sequence.is_some())
-        .collect();
-
-    sorted_inputs.sort_by(|a, b| a.creation_sequence.cmp(&b.creation_sequence));
-
-    let inputs_without_sequence: Vec<_> = inputs
-        .iter()
-        .filter(|og| og.creation_sequence.is_none())
-        .collect();
-
-    sorted_inputs.extend(inputs_without_sequence);
+    let target = funding_target(options, true);
+
+    // Sorting the inputs by creation_sequence, the ones without one last in their original order.
+    let mut sorted_inputs: Vec<_> = inputs.iter().collect();
+    sorted_inputs.sort_by_key(|input| {
+        (
+            input.creation_sequence.is_none(),
+            input.creation_sequence,
+            input.index,
+        )
+    });
 
     for input in sorted_inputs {
+        accumulated_input_count += input.input_count;
+        if exceeds_max_input_count(options, accumulated_input_count) {
+            break;
+        }
         accumulated_value += input.value;
         accumulated_weight += input.weight;
         selected_inputs.push(input.index);
@@ -50,14 +62,10 @@ pub fn select_coin_fifo(
     }
 
     if accumulated_value < target {
-        Err(insufficient_funds_error)
+        Err(unfunded_error(options, insufficient_funds_error))
     } else {
         let (fee, waste) = calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
-        Ok(SelectionOutput {
-            selected_inputs,
-            waste: WasteMetric(waste),
-            fee,
-        })
+        selection_output(options, inputs, selected_inputs, fee, waste, true, None)
     }
 }
 
@@ -67,23 +75,51 @@ mod test {
     use crate::{
         algorithms::fifo::select_coin_fifo,
         types::{
-            basic_output_group, CoinSelectionOpt, ExcessStrategy, OutputGroup, SelectionError,
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
         },
     };
 
     fn setup_basic_output_groups() -> Vec<OutputGroup> {
         vec![
-            basic_output_group(1000, 100),
-            basic_output_group(2000, 200),
-            basic_output_group(3000, 300),
+            OutputGroup {
+                value: 1000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2000,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3000,
+                weight: 300,
+                ..Default::default()
+            },
         ]
     }
     fn setup_output_groups_withsequence() -> Vec<OutputGroup> {
         let mut inputs = vec![
-            basic_output_group(1000, 100),
-            basic_output_group(2000, 200),
-            basic_output_group(3000, 300),
-            basic_output_group(1500, 150),
+            OutputGroup {
+                value: 1000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2000,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3000,
+                weight: 300,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1500,
+                weight: 150,
+                ..Default::default()
+            },
         ];
         inputs[0].creation_sequence = Some(1);
         inputs[1].creation_sequence = Some(5000);
@@ -94,14 +130,16 @@ mod test {
     fn setup_options(target_value: u64) -> CoinSelectionOpt {
         CoinSelectionOpt {
             target_value,
-            target_feerate: 0.4, // Simplified feerate
-            long_term_feerate: Some(0.4),
+            target_feerate: FeeRate::from_sat_per_wu(0.4), // Simplified feerate
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(0.4)),
             min_absolute_fee: 0,
             base_weight: 10,
             change_weight: 50,
             change_cost: 10,
             min_change_value: 500,
             excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
         }
     }
 
@@ -131,9 +169,25 @@ mod test {
         ));
     }
 
+    fn test_unconfirmed_ancestors_bump_fee() {
+        let mut inputs = setup_basic_output_groups();
+        let options = setup_options(500);
+        let confirmed = select_coin_fifo(&inputs, &options).unwrap();
+
+        // The parent paid 60 sats for 400 WU; at 0.4 sat/WU the package is 100 sats short.
+        inputs[0].ancestor_weight = 400;
+        inputs[0].ancestor_fee = 60;
+        let unconfirmed = select_coin_fifo(&inputs, &options).unwrap();
+        assert_eq!(unconfirmed.selected_inputs, confirmed.selected_inputs);
+        assert_eq!(unconfirmed.fee, confirmed.fee + 100);
+        // Without a change output the bump is paid out of excess that was wasted anyway.
+        assert_eq!(unconfirmed.waste, confirmed.waste);
+    }
+
     #[test]
     fn test_fifo() {
         test_successful_selection();
         test_insufficient_funds();
+        test_unconfirmed_ancestors_bump_fee();
     }
 }
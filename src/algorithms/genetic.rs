@@ -0,0 +1,363 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, candidate_score, check_cancelled, exceeds_max_input_count,
+        funding_target, insufficient_funds, select_with_preselected, selection_output, Deadline,
+        PreparedInputs, SelectionContext, SplitMix64, TOO_MANY_INPUTS,
+    },
+};
+
+/// Population and evolution parameters for [`select_coin_genetic_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneticParams {
+    /// Number of candidate selections kept in every generation.
+    pub population_size: usize,
+    /// Number of generations to evolve.
+    pub generations: u32,
+    /// Probability of flipping each input of an offspring, in `[0, 1]`.
+    pub mutation_rate: f64,
+    /// Seed of the pseudo random generator, so that runs are reproducible.
+    pub seed: u64,
+}
+
+impl Default for GeneticParams {
+    fn default() -> Self {
+        GeneticParams {
+            population_size: 64,
+            generations: 200,
+            mutation_rate: 0.02,
+            seed: 0,
+        }
+    }
+}
+
+/// A candidate selection: an inclusion mask over the prepared inputs plus its totals and fitness.
+#[derive(Debug, Clone)]
+struct Individual {
+    genes: Vec<bool>,
+    value: u64,
+    weight: u64,
+    /// Waste, or [`CoinSelectionOpt::selection_metric`] score when it is set.
+    score: i64,
+    /// Spends more than [`CoinSelectionOpt::max_input_count`] inputs; such individuals rank last.
+    over_cap: bool,
+}
+
+impl Individual {
+    /// Sort key of the fitness: lower is fitter.
+    fn fitness(&self) -> (bool, i64) {
+        (self.over_cap, self.score)
+    }
+}
+
+/// Performs coin selection via a genetic algorithm with the default [`GeneticParams`], seeded from
+/// [`CoinSelectionOpt::seed`] when it is set.
+pub fn select_coin_genetic(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    select_coin_genetic_with(inputs, options, &seeded_params(options))
+}
+
+/// [`select_coin_genetic`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_genetic_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    evolve(inputs, options, &seeded_params(options), context)
+}
+
+/// The default [`GeneticParams`], seeded from [`CoinSelectionOpt::seed`] when it is set.
+fn seeded_params(options: &CoinSelectionOpt) -> GeneticParams {
+    let defaults = GeneticParams::default();
+    GeneticParams {
+        seed: options.seed.unwrap_or(defaults.seed),
+        ..defaults
+    }
+}
+
+/// Performs coin selection via a genetic algorithm.
+///
+/// A population of candidate input sets is evolved with tournament selection, uniform crossover
+/// and per-input mutation, using the waste metric, or [`CoinSelectionOpt::selection_metric`] when it
+/// is set, as fitness (lower is fitter). Offspring that no
+/// longer cover the target are repaired by adding the largest unselected inputs, so every
+/// individual is always a valid selection. The two fittest individuals survive each generation
+/// unchanged, so the result is never worse than the greedy largest-first seed.
+///
+/// This is considerably more expensive than the other algorithms and is not run by
+/// [`crate::selectcoin::select_coin`] (request it through [`crate::selectcoin::select_coin_with`]
+/// instead); it is meant as a heavy-duty optimizer for irregular pools on which the heuristics
+/// produce poor results.
+pub fn select_coin_genetic_with(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &GeneticParams,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, |inputs, options| {
+            select_coin_genetic_with(inputs, options, params)
+        });
+    }
+    evolve(
+        inputs,
+        options,
+        params,
+        &SelectionContext::new(inputs, options)?,
+    )
+}
+
+fn evolve(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &GeneticParams,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    let original_inputs = inputs;
+    // Descending order lets `evaluate` repair an individual by scanning for the largest unselected input.
+    let inputs = &context.inputs;
+    // Effective values already net out per-input fees, so the target only needs the base fee.
+    let target = funding_target(options, true);
+
+    if context.total_value() < target {
+        return Err(insufficient_funds_error);
+    }
+
+    let mut rng = SplitMix64::new(params.seed);
+    let population_size = params.population_size.max(2);
+    // Random individuals include each input with a probability that roughly lands on the target.
+    let total = context.total_value();
+    let inclusion = (target as f64 / total as f64).min(1.0);
+
+    let mut population = Vec::with_capacity(population_size);
+    population.push(evaluate(
+        original_inputs,
+        inputs,
+        vec![false; inputs.len()],
+        target,
+        options,
+    )?);
+    while population.len() < population_size {
+        let genes = (0..inputs.len())
+            .map(|_| rng.next_f64() < inclusion)
+            .collect();
+        population.push(evaluate(original_inputs, inputs, genes, target, options)?);
+    }
+
+    let deadline = Deadline::start(options);
+    for _ in 0..params.generations {
+        check_cancelled(options)?;
+        if deadline.has_passed() {
+            break;
+        }
+        population.sort_by_key(Individual::fitness);
+        let mut next_generation = population[..2].to_vec();
+        while next_generation.len() < population_size {
+            let mother = tournament(&population, &mut rng);
+            let father = tournament(&population, &mut rng);
+            let genes = mother
+                .genes
+                .iter()
+                .zip(&father.genes)
+                .map(|(&m, &f)| {
+                    let gene = if rng.next_u64() & 1 == 1 { m } else { f };
+                    gene ^ (rng.next_f64() < params.mutation_rate)
+                })
+                .collect();
+            next_generation.push(evaluate(original_inputs, inputs, genes, target, options)?);
+        }
+        population = next_generation;
+    }
+
+    let best = population
+        .into_iter()
+        .min_by_key(|individual| {
+            (
+                individual.fitness(),
+                individual.genes.iter().filter(|&&g| g).count(),
+            )
+        })
+        .expect("population is never empty");
+    if best.over_cap {
+        return Err(TOO_MANY_INPUTS);
+    }
+    let (fee, waste) = calculate_fee_and_waste(options, best.value, best.weight)?;
+    let selected_inputs: Vec<usize> = inputs
+        .iter()
+        .zip(&best.genes)
+        .filter(|(_, &selected)| selected)
+        .map(|(input, _)| input.index)
+        .collect();
+    selection_output(options, inputs, selected_inputs, fee, waste, true, None)
+}
+
+/// Repairs `genes` until they cover `target` and scores the resulting individual.
+fn evaluate(
+    original_inputs: &[OutputGroup],
+    inputs: &PreparedInputs,
+    mut genes: Vec<bool>,
+    target: u64,
+    options: &CoinSelectionOpt,
+) -> Result<Individual, SelectionError> {
+    let mut value: u64 = 0;
+    let mut weight: u64 = 0;
+    let mut input_count: usize = 0;
+    for (input, _) in inputs.iter().zip(&genes).filter(|(_, &g)| g) {
+        value += input.value;
+        weight += input.weight;
+        input_count += input.input_count;
+    }
+    for (input, gene) in inputs.iter().zip(genes.iter_mut()) {
+        if value >= target {
+            break;
+        }
+        if !*gene {
+            *gene = true;
+            value += input.value;
+            weight += input.weight;
+            input_count += input.input_count;
+        }
+    }
+    let over_cap = exceeds_max_input_count(options, input_count);
+    // Over-cap individuals rank last whatever their score, and cannot make a selection to score.
+    // Infeasible ones rank after every feasible one that is not over the cap.
+    let score = if over_cap {
+        calculate_fee_and_waste(options, value, weight)?.1
+    } else {
+        candidate_score(original_inputs, inputs, &genes, value, weight, options)?
+            .unwrap_or(i64::MAX)
+    };
+    Ok(Individual {
+        genes,
+        value,
+        weight,
+        score,
+        over_cap,
+    })
+}
+
+/// Picks the fittest of three random individuals.
+fn tournament<'a>(population: &'a [Individual], rng: &mut SplitMix64) -> &'a Individual {
+    (0..3)
+        .map(|_| &population[rng.below(population.len())])
+        .min_by_key(|individual| individual.fitness())
+        .expect("tournament size is non-zero")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::genetic::{select_coin_genetic, select_coin_genetic_with, GeneticParams},
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        },
+    };
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value,
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
+            min_absolute_fee: 0,
+            base_weight: 0,
+            change_weight: 0,
+            change_cost: 20,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToFee,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_genetic_finds_exact_match() {
+        // Greedy overshoots with 8000 + 7000; the fittest individuals hit 11000 exactly.
+        let inputs = vec![
+            OutputGroup {
+                value: 8_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 7_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 600,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_genetic(&inputs, &setup_options(11_000)).unwrap();
+        let value: u64 = result
+            .selected_inputs
+            .iter()
+            .map(|&i| inputs[i].value)
+            .sum();
+        assert_eq!(value, 11_000);
+        assert_eq!(result.waste.0, 0);
+    }
+
+    #[test]
+    fn test_genetic_is_reproducible() {
+        let inputs: Vec<_> = (1..=30u64)
+            .map(|i| OutputGroup {
+                value: i * 7_919 % 5_003 + 600,
+                ..Default::default()
+            })
+            .collect();
+        let options = setup_options(40_000);
+        let params = GeneticParams {
+            generations: 50,
+            seed: 7,
+            ..Default::default()
+        };
+        let first = select_coin_genetic_with(&inputs, &options, &params).unwrap();
+        let second = select_coin_genetic_with(&inputs, &options, &params).unwrap();
+        assert_eq!(first.selected_inputs, second.selected_inputs);
+        assert_eq!(first.waste, second.waste);
+    }
+
+    #[test]
+    fn test_genetic_uses_options_seed() {
+        let inputs: Vec<_> = (1..=30u64)
+            .map(|i| OutputGroup {
+                value: i * 7_919 % 5_003 + 600,
+                ..Default::default()
+            })
+            .collect();
+        let mut options = setup_options(40_000);
+        options.seed = Some(7);
+        let params = GeneticParams {
+            seed: 7,
+            ..Default::default()
+        };
+        let seeded = select_coin_genetic(&inputs, &options).unwrap();
+        let explicit = select_coin_genetic_with(&inputs, &options, &params).unwrap();
+        assert_eq!(seeded.selected_inputs, explicit.selected_inputs);
+        assert_eq!(seeded.waste, explicit.waste);
+    }
+
+    #[test]
+    fn test_genetic_insufficient_funds() {
+        let inputs = vec![OutputGroup {
+            value: 1_000,
+            ..Default::default()
+        }];
+        let result = select_coin_genetic(&inputs, &setup_options(10_000));
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
@@ -0,0 +1,345 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, check_cancelled, exceeds_max_input_count, funding_target,
+        insufficient_funds, select_with_preselected, selection_output, PreparedInputs,
+        SelectionContext, SplitMix64, TOO_MANY_INPUTS,
+    },
+};
+
+/// Search parameters for [`select_coin_knapsack_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnapsackParams {
+    /// Number of random subsets tried by each approximate best subset search. Core uses 1000.
+    pub iterations: u32,
+    /// Seed of the pseudo random generator, so that runs are reproducible.
+    pub seed: u64,
+}
+
+impl Default for KnapsackParams {
+    fn default() -> Self {
+        KnapsackParams {
+            iterations: 1000,
+            seed: 0,
+        }
+    }
+}
+
+/// Performs coin selection with the legacy Bitcoin Core knapsack solver and the default
+/// [`KnapsackParams`], seeded from [`CoinSelectionOpt::seed`] when it is set.
+pub fn select_coin_knapsack(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    select_coin_knapsack_with(inputs, options, &seeded_params(options))
+}
+
+/// [`select_coin_knapsack`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_knapsack_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    solve(inputs, options, &seeded_params(options), context)
+}
+
+/// The default [`KnapsackParams`], seeded from [`CoinSelectionOpt::seed`] when it is set.
+fn seeded_params(options: &CoinSelectionOpt) -> KnapsackParams {
+    let defaults = KnapsackParams::default();
+    KnapsackParams {
+        seed: options.seed.unwrap_or(defaults.seed),
+        ..defaults
+    }
+}
+
+/// Performs coin selection with the knapsack solver Bitcoin Core used before Branch and Bound, for
+/// wallets that must reproduce its choices.
+///
+/// Like Core's `KnapsackSolver`, with [`CoinSelectionOpt::min_change_value`] in place of its
+/// `MIN_CHANGE`, the effective values are measured against the target plus the fee of the
+/// transaction with its change output:
+///
+/// 1. An input matching the target exactly is taken alone.
+/// 2. The inputs worth less than the target plus the minimum change are taken together if they sum
+///    to the target exactly, and the smallest input worth more, the lowest larger, is taken alone
+///    if they fall short.
+/// 3. Otherwise an approximate best subset of the smaller inputs is searched for by random
+///    inclusion, for the target and, failing an exact match, the target plus the minimum change.
+///    The lowest larger wins if that subset misses both or is not smaller than it.
+///
+/// A result breaking [`CoinSelectionOpt::max_input_count`] falls back to the lowest larger when
+/// there is one.
+///
+/// Knapsack is not run by [`crate::selectcoin::select_coin`]; request it through
+/// [`crate::selectcoin::select_coin_with`].
+pub fn select_coin_knapsack_with(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &KnapsackParams,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, |inputs, options| {
+            select_coin_knapsack_with(inputs, options, params)
+        });
+    }
+    solve(
+        inputs,
+        options,
+        params,
+        &SelectionContext::new(inputs, options)?,
+    )
+}
+
+fn solve(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &KnapsackParams,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    // Descending effective value, lighter first on ties.
+    let inputs = &context.inputs;
+    let target = funding_target(options, true);
+    let min_change = options.min_change_value;
+
+    let mut applicable = Vec::new();
+    let mut total_lower: u64 = 0;
+    let mut lowest_larger: Option<usize> = None;
+    for (position, input) in inputs.iter().enumerate() {
+        if input.value == target {
+            return knapsack_output(options, inputs, &[position]);
+        } else if input.value < target.saturating_add(min_change) {
+            applicable.push(position);
+            total_lower += input.value;
+        } else {
+            // The inputs are sorted, so each larger one is the lowest so far.
+            lowest_larger = Some(position);
+        }
+    }
+
+    if total_lower == target {
+        return knapsack_output(options, inputs, &applicable);
+    }
+    if total_lower < target {
+        return match lowest_larger {
+            Some(position) => knapsack_output(options, inputs, &[position]),
+            None => Err(insufficient_funds_error),
+        };
+    }
+
+    let mut rng = SplitMix64::new(params.seed);
+    let (mut best, mut best_value) = approximate_best_subset(
+        inputs,
+        &applicable,
+        total_lower,
+        target,
+        params,
+        &mut rng,
+        options,
+    )?;
+    if best_value != target && total_lower >= target.saturating_add(min_change) {
+        (best, best_value) = approximate_best_subset(
+            inputs,
+            &applicable,
+            total_lower,
+            target + min_change,
+            params,
+            &mut rng,
+            options,
+        )?;
+    }
+
+    let subset: Vec<usize> = applicable
+        .iter()
+        .zip(&best)
+        .filter(|(_, &included)| included)
+        .map(|(&position, _)| position)
+        .collect();
+    let subset_count: usize = subset
+        .iter()
+        .map(|&position| inputs[position].input_count)
+        .sum();
+    match lowest_larger {
+        Some(position)
+            if (best_value != target && best_value < target.saturating_add(min_change))
+                || inputs[position].value <= best_value
+                || exceeds_max_input_count(options, subset_count) =>
+        {
+            knapsack_output(options, inputs, &[position])
+        }
+        _ => knapsack_output(options, inputs, &subset),
+    }
+}
+
+/// Core's `ApproximateBestSubset`: the smallest sum of `applicable` reaching `target` among
+/// `params.iterations` random subsets, each completed in a second pass over the inputs it left out.
+/// Returns the inclusion mask over `applicable` and its sum, every input while none does better.
+fn approximate_best_subset(
+    inputs: &PreparedInputs,
+    applicable: &[usize],
+    total_lower: u64,
+    target: u64,
+    params: &KnapsackParams,
+    rng: &mut SplitMix64,
+    options: &CoinSelectionOpt,
+) -> Result<(Vec<bool>, u64), SelectionError> {
+    let mut best = vec![true; applicable.len()];
+    let mut best_value = total_lower;
+    for _ in 0..params.iterations {
+        if best_value == target {
+            break;
+        }
+        check_cancelled(options)?;
+        let mut included = vec![false; applicable.len()];
+        let mut total: u64 = 0;
+        let mut reached_target = false;
+        for pass in 0..2 {
+            if reached_target {
+                break;
+            }
+            for (i, &position) in applicable.iter().enumerate() {
+                // The first pass includes each input at random, the second every one left out.
+                let include = if pass == 0 {
+                    rng.next_u64() & 1 == 1
+                } else {
+                    !included[i]
+                };
+                if !include {
+                    continue;
+                }
+                total += inputs[position].value;
+                included[i] = true;
+                if total >= target {
+                    reached_target = true;
+                    if total < best_value {
+                        best_value = total;
+                        best.clone_from(&included);
+                    }
+                    total -= inputs[position].value;
+                    included[i] = false;
+                }
+            }
+        }
+    }
+    Ok((best, best_value))
+}
+
+/// The selection spending the prepared inputs at `positions`.
+fn knapsack_output(
+    options: &CoinSelectionOpt,
+    inputs: &PreparedInputs,
+    positions: &[usize],
+) -> Result<SelectionOutput, SelectionError> {
+    let input_count: usize = positions
+        .iter()
+        .map(|&position| inputs[position].input_count)
+        .sum();
+    if exceeds_max_input_count(options, input_count) {
+        return Err(TOO_MANY_INPUTS);
+    }
+    let value = positions
+        .iter()
+        .map(|&position| inputs[position].value)
+        .sum();
+    let weight = positions
+        .iter()
+        .map(|&position| inputs[position].weight)
+        .sum();
+    let (fee, waste) = calculate_fee_and_waste(options, value, weight)?;
+    let selected_inputs = positions
+        .iter()
+        .map(|&position| inputs[position].index)
+        .collect();
+    selection_output(options, inputs, selected_inputs, fee, waste, true, None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::knapsack::{select_coin_knapsack, select_coin_knapsack_with, KnapsackParams},
+        types::{CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, SelectionError},
+    };
+
+    /// Weightless inputs at 1 sat/kWU, so effective values are values and the target is exact.
+    fn setup_inputs(values: &[u64]) -> Vec<OutputGroup> {
+        values
+            .iter()
+            .map(|&value| OutputGroup {
+                value,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        let options = CoinSelectionOpt::builder()
+            .target_value(target_value)
+            .target_feerate(FeeRate::from_sat_per_kwu(1))
+            .base_weight(0)
+            .min_change_value(1_000)
+            .dust_threshold(0)
+            .excess_strategy(ExcessStrategy::ToFee)
+            .build()
+            .unwrap();
+        // Without a change output, whose weight the builder sets.
+        CoinSelectionOpt {
+            change_weight: 0,
+            ..options
+        }
+    }
+
+    fn selected_values(inputs: &[OutputGroup], selected: &[usize]) -> Vec<u64> {
+        let mut values: Vec<u64> = selected.iter().map(|&i| inputs[i].value).collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn test_knapsack_exact_match() {
+        let inputs = setup_inputs(&[9_000, 5_000, 3_000, 2_000]);
+        let output = select_coin_knapsack(&inputs, &setup_options(5_000)).unwrap();
+        assert_eq!(selected_values(&inputs, &output.selected_inputs), [5_000]);
+        // The smaller inputs summing to the target exactly are all taken.
+        let inputs = setup_inputs(&[20_000, 3_000, 2_000]);
+        let output = select_coin_knapsack(&inputs, &setup_options(5_000)).unwrap();
+        assert_eq!(
+            selected_values(&inputs, &output.selected_inputs),
+            [2_000, 3_000]
+        );
+    }
+
+    #[test]
+    fn test_knapsack_lowest_larger_fallback() {
+        // The smaller inputs fall short, so the smallest input worth the target plus the minimum
+        // change is taken.
+        let inputs = setup_inputs(&[50_000, 20_000, 3_000, 2_000]);
+        let output = select_coin_knapsack(&inputs, &setup_options(10_000)).unwrap();
+        assert_eq!(selected_values(&inputs, &output.selected_inputs), [20_000]);
+    }
+
+    #[test]
+    fn test_knapsack_approximate_best_subset() {
+        // 6 + 4 cover 10 exactly, which the random subsets find; 50 is larger than any subset.
+        let inputs = setup_inputs(&[50_000, 7_000, 6_000, 4_000, 1_500]);
+        let params = KnapsackParams {
+            seed: 5,
+            ..Default::default()
+        };
+        let output = select_coin_knapsack_with(&inputs, &setup_options(10_000), &params).unwrap();
+        assert_eq!(
+            selected_values(&inputs, &output.selected_inputs),
+            [4_000, 6_000]
+        );
+    }
+
+    #[test]
+    fn test_knapsack_insufficient_funds() {
+        let inputs = setup_inputs(&[3_000, 2_000]);
+        assert!(matches!(
+            select_coin_knapsack(&inputs, &setup_options(10_000)),
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
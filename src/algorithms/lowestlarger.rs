@@ -1,6 +1,11 @@
+use alloc::{vec, vec::Vec};
+
 use crate::{
-    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput, WasteMetric},
-    utils::{calculate_fee, calculate_fee_and_waste, insufficient_funds, prepare_output_groups},
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, exceeds_max_input_count, funding_target, insufficient_funds,
+        select_with_preselected, selection_output, unfunded_error, SelectionContext,
+    },
 };
 
 /// Performs coin selection using the Lowest Larger algorithm.
@@ -9,65 +14,92 @@ use crate::{
 ///
 /// 1. **Lowest larger** : the single smallest input that on its own covers the target plus fees.
 /// 2. **Accumulated smaller** : the inputs that are not individually sufficient, accumulated smallest-first until they cover the target plus fees.
+///
+/// Neither candidate may break [`CoinSelectionOpt::max_input_count`]. Among inputs of equal
+/// effective value, the lighter ones are taken first.
+///
+/// Both candidates are located by binary search over the inputs sorted by effective value and their
+/// prefix sums, so beyond the initial sort the work only grows with the size of the selection, not
+/// with the size of the pool.
 pub fn select_coin_lowestlarger(
     inputs: &[OutputGroup],
     options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, select_coin_lowestlarger);
+    }
+    select_coin_lowestlarger_with_context(inputs, options, &SelectionContext::new(inputs, options)?)
+}
+
+/// [`select_coin_lowestlarger`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_lowestlarger_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
 ) -> Result<SelectionOutput, SelectionError> {
     let insufficient_funds_error = insufficient_funds(inputs, options);
-    let inputs = prepare_output_groups(inputs, options)?;
-    let base_fee = calculate_fee(
-        options.base_weight + options.change_weight,
-        options.target_feerate,
-    )
-    .max(options.min_absolute_fee);
+    // Descending effective value, lighter first on ties.
+    let inputs = &context.inputs;
     // Effective values already net out per-input fees, so the target only needs the base fee.
-    let target = options.target_value + base_fee;
+    let target = funding_target(options, true);
 
-    let mut sorted_inputs: Vec<_> = inputs.iter().collect();
-    sorted_inputs.sort_by_key(|input| input.value);
+    // The individually sufficient inputs come first, the smaller ones after them.
+    let split = inputs.partition_point(|input| input.value >= target);
 
-    // Candidate 1: the smallest single input that alone covers target + its own fee.
+    // Candidate 1: the smallest single input that alone covers target + its own fee. Walk the runs
+    // of equal value upwards from the smallest sufficient one, lightest first within a run.
     let mut single_candidate: Option<SelectionOutput> = None;
-    for &input in &sorted_inputs {
-        if input.value >= target {
+    let mut run_end = split;
+    while run_end > 0 {
+        let value = inputs[run_end - 1].value;
+        let run_start = inputs[..run_end].partition_point(|input| input.value > value);
+        if let Some(input) = inputs[run_start..run_end]
+            .iter()
+            .find(|input| !exceeds_max_input_count(options, input.input_count))
+        {
             let (fee, waste) = calculate_fee_and_waste(options, input.value, input.weight)?;
-            single_candidate = Some(SelectionOutput {
-                selected_inputs: vec![input.index],
-                waste: WasteMetric(waste),
+            single_candidate = Some(selection_output(
+                options,
+                inputs,
+                vec![input.index],
                 fee,
-            });
+                waste,
+                true,
+                None,
+            )?);
             break;
         }
+        run_end = run_start;
     }
 
     // Candidate 2: accumulate the inputs that are not individually sufficient, smallest first.
-    let mut accumulated_value: u64 = 0;
-    let mut accumulated_weight: u64 = 0;
-    let mut selected_inputs: Vec<usize> = Vec::new();
-    let mut accumulated_sufficient = false;
-    for &input in &sorted_inputs {
-        if input.value >= target {
-            // Individually sufficient inputs belong to the single-coin candidate, skip here.
-            continue;
-        }
-        accumulated_value += input.value;
-        accumulated_weight += input.weight;
-        selected_inputs.push(input.index);
-
-        if accumulated_value >= target {
-            accumulated_sufficient = true;
-            break;
+    let accumulated_candidate = match smallest_covering(context, split, target) {
+        Some(positions) => {
+            let (mut value, mut weight, mut input_count) = (0, 0, 0);
+            let mut selected_inputs = Vec::new();
+            for input in positions.map(|position| &inputs[position]) {
+                value += input.value;
+                weight += input.weight;
+                input_count += input.input_count;
+                selected_inputs.push(input.index);
+            }
+            // Any shorter smallest-first prefix falls short, so going over the cap rules it out.
+            if exceeds_max_input_count(options, input_count) {
+                None
+            } else {
+                let (fee, waste) = calculate_fee_and_waste(options, value, weight)?;
+                Some(selection_output(
+                    options,
+                    inputs,
+                    selected_inputs,
+                    fee,
+                    waste,
+                    true,
+                    None,
+                )?)
+            }
         }
-    }
-    let accumulated_candidate = if accumulated_sufficient {
-        let (fee, waste) = calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
-        Some(SelectionOutput {
-            selected_inputs,
-            waste: WasteMetric(waste),
-            fee,
-        })
-    } else {
-        None
+        None => None,
     };
 
     // Pick the candidate with the lower waste.
@@ -81,8 +113,33 @@ pub fn select_coin_lowestlarger(
         }
         (Some(single), None) => Ok(single),
         (None, Some(accumulated)) => Ok(accumulated),
-        (None, None) => Err(insufficient_funds_error),
+        (None, None) => Err(unfunded_error(options, insufficient_funds_error)),
+    }
+}
+
+/// Positions, in the context's order, of the fewest smallest inputs from `split` on that cover
+/// `target`, or `None` if all of them together fall short. Of a run of equal values only partly
+/// needed, the lighter inputs, which the context places first, are taken.
+fn smallest_covering(
+    context: &SelectionContext,
+    split: usize,
+    target: u64,
+) -> Option<impl Iterator<Item = usize>> {
+    let inputs = &context.inputs;
+    if context.remaining_value(split) < target {
+        return None;
     }
+    // The inputs from `start` on cover the target while the value before `start` stays within
+    // `total - target`; take the last such `start`, i.e. the fewest inputs.
+    let slack = context.total_value() - target;
+    let start = split
+        + context.prefix_value[split..inputs.len()].partition_point(|&before| before <= slack)
+        - 1;
+    let value = inputs[start].value;
+    let run_start = inputs[..start].partition_point(|input| input.value > value);
+    let run_end = start + inputs[start..].partition_point(|input| input.value >= value);
+    let needed = run_end - start;
+    Some((run_start..run_start + needed).chain(run_end..inputs.len()))
 }
 
 #[cfg(test)]
@@ -91,38 +148,88 @@ mod test {
     use crate::{
         algorithms::lowestlarger::select_coin_lowestlarger,
         types::{
-            basic_output_group, CoinSelectionOpt, ExcessStrategy, OutputGroup, SelectionError,
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
         },
     };
 
     fn setup_lowestlarger_output_groups() -> Vec<OutputGroup> {
         vec![
-            basic_output_group(100, 100),
-            basic_output_group(1500, 200),
-            basic_output_group(3400, 300),
-            basic_output_group(2200, 150),
-            basic_output_group(1190, 200),
-            basic_output_group(3300, 100),
-            basic_output_group(1000, 190),
-            basic_output_group(2000, 210),
-            basic_output_group(3000, 300),
-            basic_output_group(2250, 250),
-            basic_output_group(190, 220),
-            basic_output_group(1750, 170),
+            OutputGroup {
+                value: 100,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1500,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3400,
+                weight: 300,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2200,
+                weight: 150,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1190,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3300,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1000,
+                weight: 190,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2000,
+                weight: 210,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3000,
+                weight: 300,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2250,
+                weight: 250,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 190,
+                weight: 220,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1750,
+                weight: 170,
+                ..Default::default()
+            },
         ]
     }
 
     fn setup_options(target_value: u64) -> CoinSelectionOpt {
         CoinSelectionOpt {
             target_value,
-            target_feerate: 0.4, // Simplified feerate
-            long_term_feerate: Some(0.4),
+            target_feerate: FeeRate::from_sat_per_wu(0.4), // Simplified feerate
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(0.4)),
             min_absolute_fee: 0,
             base_weight: 10,
             change_weight: 50,
             change_cost: 10,
             min_change_value: 500,
             excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
         }
     }
 
@@ -136,6 +243,60 @@ mod test {
         assert!(!selection_output.selected_inputs.is_empty());
     }
 
+    #[test]
+    fn test_lowestlarger_prefers_lighter_inputs_on_ties() {
+        // Both coins are worth 10060 sats after fees; the lighter one is the lowest larger.
+        let inputs = vec![
+            OutputGroup {
+                value: 10_140,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 10_100,
+                weight: 100,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_lowestlarger(&inputs, &setup_options(10_000)).unwrap();
+        assert_eq!(result.selected_inputs, vec![1]);
+
+        // Two 1000 and two 2000 sat coins after fees: the 3024 sat target needs both small ones
+        // and the lighter of the large ones.
+        let inputs = vec![
+            OutputGroup {
+                value: 2_080,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1_080,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_040,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1_040,
+                weight: 100,
+                ..Default::default()
+            },
+        ];
+        let result = select_coin_lowestlarger(&inputs, &setup_options(3_000)).unwrap();
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![1, 2, 3]);
+
+        let options = CoinSelectionOpt {
+            max_input_count: Some(2),
+            ..setup_options(3_000)
+        };
+        assert!(select_coin_lowestlarger(&inputs, &options).is_err());
+    }
+
     #[test]
     fn test_lowestlarger_insufficient() {
         let inputs = setup_lowestlarger_output_groups();
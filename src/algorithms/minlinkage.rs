@@ -0,0 +1,208 @@
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::cmp::Reverse;
+
+use crate::{
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, exceeds_max_input_count, funding_target, insufficient_funds,
+        select_with_preselected, selection_output, unfunded_error, PreparedInputs,
+        PreparedOutputGroup, SelectionContext,
+    },
+};
+
+/// Performs privacy-aware coin selection that links as few ownership clusters as possible.
+///
+/// Spending inputs together publicly links them, so every additional cluster in a transaction
+/// reveals common ownership that was previously hidden. Clusters come from
+/// [`OutputGroup::cluster`]; groups without a label are each treated as a cluster of their own.
+///
+/// 1. If any single cluster can fund the payment, the candidate with the lowest waste among those
+///    clusters is returned, so no new linkage is created at all.
+/// 2. Otherwise clusters are merged in order of descending total effective value until they cover
+///    the target, which touches the fewest clusters possible.
+///
+/// Within the chosen cluster(s), inputs are spent largest-first.
+pub fn select_coin_minlinkage(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, select_coin_minlinkage);
+    }
+    select_coin_minlinkage_with_context(inputs, options, &SelectionContext::new(inputs, options)?)
+}
+
+/// [`select_coin_minlinkage`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_minlinkage_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    let inputs = &context.inputs;
+    // Effective values already net out per-input fees, so the target only needs the base fee.
+    let target = funding_target(options, true);
+
+    // Bucket the inputs by cluster; unlabeled groups become singleton clusters. The context walks
+    // them largest-first, so every cluster comes out sorted that way.
+    let mut clusters: Vec<Vec<&PreparedOutputGroup>> = Vec::new();
+    let mut cluster_slots: BTreeMap<u32, usize> = BTreeMap::new();
+    for input in inputs {
+        match input.cluster {
+            Some(label) => {
+                let slot = *cluster_slots.entry(label).or_insert_with(|| {
+                    clusters.push(Vec::new());
+                    clusters.len() - 1
+                });
+                clusters[slot].push(input);
+            }
+            None => clusters.push(vec![input]),
+        }
+    }
+
+    // Candidate 1: the least wasteful cluster that can fund the payment on its own.
+    let mut best: Option<SelectionOutput> = None;
+    for cluster in &clusters {
+        if let Some(candidate) = accumulate_largest_first(inputs, cluster, target, options)? {
+            if best
+                .as_ref()
+                .is_none_or(|best| candidate.waste < best.waste)
+            {
+                best = Some(candidate);
+            }
+        }
+    }
+    if let Some(best) = best {
+        return Ok(best);
+    }
+
+    // Candidate 2: merge the richest clusters until they jointly cover the payment.
+    clusters.sort_by_key(|cluster| Reverse(cluster.iter().map(|input| input.value).sum::<u64>()));
+    let mut merged: Vec<&PreparedOutputGroup> = Vec::new();
+    for cluster in clusters {
+        merged.extend(cluster);
+        merged.sort_by_key(|input| (Reverse(input.value), input.weight, input.index));
+        if let Some(candidate) = accumulate_largest_first(inputs, &merged, target, options)? {
+            return Ok(candidate);
+        }
+    }
+
+    Err(unfunded_error(options, insufficient_funds_error))
+}
+
+/// Accumulates the (descending) `inputs` until they cover `target`, or returns `None` if they can't
+/// within [`CoinSelectionOpt::max_input_count`].
+/// `prepared` is the full working set the inputs were drawn from.
+fn accumulate_largest_first(
+    prepared: &PreparedInputs,
+    inputs: &[&PreparedOutputGroup],
+    target: u64,
+    options: &CoinSelectionOpt,
+) -> Result<Option<SelectionOutput>, SelectionError> {
+    let mut accumulated_value: u64 = 0;
+    let mut accumulated_weight: u64 = 0;
+    let mut accumulated_input_count: usize = 0;
+    let mut selected_inputs: Vec<usize> = Vec::new();
+    for input in inputs {
+        accumulated_input_count += input.input_count;
+        if exceeds_max_input_count(options, accumulated_input_count) {
+            return Ok(None);
+        }
+        accumulated_value += input.value;
+        accumulated_weight += input.weight;
+        selected_inputs.push(input.index);
+        if accumulated_value >= target {
+            let (fee, waste) =
+                calculate_fee_and_waste(options, accumulated_value, accumulated_weight)?;
+            return Ok(Some(selection_output(
+                options,
+                prepared,
+                selected_inputs,
+                fee,
+                waste,
+                true,
+                None,
+            )?));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::minlinkage::select_coin_minlinkage,
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        },
+    };
+
+    fn clustered_output_group(value: u64, cluster: Option<u32>) -> OutputGroup {
+        OutputGroup {
+            value,
+            cluster,
+            ..Default::default()
+        }
+    }
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value,
+            target_feerate: FeeRate::from_sat_per_wu(1.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
+            min_absolute_fee: 0,
+            base_weight: 0,
+            change_weight: 50,
+            change_cost: 20,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_minlinkage_prefers_single_cluster() {
+        // Two unrelated 6000 coins would fund the payment with fewer inputs, but would link two
+        // clusters; cluster 7 can pay on its own.
+        let inputs = vec![
+            clustered_output_group(6_000, None),
+            clustered_output_group(6_000, None),
+            clustered_output_group(4_000, Some(7)),
+            clustered_output_group(4_000, Some(7)),
+            clustered_output_group(3_000, Some(7)),
+        ];
+        let result = select_coin_minlinkage(&inputs, &setup_options(10_000)).unwrap();
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_minlinkage_merges_fewest_clusters() {
+        // No cluster covers 8000 alone; the two richest clusters do, the third must stay untouched.
+        let inputs = vec![
+            clustered_output_group(3_000, Some(1)),
+            clustered_output_group(2_000, Some(1)),
+            clustered_output_group(4_000, Some(2)),
+            clustered_output_group(1_000, Some(3)),
+        ];
+        let result = select_coin_minlinkage(&inputs, &setup_options(8_000)).unwrap();
+        let mut selected = result.selected_inputs;
+        selected.sort();
+        assert_eq!(selected, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_minlinkage_insufficient_funds() {
+        let inputs = vec![
+            clustered_output_group(3_000, Some(1)),
+            clustered_output_group(4_000, None),
+        ];
+        let result = select_coin_minlinkage(&inputs, &setup_options(10_000));
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
@@ -1,4 +1,17 @@
+pub mod annealing;
+pub mod blackjack;
 pub mod bnb;
 pub mod coingrinder;
+pub mod consolidate;
+pub mod fewestinputs;
 pub mod fifo;
+pub mod genetic;
+pub mod knapsack;
 pub mod lowestlarger;
+pub mod minlinkage;
+pub mod srd;
+pub mod sweep;
+
+/// Bitcoin Core's coin selection test vectors.
+#[cfg(test)]
+mod core_vectors;
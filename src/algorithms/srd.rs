@@ -0,0 +1,232 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{
+        calculate_fee_and_waste, candidate_score, check_cancelled, exceeds_max_input_count,
+        funding_target, insufficient_funds, select_with_preselected, selection_output,
+        unfunded_error, Deadline, SelectionContext, SplitMix64,
+    },
+};
+
+/// Draw parameters for [`select_coin_srd_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SrdParams {
+    /// Number of independent random draws, of which the lowest-waste one is kept. At least one
+    /// draw is always made.
+    pub draws: usize,
+    /// Seed of the pseudo random generator, so that runs are reproducible.
+    pub seed: u64,
+}
+
+impl Default for SrdParams {
+    fn default() -> Self {
+        SrdParams { draws: 8, seed: 0 }
+    }
+}
+
+/// A draw that covered the target: its inclusion mask over the prepared inputs and its totals.
+struct Draw {
+    selected: Vec<bool>,
+    value: u64,
+    weight: u64,
+    score: i64,
+}
+
+/// Performs coin selection via Single Random Draw with the default [`SrdParams`], seeded from
+/// [`CoinSelectionOpt::seed`] when it is set.
+pub fn select_coin_srd(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    select_coin_srd_with(inputs, options, &seeded_params(options))
+}
+
+/// [`select_coin_srd`] over an already prepared `context`, without preselected inputs.
+pub(crate) fn select_coin_srd_with_context(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    draw_best(inputs, options, &seeded_params(options), context)
+}
+
+/// The default [`SrdParams`], seeded from [`CoinSelectionOpt::seed`] when it is set.
+fn seeded_params(options: &CoinSelectionOpt) -> SrdParams {
+    let defaults = SrdParams::default();
+    SrdParams {
+        seed: options.seed.unwrap_or(defaults.seed),
+        ..defaults
+    }
+}
+
+/// Performs coin selection via Single Random Draw, repeated `params.draws` times.
+///
+/// Each draw adds the eligible inputs in a random order until they cover the target and the fee of
+/// the transaction with its change output, as Bitcoin Core's SRD does. A single draw often spends
+/// needlessly many or large inputs, so the draws are independent and the one with the lowest waste,
+/// or [`CoinSelectionOpt::selection_metric`] score when it is set, is kept. Draws breaking
+/// [`CoinSelectionOpt::max_input_count`] are discarded.
+///
+/// SRD is not run by [`crate::selectcoin::select_coin`]; request it through
+/// [`crate::selectcoin::select_coin_with`].
+pub fn select_coin_srd_with(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &SrdParams,
+) -> Result<SelectionOutput, SelectionError> {
+    if !options.preselected_inputs.is_empty() {
+        return select_with_preselected(inputs, options, true, |inputs, options| {
+            select_coin_srd_with(inputs, options, params)
+        });
+    }
+    draw_best(
+        inputs,
+        options,
+        params,
+        &SelectionContext::new(inputs, options)?,
+    )
+}
+
+fn draw_best(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    params: &SrdParams,
+    context: &SelectionContext,
+) -> Result<SelectionOutput, SelectionError> {
+    let insufficient_funds_error = insufficient_funds(inputs, options);
+    let original_inputs = inputs;
+    let inputs = &context.inputs;
+    let target = funding_target(options, true);
+
+    if context.total_value() < target {
+        return Err(insufficient_funds_error);
+    }
+
+    let mut rng = SplitMix64::new(params.seed);
+    let mut order: Vec<usize> = (0..inputs.len()).collect();
+    let mut best: Option<Draw> = None;
+    let deadline = Deadline::start(options);
+    for draw in 0..params.draws.max(1) {
+        check_cancelled(options)?;
+        if draw > 0 && deadline.has_passed() {
+            break;
+        }
+        // Fisher-Yates shuffle, restarting from the previous draw's order.
+        for i in (1..order.len()).rev() {
+            order.swap(i, rng.below(i + 1));
+        }
+        let mut selected = vec![false; inputs.len()];
+        let (mut value, mut weight, mut input_count) = (0u64, 0u64, 0usize);
+        for &position in &order {
+            if value >= target {
+                break;
+            }
+            let input = &inputs[position];
+            selected[position] = true;
+            value += input.value;
+            weight += input.weight;
+            input_count += input.input_count;
+        }
+        if exceeds_max_input_count(options, input_count) {
+            continue;
+        }
+        // An infeasible draw is kept only while no feasible one is found.
+        let score = candidate_score(original_inputs, inputs, &selected, value, weight, options)?
+            .unwrap_or(i64::MAX);
+        if best.as_ref().is_none_or(|best| score < best.score) {
+            best = Some(Draw {
+                selected,
+                value,
+                weight,
+                score,
+            });
+        }
+    }
+
+    let Some(best) = best else {
+        return Err(unfunded_error(options, insufficient_funds_error));
+    };
+    let (fee, waste) = calculate_fee_and_waste(options, best.value, best.weight)?;
+    let selected_inputs: Vec<usize> = inputs
+        .iter()
+        .zip(&best.selected)
+        .filter(|(_, &selected)| selected)
+        .map(|(input, _)| input.index)
+        .collect();
+    selection_output(options, inputs, selected_inputs, fee, waste, true, None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::srd::{select_coin_srd, select_coin_srd_with, SrdParams},
+        types::{CoinSelectionOpt, FeeRate, OutputGroup, SelectionError},
+    };
+
+    fn setup_inputs() -> Vec<OutputGroup> {
+        (1..=40u64)
+            .map(|i| OutputGroup {
+                value: i * 7_919 % 50_003 + 1_000,
+                weight: 272,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt::builder()
+            .target_value(target_value)
+            .target_feerate(FeeRate::from_sat_per_vb(2.0))
+            .long_term_feerate(FeeRate::from_sat_per_vb(1.0))
+            .base_weight(42)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_srd_more_draws_never_waste_more() {
+        let inputs = setup_inputs();
+        let options = setup_options(120_000);
+        let single = SrdParams { draws: 1, seed: 3 };
+        let many = SrdParams { draws: 32, seed: 3 };
+        // The first of the 32 draws is the single draw, so the best of them is at least as good.
+        let single = select_coin_srd_with(&inputs, &options, &single).unwrap();
+        let best = select_coin_srd_with(&inputs, &options, &many).unwrap();
+        assert!(best.waste <= single.waste);
+        let value: u64 = best.selected_inputs.iter().map(|&i| inputs[i].value).sum();
+        assert!(value >= options.target_value + best.fee);
+    }
+
+    #[test]
+    fn test_srd_uses_options_seed() {
+        let inputs = setup_inputs();
+        let mut options = setup_options(120_000);
+        options.seed = Some(11);
+        let seeded = select_coin_srd(&inputs, &options).unwrap();
+        let explicit = select_coin_srd_with(
+            &inputs,
+            &options,
+            &SrdParams {
+                seed: 11,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(seeded.selected_inputs, explicit.selected_inputs);
+        assert_eq!(seeded.waste, explicit.waste);
+    }
+
+    #[test]
+    fn test_srd_insufficient_funds() {
+        let inputs = vec![OutputGroup {
+            value: 1_000,
+            weight: 272,
+            ..Default::default()
+        }];
+        assert!(matches!(
+            select_coin_srd(&inputs, &setup_options(10_000)),
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
@@ -0,0 +1,298 @@
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::{
+    types::{CoinSelectionOpt, Constraint, OutputGroup, SelectionError, SweepOutput},
+    utils::{
+        calculate_cpfp_fee, calculate_fee, check_input_counts, check_overflow,
+        exceeds_max_input_count, is_eligible, is_locked, mixes_labels, resolve_inputs,
+        spends_reserve, target_feerate, validate_feerates, TOO_MANY_INPUTS,
+    },
+};
+
+/// Selects every economically spendable input and computes the maximum amount that can be sent.
+///
+/// An input is economically spendable when its value exceeds the fee for spending it at
+/// `target_feerate`; anything else would reduce the amount sent. If
+/// [`CoinSelectionOpt::dust_threshold`] is set, the surplus must reach it as well. The whole
+/// remainder goes to the recipient, so no change output is created and `target_value`,
+/// `change_weight`, and the excess strategy are ignored. The fee covers `base_weight` plus all selected inputs and is never
+/// below `min_absolute_fee`, plus the CPFP bump owed for any unconfirmed ancestors.
+///
+/// With [`CoinSelectionOpt::max_input_count`] set, only the groups adding the most to the amount
+/// sent are kept, as many as fit under the cap. [`CoinSelectionOpt::preselected_inputs`] are
+/// always spent, whether or not they are economical; [`OutputGroup::frozen`] groups never are.
+/// A sweep spends every economical group, so it fails with [`Constraint::Reserve`] under any
+/// [`CoinSelectionOpt::reserve`] beyond what the input cap leaves unspent, and with
+/// [`Constraint::MixedLabels`] when those groups carry labels the
+/// [`CoinSelectionOpt::label_policy`] keeps apart.
+///
+/// This is the computation behind a wallet's "send max" button.
+///
+/// Returns [`SelectionError::InsufficientFunds`] when nothing is left for the recipient.
+pub fn select_coin_sweep(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SweepOutput, SelectionError> {
+    validate_feerates(options)?;
+    check_input_counts(inputs)?;
+    check_overflow(inputs, options)?;
+    let inputs = &*resolve_inputs(inputs, options);
+    if options
+        .preselected_inputs
+        .iter()
+        .any(|&index| index >= inputs.len())
+    {
+        return Err(SelectionError::PreselectedInputOutOfRange);
+    }
+    if options
+        .preselected_inputs
+        .iter()
+        .any(|&index| inputs[index].frozen)
+    {
+        return Err(SelectionError::PreselectedInputFrozen);
+    }
+    if options
+        .preselected_inputs
+        .iter()
+        .any(|&index| is_locked(&inputs[index], options))
+    {
+        return Err(SelectionError::PreselectedInputLocked);
+    }
+
+    let mut spendable = Vec::new();
+    let mut preselected_input_count = 0;
+    for (index, input) in inputs.iter().enumerate() {
+        let preselected = options.preselected_inputs.contains(&index);
+        if !preselected && !is_eligible(input, options) {
+            continue;
+        }
+        let input_bump_fee = calculate_cpfp_fee(
+            input.ancestor_weight,
+            input.ancestor_fee,
+            target_feerate(options),
+        );
+        let spend_fee = calculate_fee(input.weight, target_feerate(options)) + input_bump_fee;
+        if preselected {
+            preselected_input_count += input.input_count;
+        }
+        let net_value = input.value.saturating_sub(spend_fee);
+        let economical = net_value > 0
+            && options
+                .dust_threshold
+                .is_none_or(|dust_threshold| net_value >= dust_threshold);
+        if preselected || economical {
+            spendable.push((index, preselected, net_value, input_bump_fee));
+        }
+    }
+    if exceeds_max_input_count(options, preselected_input_count) {
+        return Err(TOO_MANY_INPUTS);
+    }
+    let (spendable_input_count, spendable_value) =
+        spendable
+            .iter()
+            .fold((0, 0), |(input_count, value), &(index, ..)| {
+                (
+                    input_count + inputs[index].input_count,
+                    value + inputs[index].value,
+                )
+            });
+    if options.max_input_count.is_some() {
+        // Keep the preselected groups, then the ones that add the most to the amount sent while
+        // they fit under the cap.
+        spendable.sort_by_key(|&(index, preselected, net_value, _)| {
+            (Reverse(preselected), Reverse(net_value), index)
+        });
+        let mut input_count = 0;
+        spendable.retain(|&(index, _, _, _)| {
+            let fits = !exceeds_max_input_count(options, input_count + inputs[index].input_count);
+            if fits {
+                input_count += inputs[index].input_count;
+            }
+            fits
+        });
+        spendable.sort_by_key(|&(index, _, _, _)| index);
+    }
+
+    let mut selected_inputs = Vec::with_capacity(spendable.len());
+    let mut total_value: u64 = 0;
+    let mut total_weight: u64 = options.base_weight;
+    let mut bump_fee: u64 = 0;
+    for (index, _, _, input_bump_fee) in spendable {
+        selected_inputs.push(index);
+        total_value += inputs[index].value;
+        total_weight += inputs[index].weight;
+        bump_fee += input_bump_fee;
+    }
+
+    let input_count: usize = selected_inputs
+        .iter()
+        .map(|&index| inputs[index].input_count)
+        .sum();
+    if spends_reserve(
+        options,
+        spendable_input_count - input_count,
+        spendable_value - total_value,
+    ) {
+        return Err(SelectionError::NoSolutionFound {
+            constraint: Constraint::Reserve,
+        });
+    }
+    if mixes_labels(options, selected_inputs.iter().map(|&index| &inputs[index])) {
+        return Err(SelectionError::NoSolutionFound {
+            constraint: Constraint::MixedLabels,
+        });
+    }
+
+    let fee = calculate_fee(total_weight, target_feerate(options)).max(options.min_absolute_fee)
+        + bump_fee;
+    if total_value <= fee {
+        return Err(SelectionError::InsufficientFunds {
+            available: total_value,
+            required: fee + 1,
+        });
+    }
+
+    Ok(SweepOutput {
+        selected_inputs,
+        fee,
+        max_sendable: total_value - fee,
+    })
+}
+
+/// Returns the largest amount that can be sent from `inputs` at `target_feerate`.
+///
+/// Shorthand for the `max_sendable` of [`select_coin_sweep`].
+pub fn max_sendable(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<u64, SelectionError> {
+    select_coin_sweep(inputs, options).map(|sweep| sweep.max_sendable)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithms::sweep::{max_sendable, select_coin_sweep},
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, SelectionError,
+        },
+    };
+
+    fn setup_options() -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value: 0,
+            target_feerate: FeeRate::from_sat_per_wu(2.0),
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(1.0)),
+            min_absolute_fee: 0,
+            base_weight: 40,
+            change_weight: 50,
+            change_cost: 20,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sweep_skips_uneconomical_inputs() {
+        let inputs = vec![
+            OutputGroup {
+                value: 10_000,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 300,
+                weight: 200,
+                ..Default::default()
+            }, // Costs 400 sats to spend.
+            OutputGroup {
+                value: 5_000,
+                weight: 100,
+                ..Default::default()
+            },
+        ];
+        let sweep = select_coin_sweep(&inputs, &setup_options()).unwrap();
+        assert_eq!(sweep.selected_inputs, vec![0, 2]);
+        // (40 + 200 + 100) * 2
+        assert_eq!(sweep.fee, 680);
+        assert_eq!(sweep.max_sendable, 15_000 - 680);
+    }
+
+    #[test]
+    fn test_sweep_keeps_most_valuable_under_max_input_count() {
+        let inputs = vec![
+            OutputGroup {
+                value: 5_000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 10_000,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 4_000,
+                weight: 100,
+                ..Default::default()
+            },
+        ];
+        let mut options = setup_options();
+        options.max_input_count = Some(2);
+        let sweep = select_coin_sweep(&inputs, &options).unwrap();
+        assert_eq!(sweep.selected_inputs, vec![0, 1]);
+        // (40 + 100 + 200) * 2
+        assert_eq!(sweep.max_sendable, 15_000 - 680);
+    }
+
+    #[test]
+    fn test_sweep_spends_preselected_inputs() {
+        let inputs = vec![
+            OutputGroup {
+                value: 10_000,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 300,
+                weight: 200,
+                ..Default::default()
+            }, // Costs 400 sats to spend.
+        ];
+        let mut options = setup_options();
+        options.preselected_inputs = vec![1];
+        let sweep = select_coin_sweep(&inputs, &options).unwrap();
+        assert_eq!(sweep.selected_inputs, vec![0, 1]);
+        // (40 + 200 + 200) * 2
+        assert_eq!(sweep.max_sendable, 10_300 - 880);
+    }
+
+    #[test]
+    fn test_max_sendable_respects_min_absolute_fee() {
+        let inputs = vec![OutputGroup {
+            value: 10_000,
+            weight: 200,
+            ..Default::default()
+        }];
+        let mut options = setup_options();
+        options.min_absolute_fee = 1_000;
+        assert_eq!(max_sendable(&inputs, &options).unwrap(), 9_000);
+    }
+
+    #[test]
+    fn test_sweep_nothing_spendable() {
+        let inputs = vec![OutputGroup {
+            value: 300,
+            weight: 200,
+            ..Default::default()
+        }];
+        let result = select_coin_sweep(&inputs, &setup_options());
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
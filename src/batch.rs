@@ -0,0 +1,205 @@
+//! Batching payouts into a single transaction, as exchange wallets do with pending withdrawals.
+//!
+//! Paying every withdrawal in its own transaction pays for a header, inputs and a change output
+//! each time. A batch merges the pending payouts into one transaction with an output per payout,
+//! runs the selection once for their total, and splits the fee back over the payouts, so that each
+//! withdrawal can be charged its share.
+//!
+//! Each payout is charged the fee of its own output at the target feerate, rounded down, and an
+//! even share of the rest: the header and other outputs counted in
+//! [`CoinSelectionOpt::base_weight`], the inputs, the change output and any CPFP bump. The shares
+//! add up to the fee of the transaction, sat for sat.
+
+use alloc::vec::Vec;
+
+use crate::{
+    coinswap::split_target,
+    selectcoin::select_coin,
+    types::{
+        Algorithm, CoinSelectionOpt, OutputGroup, ScriptType, SelectionError, SelectionOutput,
+    },
+    utils::target_feerate,
+};
+
+/// A pending payout: `value` sats to an output weighing `weight` WU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Payout {
+    /// Value paid, in sats.
+    pub value: u64,
+    /// Weight of the payout's output: its amount, script length and script pubkey.
+    pub weight: u64,
+}
+
+impl Payout {
+    /// A payout of `value` sats to an output of `script_type`.
+    pub const fn new(value: u64, script_type: ScriptType) -> Self {
+        Payout {
+            value,
+            weight: script_type.output_weight(),
+        }
+    }
+}
+
+/// The selection funding a batch of payouts, with the fee attributed to each of them.
+#[derive(Debug)]
+pub struct BatchSelection {
+    /// The algorithm whose selection ranked first.
+    pub algorithm: Algorithm,
+    /// The selection, for the whole batch transaction.
+    pub output: SelectionOutput,
+    /// Fee attributed to each payout, in the order of the payouts. Adds up to `output.fee`.
+    pub payout_fees: Vec<u64>,
+}
+
+/// Funds `payouts` together in one transaction, with the best-ranked selection of
+/// [`select_coin`].
+///
+/// The transaction is funded as described by `options`, with `target_value` replaced by the total
+/// of the payouts and their outputs added to `base_weight`, which should then count the header and
+/// any outputs besides the payouts. With [`crate::types::ExcessStrategy::ToRecipient`], the excess
+/// goes to whichever payout the caller picks; it is not part of the attributed fees.
+///
+/// Returns [`SelectionError::NonPositiveTarget`] when `payouts` is empty or pays nothing, and
+/// [`SelectionError::ArithmeticOverflow`] when their values or weights do not fit in a `u64`.
+pub fn select_coin_batch(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    payouts: &[Payout],
+) -> Result<BatchSelection, SelectionError> {
+    let target_value = payouts
+        .iter()
+        .try_fold(0u64, |total, payout| total.checked_add(payout.value))
+        .ok_or(SelectionError::ArithmeticOverflow)?;
+    let base_weight = payouts
+        .iter()
+        .try_fold(options.base_weight, |total, payout| {
+            total.checked_add(payout.weight)
+        })
+        .ok_or(SelectionError::ArithmeticOverflow)?;
+    let options = CoinSelectionOpt {
+        target_value,
+        base_weight,
+        ..options.clone()
+    };
+    let (algorithm, output) = select_coin(inputs, &options)?
+        .into_iter()
+        .next()
+        .ok_or(SelectionError::NonPositiveTarget)?;
+    let payout_fees = attribute_fee(output.fee, payouts, &options);
+    Ok(BatchSelection {
+        algorithm,
+        output,
+        payout_fees,
+    })
+}
+
+/// Splits `fee` over `payouts`: the fee of each payout's output, rounded down so that their sum
+/// never exceeds `fee`, plus an even share of the remainder.
+fn attribute_fee(fee: u64, payouts: &[Payout], options: &CoinSelectionOpt) -> Vec<u64> {
+    let output_fees: Vec<u64> = payouts
+        .iter()
+        .map(|payout| {
+            payout
+                .weight
+                .saturating_mul(target_feerate(options).as_sat_per_kwu())
+                / 1000
+        })
+        .collect();
+    let shared = fee.saturating_sub(output_fees.iter().copied().fold(0, u64::saturating_add));
+    output_fees
+        .into_iter()
+        .zip(split_target(shared, payouts.len()))
+        .map(|(output_fee, share)| output_fee + share)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        batch::{select_coin_batch, Payout},
+        selectcoin::select_coin,
+        types::{CoinSelectionOpt, FeeRate, OutputGroup, ScriptType, SelectionError},
+    };
+
+    fn setup_inputs() -> Vec<OutputGroup> {
+        [250_000, 180_000, 120_000, 90_000, 40_000]
+            .into_iter()
+            .map(|value| OutputGroup {
+                value,
+                weight: 272,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn setup_options() -> CoinSelectionOpt {
+        CoinSelectionOpt::builder()
+            .target_value(1)
+            .target_feerate(FeeRate::from_sat_per_vb(5.0))
+            .base_weight(42)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_select_coin_batch_attributes_the_fee() {
+        let inputs = setup_inputs();
+        let options = setup_options();
+        let payouts = [
+            Payout::new(100_000, ScriptType::P2wpkh),
+            Payout::new(75_000, ScriptType::P2tr),
+            Payout::new(30_000, ScriptType::P2pkh),
+        ];
+        let batch = select_coin_batch(&inputs, &options, &payouts).unwrap();
+        assert!(batch.output.selected_value >= 205_000 + batch.output.fee);
+        assert_eq!(batch.payout_fees.len(), 3);
+        assert_eq!(batch.payout_fees.iter().sum::<u64>(), batch.output.fee);
+        // The taproot output weighs more than the P2WPKH one, so its payout pays more.
+        assert!(batch.payout_fees[1] > batch.payout_fees[0]);
+
+        // One transaction costs less than paying each withdrawal separately.
+        let separate: u64 = payouts
+            .iter()
+            .map(|payout| {
+                let options = CoinSelectionOpt {
+                    target_value: payout.value,
+                    base_weight: options.base_weight + payout.weight,
+                    ..options.clone()
+                };
+                select_coin(&inputs, &options).unwrap()[0].1.fee
+            })
+            .sum();
+        assert!(batch.output.fee < separate);
+    }
+
+    #[test]
+    fn test_select_coin_batch_empty() {
+        assert!(matches!(
+            select_coin_batch(&setup_inputs(), &setup_options(), &[]),
+            Err(SelectionError::NonPositiveTarget)
+        ));
+    }
+
+    #[test]
+    fn test_select_coin_batch_overflow() {
+        let inputs = setup_inputs();
+        let options = setup_options();
+        let values = [
+            Payout::new(u64::MAX, ScriptType::P2wpkh),
+            Payout::new(1, ScriptType::P2wpkh),
+        ];
+        assert!(matches!(
+            select_coin_batch(&inputs, &options, &values),
+            Err(SelectionError::ArithmeticOverflow)
+        ));
+        let weights = [Payout {
+            value: 10_000,
+            weight: u64::MAX - 1,
+        }];
+        assert!(matches!(
+            select_coin_batch(&inputs, &options, &weights),
+            Err(SelectionError::ArithmeticOverflow)
+        ));
+    }
+}
@@ -0,0 +1,448 @@
+//! Conversions between this crate's plain integer units and the `bitcoin` crate's typed units.
+//!
+//! Every method here mirrors a field or constructor that takes sats or weight units as `u64`,
+//! and carries a `bitcoin_` prefix so both forms can be used side by side. [`CoinPool`] turns a
+//! wallet's `(OutPoint, TxOut)` coins into [`OutputGroup`]s and maps selections back to them,
+//! and [`fund_psbt`] picks the coins that complete a partially constructed [`Psbt`].
+
+use std::fmt;
+
+use bitcoin::{psbt, Amount, OutPoint, Psbt, Script, TxOut, Weight};
+
+use crate::{
+    selectcoin::select_coin,
+    types::{
+        CoinSelectionOpt, CoinSelectionOptBuilder, FeeRate, OutputGroup, ScriptType,
+        SelectionError, SelectionOutput,
+    },
+};
+
+impl From<bitcoin::FeeRate> for FeeRate {
+    fn from(feerate: bitcoin::FeeRate) -> Self {
+        FeeRate::from_sat_per_kwu(feerate.to_sat_per_kwu())
+    }
+}
+
+impl From<FeeRate> for bitcoin::FeeRate {
+    fn from(feerate: FeeRate) -> Self {
+        bitcoin::FeeRate::from_sat_per_kwu(feerate.as_sat_per_kwu())
+    }
+}
+
+impl OutputGroup {
+    /// Creates a group holding a single input of the given value and spending weight.
+    pub fn from_bitcoin(value: Amount, weight: Weight) -> Self {
+        OutputGroup {
+            value: value.to_sat(),
+            weight: weight.to_wu(),
+            input_count: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a group holding the single coin `txout`, whose weight follows from the
+    /// `script_type` of its script pubkey.
+    pub fn from_txout(txout: &TxOut, script_type: ScriptType) -> Self {
+        OutputGroup::from_script_type(txout.value.to_sat(), script_type, 1)
+    }
+
+    /// [`OutputGroup::value`] as an [`Amount`].
+    pub fn bitcoin_value(&self) -> Amount {
+        Amount::from_sat(self.value)
+    }
+
+    /// [`OutputGroup::weight`] as a [`Weight`].
+    pub fn bitcoin_weight(&self) -> Weight {
+        Weight::from_wu(self.weight)
+    }
+}
+
+impl CoinSelectionOpt {
+    /// [`CoinSelectionOpt::target_value`] as an [`Amount`].
+    pub fn bitcoin_target_value(&self) -> Amount {
+        Amount::from_sat(self.target_value)
+    }
+
+    /// [`CoinSelectionOpt::target_feerate`] as a [`bitcoin::FeeRate`].
+    pub fn bitcoin_target_feerate(&self) -> bitcoin::FeeRate {
+        self.target_feerate.into()
+    }
+}
+
+impl CoinSelectionOptBuilder {
+    /// Sets [`CoinSelectionOpt::target_value`] from an [`Amount`].
+    pub fn bitcoin_target_value(self, target_value: Amount) -> Self {
+        self.target_value(target_value.to_sat())
+    }
+
+    /// Sets [`CoinSelectionOpt::min_absolute_fee`] from an [`Amount`].
+    pub fn bitcoin_min_absolute_fee(self, min_absolute_fee: Amount) -> Self {
+        self.min_absolute_fee(min_absolute_fee.to_sat())
+    }
+
+    /// Sets [`CoinSelectionOpt::base_weight`] from a [`Weight`].
+    pub fn bitcoin_base_weight(self, base_weight: Weight) -> Self {
+        self.base_weight(base_weight.to_wu())
+    }
+
+    /// Sets [`CoinSelectionOpt::change_weight`] from a [`Weight`].
+    pub fn bitcoin_change_weight(self, change_weight: Weight) -> Self {
+        self.change_weight(change_weight.to_wu())
+    }
+
+    /// Sets [`CoinSelectionOpt::change_cost`] from an [`Amount`] instead of deriving it.
+    pub fn bitcoin_change_cost(self, change_cost: Amount) -> Self {
+        self.change_cost(change_cost.to_sat())
+    }
+
+    /// Sets [`CoinSelectionOpt::min_change_value`] from an [`Amount`].
+    pub fn bitcoin_min_change_value(self, min_change_value: Amount) -> Self {
+        self.min_change_value(min_change_value.to_sat())
+    }
+}
+
+impl SelectionOutput {
+    /// [`SelectionOutput::fee`] as an [`Amount`].
+    pub fn bitcoin_fee(&self) -> Amount {
+        Amount::from_sat(self.fee)
+    }
+
+    /// [`SelectionOutput::selected_value`] as an [`Amount`].
+    pub fn bitcoin_selected_value(&self) -> Amount {
+        Amount::from_sat(self.selected_value)
+    }
+
+    /// [`SelectionOutput::selected_weight`] as a [`Weight`].
+    pub fn bitcoin_selected_weight(&self) -> Weight {
+        Weight::from_wu(self.selected_weight)
+    }
+
+    /// [`SelectionOutput::effective_feerate`] as a [`bitcoin::FeeRate`].
+    pub fn bitcoin_effective_feerate(&self) -> bitcoin::FeeRate {
+        self.effective_feerate.into()
+    }
+}
+
+/// A wallet's coins as [`OutputGroup`]s, one per coin, along with their outpoints.
+///
+/// `groups` is the input list to pass to the selection functions; the outpoint of `groups[i]` is
+/// `outpoints[i]`, so [`CoinPool::selected_outpoints`] maps a selection straight back to the coins
+/// to spend.
+#[derive(Debug, Clone, Default)]
+pub struct CoinPool {
+    /// The coins as single-input groups.
+    pub groups: Vec<OutputGroup>,
+    /// The outpoint of each group.
+    pub outpoints: Vec<OutPoint>,
+}
+
+impl CoinPool {
+    /// Creates an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Converts coins that all have the same `script_type`.
+    pub fn from_txouts(
+        coins: impl IntoIterator<Item = (OutPoint, TxOut)>,
+        script_type: ScriptType,
+    ) -> Self {
+        let mut pool = CoinPool::new();
+        for (outpoint, txout) in coins {
+            pool.push(outpoint, &txout, script_type);
+        }
+        pool
+    }
+
+    /// Adds the coin `txout` at `outpoint`, see [`OutputGroup::from_txout`].
+    pub fn push(&mut self, outpoint: OutPoint, txout: &TxOut, script_type: ScriptType) {
+        self.groups
+            .push(OutputGroup::from_txout(txout, script_type));
+        self.outpoints.push(outpoint);
+    }
+
+    /// The outpoints of the coins `output` selected from [`CoinPool::groups`].
+    pub fn selected_outpoints(&self, output: &SelectionOutput) -> Vec<OutPoint> {
+        output
+            .selected_inputs
+            .iter()
+            .map(|&index| self.outpoints[index])
+            .collect()
+    }
+}
+
+impl ScriptType {
+    /// The type of an output paying to `script_pubkey`, or `None` for scripts whose spending
+    /// weight cannot be told from the script pubkey alone, such as P2SH and P2WSH.
+    pub fn from_script_pubkey(script_pubkey: &Script) -> Option<Self> {
+        if script_pubkey.is_p2wpkh() {
+            Some(ScriptType::P2wpkh)
+        } else if script_pubkey.is_p2tr() {
+            Some(ScriptType::P2tr)
+        } else if script_pubkey.is_p2pkh() {
+            Some(ScriptType::P2pkh)
+        } else {
+            None
+        }
+    }
+}
+
+/// Errors returned by [`fund_psbt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsbtFundingError {
+    /// The PSBT input at this index has neither a `witness_utxo` nor a `non_witness_utxo`
+    /// holding its previous output, so its value is unknown.
+    MissingUtxo(usize),
+    /// The script type of the PSBT input at this index is not known, so its spending weight is
+    /// unknown.
+    UnknownInputWeight(usize),
+    /// Selecting the additional inputs failed.
+    Selection(SelectionError),
+}
+
+impl fmt::Display for PsbtFundingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PsbtFundingError::MissingUtxo(index) => {
+                write!(f, "The previous output of PSBT input {index} is missing")
+            }
+            PsbtFundingError::UnknownInputWeight(index) => {
+                write!(f, "The spending weight of PSBT input {index} is unknown")
+            }
+            PsbtFundingError::Selection(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for PsbtFundingError {}
+
+impl From<SelectionError> for PsbtFundingError {
+    fn from(err: SelectionError) -> Self {
+        PsbtFundingError::Selection(err)
+    }
+}
+
+/// The inputs [`fund_psbt`] adds to a PSBT.
+#[derive(Debug)]
+pub struct PsbtFunding {
+    /// Outpoints of the coins from the pool to add as inputs, in pool order.
+    pub new_inputs: Vec<OutPoint>,
+    /// The full selection. Its `selected_inputs` index the PSBT's existing inputs first, followed
+    /// by the pool's groups, so `fee`, `waste` and the change decision cover the whole
+    /// transaction.
+    pub selection: SelectionOutput,
+}
+
+/// Selects the coins from `pool` that complete the partially constructed `psbt`.
+///
+/// The PSBT's existing inputs are spent as preselected inputs, so only the remaining funding
+/// requirement is taken from the pool. The target is the total value of the PSBT's outputs, and
+/// `base_weight` is the weight of the unsigned transaction without its inputs, plus the segwit
+/// marker and flag; both override the values in `options`, which provides everything else. Each
+/// existing input must carry its previous output, and its spending weight is derived from its
+/// script pubkey (and, for nested segwit, its redeem script).
+pub fn fund_psbt(
+    psbt: &Psbt,
+    pool: &CoinPool,
+    options: &CoinSelectionOpt,
+) -> Result<PsbtFunding, PsbtFundingError> {
+    let mut inputs = Vec::with_capacity(psbt.inputs.len() + pool.groups.len());
+    for (index, (input, txin)) in psbt.inputs.iter().zip(&psbt.unsigned_tx.input).enumerate() {
+        let txout = previous_output(input, txin.previous_output.vout)
+            .ok_or(PsbtFundingError::MissingUtxo(index))?;
+        let script_type = psbt_input_script_type(input, txout)
+            .ok_or(PsbtFundingError::UnknownInputWeight(index))?;
+        inputs.push(OutputGroup::from_txout(txout, script_type));
+    }
+    let existing = inputs.len();
+    inputs.extend_from_slice(&pool.groups);
+
+    let mut tx = psbt.unsigned_tx.clone();
+    tx.input.clear();
+    let mut options = options.clone();
+    options.target_value = tx.output.iter().map(|output| output.value.to_sat()).sum();
+    // The segwit marker and flag are witness data, one weight unit each.
+    options.base_weight = tx.weight().to_wu() + 2;
+    options.preselected_inputs = (0..existing).collect();
+
+    let (_, selection) = select_coin(&inputs, &options)?.swap_remove(0);
+    let new_inputs = selection
+        .selected_inputs
+        .iter()
+        .filter(|&&index| index >= existing)
+        .map(|&index| pool.outpoints[index - existing])
+        .collect();
+    Ok(PsbtFunding {
+        new_inputs,
+        selection,
+    })
+}
+
+fn previous_output(input: &psbt::Input, vout: u32) -> Option<&TxOut> {
+    input.witness_utxo.as_ref().or_else(|| {
+        input
+            .non_witness_utxo
+            .as_ref()
+            .and_then(|tx| tx.output.get(vout as usize))
+    })
+}
+
+fn psbt_input_script_type(input: &psbt::Input, txout: &TxOut) -> Option<ScriptType> {
+    if txout.script_pubkey.is_p2sh() {
+        return input
+            .redeem_script
+            .as_ref()
+            .filter(|script| script.is_p2wpkh())
+            .map(|_| ScriptType::P2shP2wpkh);
+    }
+    ScriptType::from_script_pubkey(&txout.script_pubkey)
+}
+
+#[cfg(test)]
+mod test {
+    use bitcoin::{
+        absolute::LockTime, hashes::Hash, transaction::Version, Amount, OutPoint, Psbt, ScriptBuf,
+        Transaction, TxIn, TxOut, Txid, WPubkeyHash, Weight,
+    };
+
+    use crate::{
+        bitcoin::{fund_psbt, CoinPool, PsbtFundingError},
+        selectcoin::select_coin,
+        types::{CoinSelectionOpt, FeeRate, OutputGroup, ScriptType},
+    };
+
+    #[test]
+    fn test_feerate_round_trip() {
+        let feerate = bitcoin::FeeRate::from_sat_per_vb_u32(12);
+        let converted = FeeRate::from(feerate);
+        assert_eq!(converted, FeeRate::from_sat_per_vb(12.0));
+        assert_eq!(bitcoin::FeeRate::from(converted), feerate);
+    }
+
+    #[test]
+    fn test_select_with_bitcoin_units() {
+        let inputs = vec![
+            OutputGroup::from_bitcoin(Amount::from_sat(60_000), Weight::from_wu(272)),
+            OutputGroup::from_bitcoin(Amount::from_sat(50_000), Weight::from_wu(272)),
+        ];
+        assert_eq!(inputs[0].bitcoin_value(), Amount::from_sat(60_000));
+        assert_eq!(inputs[0].bitcoin_weight(), Weight::from_wu(272));
+
+        let options = CoinSelectionOpt::builder()
+            .bitcoin_target_value(Amount::from_sat(100_000))
+            .target_feerate(bitcoin::FeeRate::from_sat_per_vb_u32(2))
+            .bitcoin_base_weight(Weight::from_wu(400))
+            .build()
+            .unwrap();
+        assert_eq!(options.target_feerate, FeeRate::from_sat_per_kwu(500));
+        assert_eq!(options.bitcoin_target_value(), Amount::from_sat(100_000));
+
+        let ranked = select_coin(&inputs, &options).unwrap();
+        let (_, best) = &ranked[0];
+        assert_eq!(best.bitcoin_fee(), Amount::from_sat(best.fee));
+        assert_eq!(best.bitcoin_selected_value(), Amount::from_sat(110_000));
+        assert_eq!(best.bitcoin_selected_weight(), Weight::from_wu(544));
+    }
+
+    #[test]
+    fn test_coin_pool_maps_selection_to_outpoints() {
+        let coin = |vout, sats| {
+            (
+                OutPoint::new(Txid::all_zeros(), vout),
+                TxOut {
+                    value: Amount::from_sat(sats),
+                    script_pubkey: ScriptBuf::new(),
+                },
+            )
+        };
+        let pool = CoinPool::from_txouts(
+            [coin(0, 20_000), coin(1, 150_000), coin(2, 30_000)],
+            ScriptType::P2wpkh,
+        );
+        assert_eq!(pool.groups[1].value, 150_000);
+        assert_eq!(pool.groups[1].weight, ScriptType::P2wpkh.input_weight());
+
+        let options = CoinSelectionOpt::builder()
+            .bitcoin_target_value(Amount::from_sat(100_000))
+            .target_feerate(bitcoin::FeeRate::from_sat_per_vb_u32(2))
+            .build()
+            .unwrap();
+        let ranked = select_coin(&pool.groups, &options).unwrap();
+        assert_eq!(
+            pool.selected_outpoints(&ranked[0].1),
+            vec![OutPoint::new(Txid::all_zeros(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_fund_psbt() {
+        let p2wpkh = ScriptBuf::new_p2wpkh(&WPubkeyHash::all_zeros());
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::all_zeros(), 7),
+                ..Default::default()
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(100_000),
+                script_pubkey: p2wpkh.clone(),
+            }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        let pool = CoinPool::from_txouts(
+            [20_000, 150_000, 30_000]
+                .into_iter()
+                .enumerate()
+                .map(|(vout, sats)| {
+                    (
+                        OutPoint::new(Txid::all_zeros(), vout as u32),
+                        TxOut {
+                            value: Amount::from_sat(sats),
+                            script_pubkey: p2wpkh.clone(),
+                        },
+                    )
+                }),
+            ScriptType::P2wpkh,
+        );
+        let options = CoinSelectionOpt::builder()
+            .bitcoin_target_value(Amount::from_sat(100_000))
+            .target_feerate(bitcoin::FeeRate::from_sat_per_vb_u32(2))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            fund_psbt(&psbt, &pool, &options).unwrap_err(),
+            PsbtFundingError::MissingUtxo(0)
+        );
+
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat(90_000),
+            script_pubkey: p2wpkh.clone(),
+        });
+        let funding = fund_psbt(&psbt, &pool, &options).unwrap();
+        // The existing input is always spent and the pool covers the remaining 10_000 sats
+        // plus fees.
+        assert!(funding.selection.selected_inputs.contains(&0));
+        assert!(!funding.new_inputs.is_empty());
+        assert_eq!(
+            funding.new_inputs.len() + 1,
+            funding.selection.selected_inputs.len()
+        );
+        assert!(funding.selection.selected_value >= 100_000 + funding.selection.fee);
+        // The transaction's weight covers the existing input and output.
+        assert!(
+            funding.selection.selected_weight
+                >= ScriptType::P2wpkh.input_weight() + ScriptType::P2wpkh.output_weight()
+        );
+
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat(90_000),
+            script_pubkey: ScriptBuf::new_p2sh(&bitcoin::ScriptHash::all_zeros()),
+        });
+        assert_eq!(
+            fund_psbt(&psbt, &pool, &options).unwrap_err(),
+            PsbtFundingError::UnknownInputWeight(0)
+        );
+    }
+}
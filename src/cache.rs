@@ -0,0 +1,306 @@
+//! Reusing the preparation of a pool across selections.
+//!
+//! Before searching, every selection computes the effective value of each input, drops the
+//! ineligible and dust ones and sorts the rest. Services running thousands of selections a minute
+//! against a wallet that only changes now and then redo that work on every call. A
+//! [`SelectionCache`] attached to an [`AlgorithmRegistry`](crate::selectcoin::AlgorithmRegistry)
+//! keeps the sorted working sets and their prefix sums, keyed by the pool and the options they
+//! depend on, so repeated selections only pay for the search.
+
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    types::{CoinSelectionOpt, OutputGroup, SelectionError, UnconfirmedPolicy},
+    utils::{check_cancelled, check_overflow, dust_threshold, target_feerate, SelectionContext},
+};
+
+/// Pools kept by [`SelectionCache::default`].
+pub const DEFAULT_CACHE_CAPACITY: usize = 8;
+
+/// A bounded cache of prepared pools, shared by the selections of an
+/// [`AlgorithmRegistry`](crate::selectcoin::AlgorithmRegistry).
+///
+/// An entry is reused when the pool and the target feerate, [`CoinSelectionOpt::min_confirmations`]
+/// and dust threshold match, whatever the payment. When full, the least recently used entry makes
+/// room for the next one.
+#[derive(Debug)]
+pub struct SelectionCache {
+    capacity: usize,
+    /// Least recently used first.
+    entries: Mutex<VecDeque<Entry>>,
+}
+
+impl SelectionCache {
+    /// Creates a cache holding up to `capacity` pools. A zero capacity caches nothing.
+    pub fn new(capacity: usize) -> Self {
+        SelectionCache {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Number of pools currently cached.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    /// Whether no pool is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every cached pool.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    /// The prepared context of `inputs` for `options`, from the cache if an earlier selection
+    /// prepared the same pool, otherwise prepared and cached now. Fails like
+    /// [`SelectionContext::new`], and failures are not cached.
+    pub(crate) fn context(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Result<Arc<SelectionContext>, SelectionError> {
+        let key = PoolKey::new(inputs, options);
+        let hash = key.hash();
+        let mut entries = self.entries();
+        // The hash only narrows the candidates down; the key material itself decides a hit.
+        if let Some(position) = entries
+            .iter()
+            .position(|entry| entry.hash == hash && entry.key == key)
+        {
+            // The checks the preparation would have made for this payment still apply.
+            options.validate()?;
+            check_overflow(inputs, options)?;
+            check_cancelled(options)?;
+            let entry = entries.remove(position).expect("position is in range");
+            let context = Arc::clone(&entry.context);
+            entries.push_back(entry);
+            return Ok(context);
+        }
+        // Preparing may take a while on a large pool; let other selections use the cache meanwhile.
+        drop(entries);
+        let context = Arc::new(SelectionContext::new(inputs, options)?);
+        if self.capacity > 0 {
+            let mut entries = self.entries();
+            if !entries
+                .iter()
+                .any(|entry| entry.hash == hash && entry.key == key)
+            {
+                if entries.len() == self.capacity {
+                    entries.pop_front();
+                }
+                entries.push_back(Entry {
+                    hash,
+                    key,
+                    context: Arc::clone(&context),
+                });
+            }
+        }
+        Ok(context)
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, VecDeque<Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for SelectionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+/// A cached pool: its prepared context, and the key material it was prepared from.
+#[derive(Debug)]
+struct Entry {
+    /// [`PoolKey::hash`] of `key`, to skip comparing most non-matching keys in full.
+    hash: u64,
+    key: PoolKey,
+    context: Arc<SelectionContext>,
+}
+
+/// Everything the prepared context of a pool depends on: every field of every input, in order,
+/// and the options deciding effective values, eligibility and dust.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct PoolKey {
+    target_feerate: u64,
+    min_confirmations: u32,
+    dust_threshold: u64,
+    available_spend_paths: Option<u32>,
+    unconfirmed_policy: UnconfirmedPolicy,
+    current_height: Option<u32>,
+    only_labels: Vec<String>,
+    preselected_inputs: Vec<usize>,
+    inputs: Vec<InputKey>,
+}
+
+/// The fields of an [`OutputGroup`], which does not implement `Eq` and `Hash` itself.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct InputKey {
+    value: u64,
+    weight: u64,
+    input_count: usize,
+    creation_sequence: Option<u32>,
+    cluster: Option<u32>,
+    ancestor_weight: u64,
+    ancestor_fee: u64,
+    confirmations: u32,
+    frozen: bool,
+    signing_cost: u64,
+    self_transfer: bool,
+    coinbase: bool,
+    available_height: Option<u32>,
+    labels: Vec<String>,
+    weight_variants: Vec<(u8, u64)>,
+}
+
+impl PoolKey {
+    fn new(inputs: &[OutputGroup], options: &CoinSelectionOpt) -> Self {
+        PoolKey {
+            target_feerate: target_feerate(options).as_sat_per_kwu(),
+            min_confirmations: options.min_confirmations,
+            dust_threshold: dust_threshold(options),
+            available_spend_paths: options.available_spend_paths,
+            unconfirmed_policy: options.unconfirmed_policy,
+            current_height: options.current_height,
+            only_labels: options.label_policy.only.clone(),
+            preselected_inputs: options.preselected_inputs.clone(),
+            inputs: inputs
+                .iter()
+                .map(|input| InputKey {
+                    value: input.value,
+                    weight: input.weight,
+                    input_count: input.input_count,
+                    creation_sequence: input.creation_sequence,
+                    cluster: input.cluster,
+                    ancestor_weight: input.ancestor_weight,
+                    ancestor_fee: input.ancestor_fee,
+                    confirmations: input.confirmations,
+                    frozen: input.frozen,
+                    signing_cost: input.signing_cost,
+                    self_transfer: input.self_transfer,
+                    coinbase: input.coinbase,
+                    available_height: input.available_height,
+                    labels: input.labels.clone(),
+                    weight_variants: input
+                        .weight_variants
+                        .iter()
+                        .map(|variant| (variant.path, variant.weight))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        Hash::hash(self, &mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::{
+        cache::{PoolKey, SelectionCache},
+        selectcoin::{select_coin, AlgorithmRegistry},
+        types::{CoinSelectionOpt, FeeRate, OutputGroup, SelectionError},
+    };
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt::builder()
+            .target_value(target_value)
+            .target_feerate(FeeRate::from_sat_per_wu(0.4))
+            .long_term_feerate(FeeRate::from_sat_per_wu(0.4))
+            .change_weight(50)
+            .change_cost(10)
+            .min_change_value(500)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cached_selection_matches_uncached() {
+        let inputs: Vec<_> = (1..=12)
+            .map(|i| OutputGroup {
+                value: 1_000 * i,
+                weight: 100 + 10 * i,
+                ..Default::default()
+            })
+            .collect();
+        let mut registry = AlgorithmRegistry::with_defaults();
+        registry.set_cache(SelectionCache::new(2));
+
+        for target in [5_000, 17_000, 40_000] {
+            let options = setup_options(target);
+            assert_eq!(
+                format!("{:?}", registry.select_coin(&inputs, &options).unwrap()),
+                format!("{:?}", select_coin(&inputs, &options).unwrap())
+            );
+        }
+        // The payment changed, not the pool, so one preparation served all three.
+        let cache = registry.cache().unwrap();
+        assert_eq!(cache.len(), 1);
+
+        // A different feerate or pool needs its own entry, evicting the oldest one.
+        let options = CoinSelectionOpt {
+            target_feerate: FeeRate::from_sat_per_wu(0.5),
+            ..setup_options(5_000)
+        };
+        registry.select_coin(&inputs, &options).unwrap();
+        registry.select_coin(&inputs[1..], &options).unwrap();
+        assert_eq!(registry.cache().unwrap().len(), 2);
+
+        // Checks tied to the payment still run on a hit.
+        assert!(matches!(
+            registry.select_coin(
+                &inputs[1..],
+                &CoinSelectionOpt {
+                    target_value: 0,
+                    ..options
+                }
+            ),
+            Err(SelectionError::NonPositiveTarget)
+        ));
+
+        registry.cache().unwrap().clear();
+        assert!(registry.cache().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hash_collision_is_not_a_hit() {
+        let inputs: Vec<_> = (1..=6)
+            .map(|i| OutputGroup {
+                value: 1_000 * i,
+                weight: 100,
+                ..Default::default()
+            })
+            .collect();
+        let other: Vec<_> = (1..=6)
+            .map(|i| OutputGroup {
+                value: 2_000 * i,
+                weight: 100,
+                ..Default::default()
+            })
+            .collect();
+        let options = setup_options(5_000);
+        let cache = SelectionCache::new(2);
+        let cached = cache.context(&inputs, &options).unwrap();
+
+        // Forge a collision: the entry of `inputs` now carries the hash of `other`.
+        cache.entries().front_mut().unwrap().hash = PoolKey::new(&other, &options).hash();
+        let context = cache.context(&other, &options).unwrap();
+        assert!(!Arc::ptr_eq(&context, &cached));
+        // Prepared from `other`: its value less a 40 sat spend fee per input.
+        assert_eq!(context.total_value(), 42_000 - 6 * 40);
+        assert_eq!(cache.len(), 2);
+    }
+}
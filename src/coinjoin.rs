@@ -0,0 +1,176 @@
+//! Selecting inputs for a coinjoin or coinswap pool with fixed output denominations.
+//!
+//! Mixing pools only accept outputs of standard denominations, e.g. 0.1, 0.01 and 0.001 BTC, so
+//! that outputs of one denomination cannot be told apart. Whatever the inputs bring beyond their
+//! denominations and the fee is left over as change that links back to them, the "toxic change".
+//! A good selection decomposes into the denominations with as little of it as possible.
+
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::{
+    selectcoin::select_coin,
+    types::{Algorithm, CoinSelectionOpt, OutputGroup, SelectionError, SelectionOutput},
+    utils::{calculate_fee, target_feerate},
+};
+
+/// A selection decomposed into denomination outputs, see [`select_coin_denominations`].
+#[derive(Debug)]
+pub struct DenominationSelection {
+    /// The algorithm whose selection was kept.
+    pub algorithm: Algorithm,
+    /// The selection funding the denominations covering the target. Its fee counts their outputs.
+    pub output: SelectionOutput,
+    /// Values of the denomination outputs, largest first.
+    pub denominations: Vec<u64>,
+    /// Fee of the denomination outputs carved out of the excess, which `output.fee` does not
+    /// count.
+    pub extra_fee: u64,
+    /// Value left over once every denomination that fits is carved out: the toxic change.
+    ///
+    /// The selected value is the sum of the denominations, `output.fee`, `extra_fee` and this.
+    pub toxic_change: u64,
+}
+
+/// Selects inputs mixing at least `options.target_value` sats into outputs of `denominations`,
+/// each weighing `output_weight`, with the least toxic change.
+///
+/// The target is decomposed into denominations greedily, largest first, and rounded up with one
+/// more of the smallest. Every selection of [`select_coin`] funding those outputs is then
+/// considered: further denominations are carved out of its excess while it covers them and the fee
+/// of their output, and the selection leaving the least over is kept, the best-ranked one among
+/// equals.
+///
+/// Returns [`SelectionError::NonPositiveTarget`] when no denomination is positive.
+pub fn select_coin_denominations(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    denominations: &[u64],
+    output_weight: u64,
+) -> Result<DenominationSelection, SelectionError> {
+    let mut sorted: Vec<u64> = denominations
+        .iter()
+        .copied()
+        .filter(|&denomination| denomination > 0)
+        .collect();
+    sorted.sort_unstable_by_key(|&denomination| Reverse(denomination));
+    sorted.dedup();
+    let smallest = *sorted.last().ok_or(SelectionError::NonPositiveTarget)?;
+
+    let mut outputs = Vec::new();
+    let mut remaining = options.target_value;
+    for &denomination in &sorted {
+        while remaining >= denomination {
+            outputs.push(denomination);
+            remaining -= denomination;
+        }
+    }
+    if remaining > 0 {
+        outputs.push(smallest);
+    }
+
+    let options = CoinSelectionOpt {
+        target_value: outputs.iter().sum(),
+        base_weight: options.base_weight + outputs.len() as u64 * output_weight,
+        ..options.clone()
+    };
+    let output_fee = calculate_fee(output_weight, target_feerate(&options));
+    select_coin(inputs, &options)?
+        .into_iter()
+        .map(|(algorithm, output)| {
+            let mut denominations = outputs.clone();
+            let mut excess = output
+                .selected_value
+                .saturating_sub(options.target_value + output.fee);
+            let mut extra_fee = 0;
+            for &denomination in &sorted {
+                while excess >= denomination.saturating_add(output_fee) {
+                    denominations.push(denomination);
+                    excess -= denomination + output_fee;
+                    extra_fee += output_fee;
+                }
+            }
+            denominations.sort_unstable_by_key(|&denomination| Reverse(denomination));
+            DenominationSelection {
+                algorithm,
+                output,
+                denominations,
+                extra_fee,
+                toxic_change: excess,
+            }
+        })
+        .min_by_key(|selection| selection.toxic_change)
+        .ok_or(SelectionError::NonPositiveTarget)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        coinjoin::select_coin_denominations,
+        selectcoin::select_coin,
+        types::{CoinSelectionOpt, FeeRate, OutputGroup, SelectionError},
+    };
+
+    const DENOMINATIONS: [u64; 3] = [1_000_000, 100_000, 10_000];
+
+    fn setup_inputs() -> Vec<OutputGroup> {
+        [1_350_000, 1_101_500, 730_000, 212_345, 95_000]
+            .into_iter()
+            .map(|value| OutputGroup {
+                value,
+                weight: 272,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt::builder()
+            .target_value(target_value)
+            .target_feerate(FeeRate::from_sat_per_vb(1.0))
+            .base_weight(42)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_select_coin_denominations() {
+        let inputs = setup_inputs();
+        let options = setup_options(1_100_000);
+        let selection = select_coin_denominations(&inputs, &options, &DENOMINATIONS, 124).unwrap();
+        assert!(selection.denominations.iter().sum::<u64>() >= 1_100_000);
+        assert!(selection
+            .denominations
+            .iter()
+            .all(|denomination| DENOMINATIONS.contains(denomination)));
+        assert!(selection.denominations.is_sorted_by(|a, b| a >= b));
+        assert_eq!(
+            selection.output.selected_value,
+            selection.denominations.iter().sum::<u64>()
+                + selection.output.fee
+                + selection.extra_fee
+                + selection.toxic_change
+        );
+        // Nothing left over could fund one more of the smallest denomination.
+        assert!(selection.toxic_change < 10_000 + 124);
+
+        // The least toxic change among the selections funding the same outputs.
+        let funded = CoinSelectionOpt {
+            target_value: 1_100_000,
+            base_weight: options.base_weight + 2 * 124,
+            ..options.clone()
+        };
+        for (_, output) in select_coin(&inputs, &funded).unwrap() {
+            let excess = output.selected_value - 1_100_000 - output.fee;
+            assert!(selection.toxic_change <= excess);
+        }
+    }
+
+    #[test]
+    fn test_select_coin_denominations_without_denominations() {
+        assert!(matches!(
+            select_coin_denominations(&setup_inputs(), &setup_options(100_000), &[0], 124),
+            Err(SelectionError::NonPositiveTarget)
+        ));
+    }
+}
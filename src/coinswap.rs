@@ -0,0 +1,155 @@
+//! Funding several transactions at once from one pool, as coinswap makers and takers do.
+//!
+//! A coinswap splits the swapped amount across several funding transactions. Each one needs its
+//! own inputs, so the selections must be disjoint, and each must stay below a weight limit so it
+//! remains standard.
+
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::{
+    selectcoin::select_coin,
+    types::{CoinSelectionOpt, Constraint, OutputGroup, SelectionError, SelectionOutput},
+    utils::excess_output_weight,
+};
+
+/// Splits `total` into `count` amounts that differ by at most one sat, largest first.
+///
+/// Returns an empty list when `count` is zero.
+pub fn split_target(total: u64, count: usize) -> Vec<u64> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let share = total / count as u64;
+    let remainder = (total % count as u64) as usize;
+    (0..count)
+        .map(|index| share + u64::from(index < remainder))
+        .collect()
+}
+
+/// Selects disjoint inputs for one funding transaction per entry of `amounts`.
+///
+/// Every transaction is funded as described by `options`, with `target_value` replaced by its
+/// amount; see [`split_target`] to split a total evenly. Larger amounts are funded first, and each
+/// takes the best-ranked selection of [`select_coin`] among the inputs the previous ones left,
+/// which keeps the combined waste low. When `max_tx_weight` is set, selections whose transaction
+/// (the base weight, the selected inputs and room for the change output) would weigh more are
+/// skipped.
+///
+/// Returns one selection per amount, in the order of `amounts`. The selected indices refer to
+/// `inputs`, and no input is selected twice. [`CoinSelectionOpt::preselected_inputs`] are spent by
+/// the first transaction funded. Fails with [`Constraint::MaxWeight`] when no selection of some
+/// transaction fits the weight limit.
+pub fn select_coin_coinswap(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    amounts: &[u64],
+    max_tx_weight: Option<u64>,
+) -> Result<Vec<SelectionOutput>, SelectionError> {
+    let mut order: Vec<usize> = (0..amounts.len()).collect();
+    order.sort_by_key(|&index| Reverse(amounts[index]));
+
+    // Spent inputs are frozen rather than removed, so indices keep matching `inputs`.
+    let mut pool = inputs.to_vec();
+    let mut options = options.clone();
+    let mut selections: Vec<Option<SelectionOutput>> = amounts.iter().map(|_| None).collect();
+    for index in order {
+        options.target_value = amounts[index];
+        let fits = |output: &SelectionOutput| {
+            max_tx_weight.is_none_or(|max_weight| {
+                options.base_weight + output.selected_weight + excess_output_weight(&options)
+                    <= max_weight
+            })
+        };
+        let (_, output) = select_coin(&pool, &options)?
+            .into_iter()
+            .find(|(_, output)| fits(output))
+            .ok_or(SelectionError::NoSolutionFound {
+                constraint: Constraint::MaxWeight,
+            })?;
+        for &selected in &output.selected_inputs {
+            pool[selected].frozen = true;
+        }
+        options.preselected_inputs.clear();
+        selections[index] = Some(output);
+    }
+    Ok(selections.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        coinswap::{select_coin_coinswap, split_target},
+        types::{CoinSelectionOpt, Constraint, OutputGroup, SelectionError},
+    };
+
+    fn setup_inputs() -> Vec<OutputGroup> {
+        [60_000, 45_000, 40_000, 30_000, 25_000, 20_000, 10_000]
+            .into_iter()
+            .map(|value| OutputGroup {
+                value,
+                weight: 272,
+                input_count: 1,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn setup_options() -> CoinSelectionOpt {
+        CoinSelectionOpt::builder()
+            .target_value(1)
+            .target_feerate(crate::types::FeeRate::from_sat_per_vb(2.0))
+            .base_weight(400)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_split_target() {
+        assert_eq!(split_target(100_001, 3), vec![33_334, 33_334, 33_333]);
+        assert_eq!(split_target(90, 3), vec![30, 30, 30]);
+        assert!(split_target(90, 0).is_empty());
+    }
+
+    #[test]
+    fn test_select_coin_coinswap_disjoint() {
+        let inputs = setup_inputs();
+        let amounts = split_target(150_000, 3);
+        let selections = select_coin_coinswap(&inputs, &setup_options(), &amounts, None).unwrap();
+        assert_eq!(selections.len(), 3);
+
+        let mut spent: Vec<usize> = selections
+            .iter()
+            .flat_map(|selection| selection.selected_inputs.iter().copied())
+            .collect();
+        let count = spent.len();
+        spent.sort();
+        spent.dedup();
+        assert_eq!(spent.len(), count, "inputs must not be reused");
+
+        for (selection, amount) in selections.iter().zip(&amounts) {
+            assert!(selection.selected_value >= amount + selection.fee);
+        }
+    }
+
+    #[test]
+    fn test_select_coin_coinswap_weight_limit() {
+        let inputs = setup_inputs();
+        let options = setup_options();
+        let amounts = [80_000, 50_000];
+        // Room for the base weight, two inputs and the change output only.
+        let max_tx_weight = options.base_weight + 2 * 272 + options.change_weight;
+        let selections =
+            select_coin_coinswap(&inputs, &options, &amounts, Some(max_tx_weight)).unwrap();
+        assert!(selections
+            .iter()
+            .all(|selection| selection.selected_inputs.len() <= 2));
+
+        assert_eq!(
+            select_coin_coinswap(&inputs, &options, &[200_000], Some(max_tx_weight)).unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::MaxWeight
+            }
+        );
+    }
+}
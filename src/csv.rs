@@ -0,0 +1,163 @@
+//! Loading UTXO sets exported as CSV.
+//!
+//! Each record describes one coin as `txid,vout,value,weight,confirmations`. The weight column
+//! holds either the spending weight in weight units or the name of the coin's [`ScriptType`]
+//! (`p2pkh`, `p2sh-p2wpkh`, `p2wpkh` or `p2tr`), from which the weight is derived. A first line
+//! starting with `txid` is taken as a header, and blank lines and lines starting with `#` are
+//! skipped.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::types::{OutputGroup, ScriptType};
+
+/// A coin read from a CSV record.
+#[derive(Debug, Clone)]
+pub struct CsvUtxo {
+    /// Id of the transaction that created the coin, as written in the CSV.
+    pub txid: String,
+    /// Index of the coin among the outputs of its transaction.
+    pub vout: u32,
+    /// The coin as a single-input group, carrying its value, weight and confirmations.
+    pub group: OutputGroup,
+}
+
+/// Errors returned by [`parse_utxos`]. Lines are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    /// A record does not have the five columns `txid,vout,value,weight,confirmations`.
+    ColumnCount { line: usize, found: usize },
+    /// A column of a record could not be parsed.
+    InvalidField { line: usize, column: &'static str },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::ColumnCount { line, found } => {
+                write!(f, "Line {line}: expected 5 columns, found {found}")
+            }
+            CsvError::InvalidField { line, column } => {
+                write!(f, "Line {line}: invalid {column}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CsvError {}
+
+const COLUMNS: [&str; 5] = ["txid", "vout", "value", "weight", "confirmations"];
+
+/// Parses a CSV of `txid,vout,value,weight,confirmations` records into coins, in file order.
+///
+/// The `group`s of the returned coins are ready to be passed to the selection functions, and the
+/// indices of a selection map back to the coins' outpoints.
+pub fn parse_utxos(csv: &str) -> Result<Vec<CsvUtxo>, CsvError> {
+    let mut utxos = Vec::new();
+    for (index, record) in csv.lines().enumerate() {
+        let line = index + 1;
+        let record = record.trim();
+        if record.is_empty()
+            || record.starts_with('#')
+            || (line == 1 && record.to_ascii_lowercase().starts_with("txid"))
+        {
+            continue;
+        }
+        let fields: Vec<&str> = record.split(',').map(str::trim).collect();
+        if fields.len() != COLUMNS.len() {
+            return Err(CsvError::ColumnCount {
+                line,
+                found: fields.len(),
+            });
+        }
+        let invalid = |column: usize| CsvError::InvalidField {
+            line,
+            column: COLUMNS[column],
+        };
+        if fields[0].is_empty() {
+            return Err(invalid(0));
+        }
+        let vout = fields[1].parse().map_err(|_| invalid(1))?;
+        let value = fields[2].parse().map_err(|_| invalid(2))?;
+        let weight = match fields[3].parse() {
+            Ok(weight) => weight,
+            Err(_) => script_type(fields[3])
+                .ok_or_else(|| invalid(3))?
+                .input_weight(),
+        };
+        let confirmations = fields[4].parse().map_err(|_| invalid(4))?;
+        utxos.push(CsvUtxo {
+            txid: fields[0].to_string(),
+            vout,
+            group: OutputGroup {
+                value,
+                weight,
+                input_count: 1,
+                confirmations,
+                ..Default::default()
+            },
+        });
+    }
+    Ok(utxos)
+}
+
+fn script_type(name: &str) -> Option<ScriptType> {
+    match name.to_ascii_lowercase().replace('_', "-").as_str() {
+        "p2pkh" => Some(ScriptType::P2pkh),
+        "p2sh-p2wpkh" => Some(ScriptType::P2shP2wpkh),
+        "p2wpkh" => Some(ScriptType::P2wpkh),
+        "p2tr" => Some(ScriptType::P2tr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        csv::{parse_utxos, CsvError},
+        types::ScriptType,
+    };
+
+    #[test]
+    fn test_parse_utxos() {
+        let csv = "txid,vout,value,weight,confirmations\n\
+                   # cold wallet\n\
+                   aa11,0,150000,272,6\n\
+                   \n\
+                   bb22, 3, 20000, P2SH-P2WPKH, 0\n\
+                   cc33,1,5000,p2tr,101\n";
+        let utxos = parse_utxos(csv).unwrap();
+        assert_eq!(utxos.len(), 3);
+        assert_eq!(utxos[0].txid, "aa11");
+        assert_eq!(utxos[0].group.value, 150_000);
+        assert_eq!(utxos[0].group.weight, 272);
+        assert_eq!(utxos[0].group.confirmations, 6);
+        assert_eq!((utxos[1].txid.as_str(), utxos[1].vout), ("bb22", 3));
+        assert_eq!(utxos[1].group.weight, ScriptType::P2shP2wpkh.input_weight());
+        assert_eq!(utxos[2].group.weight, ScriptType::P2tr.input_weight());
+        assert_eq!(utxos[2].group.input_count, 1);
+
+        assert_eq!(
+            parse_utxos("aa11,0,150000,272").unwrap_err(),
+            CsvError::ColumnCount { line: 1, found: 4 }
+        );
+        assert_eq!(
+            parse_utxos("aa11,0,150000,272,6\nbb22,0,1000,p2wsh,6").unwrap_err(),
+            CsvError::InvalidField {
+                line: 2,
+                column: "weight"
+            }
+        );
+        assert_eq!(
+            parse_utxos("aa11,0,-5,272,6").unwrap_err(),
+            CsvError::InvalidField {
+                line: 1,
+                column: "value"
+            }
+        );
+    }
+}
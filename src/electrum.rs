@@ -0,0 +1,111 @@
+//! Converting Electrum server `blockchain.scripthash.listunspent` results into [`OutputGroup`]s.
+//!
+//! Electrum servers list the coins of one script hash at a time, as `tx_hash`, `tx_pos`,
+//! `height` and `value` (in sats). The script is the caller's, so its [`ScriptType`] is passed
+//! in, and confirmations are counted from the chain tip. A `height` of `0` or below marks a
+//! mempool coin.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::types::{OutputGroup, ScriptType};
+
+/// A coin listed by an Electrum server.
+#[derive(Debug, Clone)]
+pub struct ElectrumUtxo {
+    /// Id of the transaction that created the coin.
+    pub txid: String,
+    /// Index of the coin among the outputs of its transaction.
+    pub vout: u32,
+    /// The coin as a single-input group.
+    pub group: OutputGroup,
+}
+
+/// Errors returned by [`parse_electrum_listunspent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElectrumError {
+    /// The input is not a valid `blockchain.scripthash.listunspent` result.
+    Json(String),
+}
+
+impl fmt::Display for ElectrumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElectrumError::Json(err) => write!(f, "Invalid Electrum listunspent result: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ElectrumError {}
+
+/// An entry of a `blockchain.scripthash.listunspent` result.
+#[derive(Deserialize)]
+struct Entry {
+    tx_hash: String,
+    tx_pos: u32,
+    height: i64,
+    value: u64,
+}
+
+/// Parses the JSON result of `blockchain.scripthash.listunspent` for a script of `script_type`
+/// into coins, given the current `tip_height`.
+///
+/// Every coin pays to the same script, so wallets that call this per address can put the
+/// returned groups into one [`OutputGroup::cluster`].
+pub fn parse_electrum_listunspent(
+    json: &str,
+    script_type: ScriptType,
+    tip_height: u32,
+) -> Result<Vec<ElectrumUtxo>, ElectrumError> {
+    let entries: Vec<Entry> =
+        serde_json::from_str(json).map_err(|err| ElectrumError::Json(err.to_string()))?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| {
+            let confirmations = u32::try_from(entry.height)
+                .ok()
+                .filter(|&height| height > 0)
+                .map_or(0, |height| tip_height.saturating_sub(height) + 1);
+            ElectrumUtxo {
+                txid: entry.tx_hash,
+                vout: entry.tx_pos,
+                group: OutputGroup {
+                    confirmations,
+                    ..OutputGroup::from_script_type(entry.value, script_type, 1)
+                },
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        electrum::{parse_electrum_listunspent, ElectrumError},
+        types::ScriptType,
+    };
+
+    #[test]
+    fn test_parse_electrum_listunspent() {
+        let json = r#"[
+            {"tx_hash": "aa11", "tx_pos": 0, "height": 800000, "value": 150000},
+            {"tx_hash": "bb22", "tx_pos": 3, "height": 0, "value": 20000},
+            {"tx_hash": "cc33", "tx_pos": 1, "height": -1, "value": 5000}
+        ]"#;
+        let utxos = parse_electrum_listunspent(json, ScriptType::P2wpkh, 800_005).unwrap();
+        assert_eq!(utxos.len(), 3);
+        assert_eq!((utxos[0].txid.as_str(), utxos[0].vout), ("aa11", 0));
+        assert_eq!(utxos[0].group.value, 150_000);
+        assert_eq!(utxos[0].group.weight, ScriptType::P2wpkh.input_weight());
+        assert_eq!(utxos[0].group.confirmations, 6);
+        assert_eq!(utxos[1].vout, 3);
+        assert_eq!(utxos[1].group.confirmations, 0);
+        assert_eq!(utxos[2].group.confirmations, 0);
+
+        assert!(matches!(
+            parse_electrum_listunspent("[{}]", ScriptType::P2wpkh, 0).unwrap_err(),
+            ElectrumError::Json(_)
+        ));
+    }
+}
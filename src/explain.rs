@@ -0,0 +1,459 @@
+//! Explaining why a selection picked the coins it did.
+//!
+//! [`select_coin_detailed`] reports every algorithm's outcome, but answering "why did the wallet
+//! pick these coins?" still means reading the ranking rules and re-deriving the effective value of
+//! each input. [`explain`] does that work: it summarizes every selection in rank order, names the
+//! criterion that put the winner ahead of the runner-up, lists the algorithms that failed and why,
+//! and gives the reason each input that never took part was left out. The [`Explanation`] can be
+//! inspected field by field or printed as a plain text report.
+
+use alloc::vec::Vec;
+use core::{fmt, time::Duration};
+
+use crate::{
+    selectcoin::{
+        is_changeless, older_groups_passed_over, select_coin_detailed, strategy_key,
+        SelectionAlgorithm,
+    },
+    types::{
+        AgePreference, Algorithm, CoinSelectionOpt, OutputGroup, RankStrategy, SelectionError,
+        SelectionReport,
+    },
+    utils::{
+        dust_threshold, is_dust, is_eligible, is_locked, label_allowed, spend_effective_value,
+        unconfirmed_allowed, unconfirmed_over_caps,
+    },
+};
+
+/// Why a selection ranked first, and what it was compared against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// The algorithm whose selection ranked first.
+    pub winner: Algorithm,
+    /// Why it ranked ahead of the runner-up.
+    pub reason: WinReason,
+    /// One summary per successful algorithm, best-first.
+    pub selections: Vec<SelectionSummary>,
+    /// The algorithms that found no selection, with the error each returned.
+    pub failures: Vec<(Algorithm, SelectionError)>,
+    /// The inputs no algorithm could select, in input order.
+    pub excluded: Vec<ExcludedInput>,
+}
+
+/// The figures a selection was ranked on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionSummary {
+    /// The algorithm that made the selection.
+    pub algorithm: Algorithm,
+    /// The selected input indices.
+    pub selected_inputs: Vec<usize>,
+    /// Summed [`OutputGroup::input_count`] of the selected groups.
+    pub input_count: usize,
+    /// Fee paid, in sats.
+    pub fee: u64,
+    /// Waste of the selection, in sats.
+    pub waste: i64,
+    /// Summed [`OutputGroup::signing_cost`] of the selected groups, which the ranking adds to
+    /// `waste`.
+    pub signing_cost: u64,
+    /// Value of the change output, `0` when changeless.
+    pub change_value: u64,
+    /// Wall-clock time the algorithm took.
+    pub runtime: Duration,
+}
+
+/// What put the winner first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinReason {
+    /// No other algorithm found a selection.
+    OnlySelection,
+    /// With [`CoinSelectionOpt::avoid_change`], the winner is changeless and the runner-up is not.
+    Changeless { runner_up: Algorithm },
+    /// The first ranking criterion on which the winner beat the runner-up, e.g. `"waste"`, with
+    /// the value of each. Waste is compared with the signing cost added.
+    Criterion {
+        criterion: &'static str,
+        winner: i64,
+        runner_up: Algorithm,
+        runner_up_value: i64,
+    },
+    /// The runner-up tied on every criterion and ran later.
+    Tie { runner_up: Algorithm },
+}
+
+/// An input no algorithm could select.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExcludedInput {
+    /// Index of the input.
+    pub index: usize,
+    /// Why it was left out.
+    pub reason: Exclusion,
+}
+
+/// Why an input was left out of the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exclusion {
+    /// The input is [`OutputGroup::frozen`].
+    Frozen,
+    /// The input has fewer than [`CoinSelectionOpt::min_confirmations`].
+    Unconfirmed { confirmations: u32 },
+    /// The input is an immature [`OutputGroup::coinbase`] or its
+    /// [`OutputGroup::available_height`] is above the [`CoinSelectionOpt::current_height`].
+    Locked,
+    /// The input is unconfirmed and the [`CoinSelectionOpt::unconfirmed_policy`] rules it out or
+    /// its caps leave no room for it.
+    UnconfirmedPolicy,
+    /// The input carries none of the labels the [`CoinSelectionOpt::label_policy`] selects.
+    Label,
+    /// None of the input's [`OutputGroup::weight_variants`] is among the
+    /// [`CoinSelectionOpt::available_spend_paths`].
+    NoSpendPath,
+    /// Spending the input costs more than it is worth at the target feerate.
+    Uneconomical { effective_value: i64 },
+    /// The effective value of the input is below the dust threshold.
+    Dust {
+        effective_value: i64,
+        threshold: u64,
+    },
+}
+
+/// Runs the same algorithms as [`crate::selectcoin::select_coin`] and explains the outcome. Fails
+/// like [`select_coin_detailed`].
+pub fn explain(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<Explanation, SelectionError> {
+    let report = select_coin_detailed(inputs, options)?;
+    Ok(Explanation::new(&report, inputs, options))
+}
+
+impl Explanation {
+    /// Explains a `report` of [`select_coin_detailed`] or
+    /// [`AlgorithmRegistry::select_coin_detailed`](crate::selectcoin::AlgorithmRegistry::select_coin_detailed),
+    /// run on `inputs` and `options`.
+    pub fn new(
+        report: &SelectionReport,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Self {
+        let mut selections = Vec::new();
+        let mut failures = Vec::new();
+        let mut successes = Vec::new();
+        for outcome in &report.outcomes {
+            match &outcome.result {
+                Ok(output) => {
+                    successes.push((outcome.algorithm, output));
+                    selections.push(SelectionSummary {
+                        algorithm: outcome.algorithm,
+                        selected_inputs: output.selected_inputs.clone(),
+                        input_count: output
+                            .selected_inputs
+                            .iter()
+                            .map(|&index| inputs[index].input_count)
+                            .sum(),
+                        fee: output.fee,
+                        waste: output.waste.0,
+                        signing_cost: output
+                            .selected_inputs
+                            .iter()
+                            .map(|&index| inputs[index].signing_cost)
+                            .sum(),
+                        change_value: outcome.change_value.unwrap_or(0),
+                        runtime: outcome.runtime,
+                    });
+                }
+                Err(error) => failures.push((outcome.algorithm, *error)),
+            }
+        }
+
+        let reason = match successes.as_slice() {
+            [(_, winner), (runner_up, second), ..] => {
+                if options.avoid_change
+                    && is_changeless(winner, inputs, options)
+                    && !is_changeless(second, inputs, options)
+                {
+                    WinReason::Changeless {
+                        runner_up: *runner_up,
+                    }
+                } else {
+                    let key = |output| {
+                        let passed_over = match options.age_preference {
+                            AgePreference::StrictOldestFirst => {
+                                older_groups_passed_over(output, inputs, options) as i64
+                            }
+                            AgePreference::Ignore | AgePreference::Weighted { .. } => 0,
+                        };
+                        let (first, second, third) = strategy_key(output, inputs, options);
+                        [passed_over, first, second, third]
+                    };
+                    let (winner, second) = (key(winner), key(second));
+                    criteria(options)
+                        .into_iter()
+                        .zip(winner.into_iter().zip(second))
+                        .find(|(_, (winner, second))| winner != second)
+                        .map_or(
+                            WinReason::Tie {
+                                runner_up: *runner_up,
+                            },
+                            |(criterion, (winner, runner_up_value))| WinReason::Criterion {
+                                criterion,
+                                winner,
+                                runner_up: *runner_up,
+                                runner_up_value,
+                            },
+                        )
+                }
+            }
+            _ => WinReason::OnlySelection,
+        };
+
+        let over_caps = unconfirmed_over_caps(inputs, options);
+        let excluded = inputs
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !options.preselected_inputs.contains(index))
+            .filter_map(|(index, input)| {
+                exclusion(input, over_caps.contains(&index), options)
+                    .map(|reason| ExcludedInput { index, reason })
+            })
+            .collect();
+
+        Explanation {
+            winner: report.winner,
+            reason,
+            selections,
+            failures,
+            excluded,
+        }
+    }
+}
+
+/// Names of the criteria the ranking compares after the changeless preference, in order: the
+/// older groups passed over under [`AgePreference::StrictOldestFirst`], then those of
+/// [`strategy_key`].
+fn criteria(options: &CoinSelectionOpt) -> [&'static str; 4] {
+    let passed_over = "older groups passed over";
+    if options.selection_metric.is_some() {
+        return [passed_over, "selection metric score", "waste", "UTXO count"];
+    }
+    match options.rank_strategy {
+        RankStrategy::MinInputs => [passed_over, "UTXO count", "group count", "waste"],
+        // The last component is constant.
+        RankStrategy::MinWaste => [passed_over, "waste", "UTXO count", ""],
+        RankStrategy::MinChange => [passed_over, "change value", "waste", "UTXO count"],
+        RankStrategy::MinFee => [passed_over, "fee", "waste", "UTXO count"],
+        RankStrategy::MinWeight => [passed_over, "weight", "waste", "UTXO count"],
+    }
+}
+
+/// Why `input` cannot be selected under `options`, if it cannot. `over_caps` tells whether the
+/// unconfirmed input is beyond the caps of the [`CoinSelectionOpt::unconfirmed_policy`].
+fn exclusion(
+    input: &OutputGroup,
+    over_caps: bool,
+    options: &CoinSelectionOpt,
+) -> Option<Exclusion> {
+    if input.frozen {
+        return Some(Exclusion::Frozen);
+    }
+    if input.confirmations < options.min_confirmations {
+        return Some(Exclusion::Unconfirmed {
+            confirmations: input.confirmations,
+        });
+    }
+    if is_locked(input, options) {
+        return Some(Exclusion::Locked);
+    }
+    if over_caps || !unconfirmed_allowed(input, options) {
+        return Some(Exclusion::UnconfirmedPolicy);
+    }
+    if !label_allowed(input, options) {
+        return Some(Exclusion::Label);
+    }
+    if !is_eligible(input, options) {
+        return Some(Exclusion::NoSpendPath);
+    }
+    if !is_dust(input, options) {
+        return None;
+    }
+    let effective_value = spend_effective_value(input, options);
+    Some(if effective_value <= 0 {
+        Exclusion::Uneconomical { effective_value }
+    } else {
+        Exclusion::Dust {
+            effective_value,
+            threshold: dust_threshold(options),
+        }
+    })
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Winner: {}", self.winner.name())?;
+        match self.reason {
+            WinReason::OnlySelection => writeln!(f, ", the only selection found")?,
+            WinReason::Changeless { runner_up } => {
+                writeln!(f, ", changeless where {} creates change", runner_up.name())?
+            }
+            WinReason::Criterion {
+                criterion,
+                winner,
+                runner_up,
+                runner_up_value,
+            } => writeln!(
+                f,
+                ", by {criterion}: {winner} against {runner_up_value} for {}",
+                runner_up.name()
+            )?,
+            WinReason::Tie { runner_up } => writeln!(
+                f,
+                ", tied with {} on every criterion and ran first",
+                runner_up.name()
+            )?,
+        }
+        writeln!(f, "Selections, best first:")?;
+        for (rank, selection) in self.selections.iter().enumerate() {
+            write!(
+                f,
+                "  {}. {}: inputs {:?} ({} UTXOs), fee {} sats, waste {} sats, ",
+                rank + 1,
+                selection.algorithm.name(),
+                selection.selected_inputs,
+                selection.input_count,
+                selection.fee,
+                selection.waste
+            )?;
+            if selection.signing_cost > 0 {
+                write!(f, "signing cost {} sats, ", selection.signing_cost)?;
+            }
+            match selection.change_value {
+                0 => writeln!(f, "no change")?,
+                change => writeln!(f, "change {change} sats")?,
+            }
+        }
+        if !self.failures.is_empty() {
+            writeln!(f, "Failed:")?;
+            for (algorithm, error) in &self.failures {
+                writeln!(f, "  {}: {error}", algorithm.name())?;
+            }
+        }
+        if !self.excluded.is_empty() {
+            writeln!(f, "Excluded inputs:")?;
+            for excluded in &self.excluded {
+                write!(f, "  #{}: ", excluded.index)?;
+                match excluded.reason {
+                    Exclusion::Frozen => writeln!(f, "frozen")?,
+                    Exclusion::Unconfirmed { confirmations } => {
+                        writeln!(f, "{confirmations} confirmations, too few")?
+                    }
+                    Exclusion::Locked => writeln!(f, "immature coinbase or timelocked")?,
+                    Exclusion::UnconfirmedPolicy => {
+                        writeln!(f, "unconfirmed, ruled out by the unconfirmed policy")?
+                    }
+                    Exclusion::Label => writeln!(f, "not among the selected labels")?,
+                    Exclusion::NoSpendPath => writeln!(f, "no available spend path")?,
+                    Exclusion::Uneconomical { effective_value } => {
+                        writeln!(f, "uneconomical, effective value {effective_value} sats")?
+                    }
+                    Exclusion::Dust {
+                        effective_value,
+                        threshold,
+                    } => writeln!(
+                        f,
+                        "dust, effective value {effective_value} sats below {threshold} sats"
+                    )?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        explain::{explain, ExcludedInput, Exclusion, WinReason},
+        selectcoin::{select_coin_detailed, SelectionAlgorithm},
+        types::{CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, ScriptType},
+    };
+
+    fn setup_inputs() -> Vec<OutputGroup> {
+        vec![
+            OutputGroup::from_script_type(50_000, ScriptType::P2wpkh, 1),
+            OutputGroup::from_script_type(30_000, ScriptType::P2wpkh, 1),
+            OutputGroup {
+                frozen: true,
+                ..OutputGroup::from_script_type(80_000, ScriptType::P2wpkh, 1)
+            },
+            // Costs about 1,360 sats to spend at 20 sat/vB.
+            OutputGroup::from_script_type(1_000, ScriptType::P2wpkh, 1),
+            OutputGroup::from_script_type(1_500, ScriptType::P2wpkh, 1),
+            OutputGroup::from_script_type(20_000, ScriptType::P2wpkh, 1),
+        ]
+    }
+
+    #[test]
+    fn test_explain_selection() {
+        let inputs = setup_inputs();
+        let options = CoinSelectionOpt::builder()
+            .target_value(45_000)
+            .target_feerate(FeeRate::from_sat_per_vb(20.0))
+            .excess_strategy(ExcessStrategy::ToChange)
+            .rank_strategy(RankStrategy::MinWaste)
+            .build()
+            .unwrap();
+        let explanation = explain(&inputs, &options).unwrap();
+        let report = select_coin_detailed(&inputs, &options).unwrap();
+
+        assert_eq!(explanation.winner, report.winner);
+        assert_eq!(
+            explanation.selections.len() + explanation.failures.len(),
+            report.outcomes.len()
+        );
+        assert_eq!(
+            explanation.selections[0].waste,
+            report.outcomes[0].result.as_ref().unwrap().waste.0
+        );
+        match explanation.reason {
+            WinReason::Criterion {
+                criterion,
+                winner,
+                runner_up_value,
+                ..
+            } => {
+                assert!(["waste", "UTXO count"].contains(&criterion));
+                assert!(winner < runner_up_value);
+            }
+            WinReason::Tie { .. } => {}
+            reason => panic!("unexpected reason {reason:?}"),
+        }
+        assert_eq!(
+            explanation.excluded,
+            [
+                ExcludedInput {
+                    index: 2,
+                    reason: Exclusion::Frozen
+                },
+                ExcludedInput {
+                    index: 3,
+                    reason: Exclusion::Uneconomical {
+                        effective_value: -360
+                    }
+                },
+                ExcludedInput {
+                    index: 4,
+                    reason: Exclusion::Dust {
+                        effective_value: 140,
+                        threshold: 294
+                    }
+                },
+            ]
+        );
+
+        let text = explanation.to_string();
+        assert!(text.starts_with(&format!("Winner: {}", report.winner.name())));
+        assert!(text.contains("  #2: frozen\n"));
+        assert!(text.contains("  #3: uneconomical, effective value -360 sats\n"));
+        assert!(text.contains("  #4: dust, effective value 140 sats below 294 sats\n"));
+    }
+}
@@ -1,9 +1,41 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-/// Collection of coin selection algorithms: Branch and Bound (BnB), CoinGrinder, First-In-First-Out (FIFO), and Lowest Larger
+extern crate alloc;
+
+/// Collection of coin selection algorithms: Blackjack, Branch and Bound (BnB), CoinGrinder, Fewest Inputs, First-In-First-Out (FIFO), Lowest Larger, Simulated Annealing, Genetic, Single Random Draw, Knapsack, Consolidation and Minimum Linkage
 pub mod algorithms;
+/// Batching payouts into one transaction with a per-payout fee attribution, as exchange wallets need
+pub mod batch;
+/// Conversions to and from `bitcoin::Amount`, `bitcoin::Weight` and `bitcoin::FeeRate`, and from `bitcoin::TxOut` coins
+#[cfg(feature = "bitcoin")]
+pub mod bitcoin;
+/// Caching the prepared pool across selections from an unchanging wallet
+#[cfg(feature = "std")]
+pub mod cache;
+/// Selecting inputs that decompose into fixed coinjoin or coinswap denominations with minimal toxic change
+pub mod coinjoin;
+/// Funding several transactions with disjoint inputs, as coinswap makers and takers need
+pub mod coinswap;
+/// Loading UTXO sets exported as CSV into `OutputGroup`s
+pub mod csv;
+/// Conversion of Electrum server `listunspent` results into `OutputGroup`s
+#[cfg(feature = "electrum")]
+pub mod electrum;
+/// Human-readable explanations of why a selection picked its coins
+pub mod explain;
+/// Conversion of Bitcoin Core `listunspent` results into `OutputGroup`s
+#[cfg(feature = "listunspent")]
+pub mod listunspent;
+/// Privacy scoring of selections: partial cluster spends, cluster merging, change detectability and round amounts
+pub mod privacy;
 /// Wrapper API that runs all coin selection algorithms in parallel and returns the result with lowest waste
 pub mod selectcoin;
+/// Replaying payment scenarios against a virtual wallet to compare algorithms over time
+pub mod simulation;
+/// `proptest` strategies generating realistic pools and options, for property tests downstream
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 /// Core types and structs used throughout the library including OutputGroup and CoinSelectionOpt
 pub mod types;
 /// Helper functions with tests for fee calculation, weight computation, and waste metrics
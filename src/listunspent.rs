@@ -0,0 +1,208 @@
+//! Converting the output of Bitcoin Core's `listunspent` RPC into [`OutputGroup`]s.
+//!
+//! Each coin becomes a single-input group whose weight is derived from its `scriptPubKey` (and,
+//! for nested segwit, its `redeemScript`). Coins on the same `address` share a
+//! [`OutputGroup::cluster`], coins Core reports as not `spendable` are [`OutputGroup::frozen`],
+//! and the `ancestorsize`/`ancestorfees` of unconfirmed coins fill in the CPFP fields. The address
+//! `label`, if any, becomes the group's only [`OutputGroup::labels`] entry.
+
+use std::{collections::HashMap, fmt};
+
+use serde::Deserialize;
+
+use crate::types::{OutputGroup, ScriptType};
+
+/// A coin listed by `listunspent`.
+#[derive(Debug, Clone)]
+pub struct CoreUtxo {
+    /// Id of the transaction that created the coin.
+    pub txid: String,
+    /// Index of the coin among the outputs of its transaction.
+    pub vout: u32,
+    /// The coin as a single-input group.
+    pub group: OutputGroup,
+}
+
+/// Errors returned by [`parse_listunspent`]. `index` is the position of the coin in the list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListUnspentError {
+    /// The input is not a valid `listunspent` result.
+    Json(String),
+    /// The `scriptPubKey` of a coin is not valid hex.
+    InvalidScriptPubKey { index: usize },
+    /// The script type of a coin is not one whose spending weight can be derived.
+    UnknownScriptType { index: usize },
+}
+
+impl fmt::Display for ListUnspentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListUnspentError::Json(err) => write!(f, "Invalid listunspent result: {err}"),
+            ListUnspentError::InvalidScriptPubKey { index } => {
+                write!(f, "The scriptPubKey of coin {index} is not valid hex")
+            }
+            ListUnspentError::UnknownScriptType { index } => {
+                write!(f, "The script type of coin {index} is not supported")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ListUnspentError {}
+
+/// The fields of a `listunspent` entry this module reads.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    txid: String,
+    vout: u32,
+    #[serde(default)]
+    address: Option<String>,
+    script_pub_key: String,
+    #[serde(default)]
+    redeem_script: Option<String>,
+    #[serde(default)]
+    label: Option<String>,
+    /// In BTC.
+    amount: f64,
+    confirmations: u32,
+    #[serde(default = "default_spendable")]
+    spendable: bool,
+    /// In virtual bytes.
+    #[serde(default, rename = "ancestorsize")]
+    ancestor_size: u64,
+    /// In sats.
+    #[serde(default, rename = "ancestorfees")]
+    ancestor_fees: u64,
+}
+
+fn default_spendable() -> bool {
+    true
+}
+
+/// Parses the JSON result of `listunspent` into coins, in the order Core listed them.
+pub fn parse_listunspent(json: &str) -> Result<Vec<CoreUtxo>, ListUnspentError> {
+    let entries: Vec<Entry> =
+        serde_json::from_str(json).map_err(|err| ListUnspentError::Json(err.to_string()))?;
+    let mut clusters = HashMap::new();
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let script_pubkey = decode_hex(&entry.script_pub_key)
+                .ok_or(ListUnspentError::InvalidScriptPubKey { index })?;
+            let redeem_script = entry.redeem_script.as_deref().and_then(decode_hex);
+            let script_type = script_type(&script_pubkey, redeem_script.as_deref())
+                .ok_or(ListUnspentError::UnknownScriptType { index })?;
+            let cluster = entry.address.map(|address| {
+                let next = clusters.len() as u32;
+                *clusters.entry(address).or_insert(next)
+            });
+            Ok(CoreUtxo {
+                txid: entry.txid,
+                vout: entry.vout,
+                group: OutputGroup {
+                    cluster,
+                    confirmations: entry.confirmations,
+                    frozen: !entry.spendable,
+                    ancestor_weight: entry.ancestor_size * 4,
+                    ancestor_fee: entry.ancestor_fees,
+                    labels: entry
+                        .label
+                        .into_iter()
+                        .filter(|label| !label.is_empty())
+                        .collect(),
+                    ..OutputGroup::from_script_type(
+                        (entry.amount * 100_000_000.0).round() as u64,
+                        script_type,
+                        1,
+                    )
+                },
+            })
+        })
+        .collect()
+}
+
+/// The script type of a coin paying to `script_pubkey`, see [`ScriptType`].
+fn script_type(script_pubkey: &[u8], redeem_script: Option<&[u8]>) -> Option<ScriptType> {
+    let is_p2wpkh = |script: &[u8]| script.len() == 22 && script[..2] == [0x00, 0x14];
+    match script_pubkey {
+        [0x76, 0xa9, 0x14, .., 0x88, 0xac] if script_pubkey.len() == 25 => Some(ScriptType::P2pkh),
+        [0xa9, 0x14, .., 0x87] if script_pubkey.len() == 23 => redeem_script
+            .filter(|script| is_p2wpkh(script))
+            .map(|_| ScriptType::P2shP2wpkh),
+        script if is_p2wpkh(script) => Some(ScriptType::P2wpkh),
+        [0x51, 0x20, ..] if script_pubkey.len() == 34 => Some(ScriptType::P2tr),
+        _ => None,
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        listunspent::{parse_listunspent, ListUnspentError},
+        types::ScriptType,
+    };
+
+    #[test]
+    fn test_parse_listunspent() {
+        let json = r#"[
+            {
+                "txid": "aa11", "vout": 0, "address": "bc1qa", "label": "savings",
+                "scriptPubKey": "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+                "amount": 0.0015, "confirmations": 6, "spendable": true
+            },
+            {
+                "txid": "bb22", "vout": 1, "address": "3Nested",
+                "scriptPubKey": "a914b472a266d0bd89c13706a4132ccfb16f7c3b9fcb87",
+                "redeemScript": "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+                "amount": 0.0002, "confirmations": 0,
+                "ancestorcount": 1, "ancestorsize": 141, "ancestorfees": 282
+            },
+            {
+                "txid": "cc33", "vout": 2, "address": "bc1qa",
+                "scriptPubKey": "5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+                "amount": 21000000, "confirmations": 1, "spendable": false
+            }
+        ]"#;
+        let utxos = parse_listunspent(json).unwrap();
+        assert_eq!(utxos.len(), 3);
+        assert_eq!(utxos[0].txid, "aa11");
+        assert_eq!(utxos[0].group.value, 150_000);
+        assert_eq!(utxos[0].group.weight, ScriptType::P2wpkh.input_weight());
+        assert_eq!(utxos[0].group.confirmations, 6);
+        assert_eq!(utxos[0].group.labels, vec!["savings".to_string()]);
+        assert!(utxos[1].group.labels.is_empty());
+        assert_eq!((utxos[1].txid.as_str(), utxos[1].vout), ("bb22", 1));
+        assert_eq!(utxos[1].group.value, 20_000);
+        assert_eq!(utxos[1].group.weight, ScriptType::P2shP2wpkh.input_weight());
+        assert_eq!(utxos[1].group.ancestor_weight, 564);
+        assert_eq!(utxos[1].group.ancestor_fee, 282);
+        assert_eq!(utxos[2].group.value, 2_100_000_000_000_000);
+        assert_eq!(utxos[2].group.weight, ScriptType::P2tr.input_weight());
+        assert!(utxos[2].group.frozen && !utxos[0].group.frozen);
+        // Coins on the same address share a cluster.
+        assert_eq!(utxos[0].group.cluster, utxos[2].group.cluster);
+        assert_ne!(utxos[0].group.cluster, utxos[1].group.cluster);
+
+        let unknown = r#"[{"txid": "aa11", "vout": 0, "scriptPubKey": "a914b472a266d0bd89c13706a4132ccfb16f7c3b9fcb87", "amount": 0.1, "confirmations": 1}]"#;
+        assert_eq!(
+            parse_listunspent(unknown).unwrap_err(),
+            ListUnspentError::UnknownScriptType { index: 0 }
+        );
+        assert!(matches!(
+            parse_listunspent("{}").unwrap_err(),
+            ListUnspentError::Json(_)
+        ));
+    }
+}
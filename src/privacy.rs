@@ -0,0 +1,319 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+
+use crate::{
+    selectcoin::change_value,
+    types::{CoinSelectionOpt, OutputGroup, SelectionMetric, SelectionOutput},
+    utils::{calculate_fee, target_feerate, SplitMix64},
+};
+
+/// Payment amounts that are multiples of this many sats look chosen by a human, see
+/// [`PrivacyScore::round_amount_leak`].
+pub const ROUND_AMOUNT: u64 = 10_000;
+
+/// What a selection reveals about the wallet to a chain observer, see [`privacy_score`].
+///
+/// Every field counts against privacy, so lower is better.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrivacyScore {
+    /// Ownership clusters ([`OutputGroup::cluster`]) the selection spends only part of. The groups
+    /// left behind, e.g. received on the same reused address, stay linked to the spent ones.
+    pub partial_spends: usize,
+    /// Ownership clusters linked together by the selection, beyond the first. Every selected
+    /// group without a cluster counts as a cluster of its own.
+    pub merged_clusters: usize,
+    /// The change output is smaller than the smallest selected input. Paying that little would
+    /// not have needed the input, so observers take this output for the change (the
+    /// unnecessary-input heuristic).
+    pub change_detectable: bool,
+    /// The payment is a round amount (a multiple of [`ROUND_AMOUNT`]) and the change is not, which
+    /// tells the two apart.
+    pub round_amount_leak: bool,
+}
+
+impl PrivacyScore {
+    /// Total penalty, one point per partial spend, merged cluster and leaking heuristic.
+    pub fn penalty(&self) -> u64 {
+        (self.partial_spends + self.merged_clusters) as u64
+            + u64::from(self.change_detectable)
+            + u64::from(self.round_amount_leak)
+    }
+}
+
+/// Rates the privacy of `output`, a selection from `inputs` funding `options`.
+pub fn privacy_score(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> PrivacyScore {
+    let selected: BTreeSet<usize> = output.selected_inputs.iter().copied().collect();
+    let mut spent_clusters = BTreeSet::new();
+    let mut kept_clusters = BTreeSet::new();
+    let mut unclustered = 0;
+    for (index, input) in inputs.iter().enumerate() {
+        match (input.cluster, selected.contains(&index)) {
+            (Some(cluster), true) => {
+                spent_clusters.insert(cluster);
+            }
+            (Some(cluster), false) => {
+                kept_clusters.insert(cluster);
+            }
+            (None, true) => unclustered += 1,
+            (None, false) => {}
+        }
+    }
+
+    let change = change_value(output, inputs, options);
+    let smallest_input = output
+        .selected_inputs
+        .iter()
+        .map(|&index| inputs[index].value)
+        .min()
+        .unwrap_or(0);
+    PrivacyScore {
+        partial_spends: spent_clusters.intersection(&kept_clusters).count(),
+        merged_clusters: (spent_clusters.len() + unclustered).saturating_sub(1),
+        change_detectable: change > 0 && change < smallest_input,
+        round_amount_leak: change > 0
+            && options.target_value.is_multiple_of(ROUND_AMOUNT)
+            && !change.is_multiple_of(ROUND_AMOUNT),
+    }
+}
+
+/// A [`SelectionMetric`] trading waste for privacy: the waste plus `sats_per_point` sats for every
+/// point of [`PrivacyScore::penalty`].
+///
+/// With it as [`CoinSelectionOpt::selection_metric`], a selection wasting up to `sats_per_point`
+/// more sats ranks first if it leaks one heuristic less.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrivacyMetric {
+    /// Sats of waste one point of privacy penalty is worth.
+    pub sats_per_point: u64,
+}
+
+impl SelectionMetric for PrivacyMetric {
+    fn score(
+        &self,
+        output: &SelectionOutput,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> i64 {
+        let penalty = privacy_score(output, inputs, options)
+            .penalty()
+            .saturating_mul(self.sats_per_point);
+        output
+            .waste
+            .0
+            .saturating_add(i64::try_from(penalty).unwrap_or(i64::MAX))
+    }
+}
+
+/// Change outputs proposed by [`split_change_randomized`] in place of a selection's single one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangeSplitProposal {
+    /// Values of the change outputs, each of `change_weight`, in random order.
+    pub values: Vec<u64>,
+    /// Fee of the change outputs beyond the first, which the selection did not pay for. The values
+    /// add up to the selection's change less this fee.
+    pub extra_fee: u64,
+}
+
+/// Proposes splitting the change of `output`, a selection from `inputs` funding `options`, into up
+/// to `max_outputs` outputs of random, non-round sizes, so that no output stands out as the change
+/// by its amount.
+///
+/// The number of outputs is drawn among those the change can fund: every output is worth at least
+/// `min_change_value`, and the outputs beyond the first pay their own fee at the target feerate.
+/// Sizes are drawn at random above that floor and nudged off multiples of [`ROUND_AMOUNT`]. When
+/// the change cannot fund two outputs, the proposal keeps the single one.
+///
+/// `seed` drives the draw; wallets should pass fresh randomness, as a predictable split leaks as
+/// much as no split. Returns `None` for a selection without change.
+pub fn split_change_randomized(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    max_outputs: usize,
+    seed: u64,
+) -> Option<ChangeSplitProposal> {
+    let change = change_value(output, inputs, options);
+    if change == 0 {
+        return None;
+    }
+    let output_fee = calculate_fee(options.change_weight, target_feerate(options));
+    let floor = options.min_change_value.max(1);
+    let fundable = (1..=max_outputs.max(1) as u64)
+        .take_while(|&count| {
+            (count - 1)
+                .checked_mul(output_fee)
+                .and_then(|fee| count.checked_mul(floor)?.checked_add(fee))
+                .is_some_and(|needed| needed <= change)
+        })
+        .last()
+        .unwrap_or(1);
+
+    let mut rng = SplitMix64::new(seed);
+    let count = if fundable > 1 {
+        2 + rng.below(fundable as usize - 1) as u64
+    } else {
+        1
+    };
+    let extra_fee = (count - 1) * output_fee;
+    let spread = (change - extra_fee).saturating_sub(count * floor);
+
+    // Random cut points across the spread, one output between each consecutive pair.
+    let mut cuts: Vec<u64> = (1..count)
+        .map(|_| rng.next_u64() % (spread + 1))
+        .chain([0, spread])
+        .collect();
+    cuts.sort_unstable();
+    let mut values: Vec<u64> = cuts
+        .windows(2)
+        .map(|pair| floor + pair[1] - pair[0])
+        .collect();
+    for index in 0..values.len() {
+        if values.len() > 1 && values[index].is_multiple_of(ROUND_AMOUNT) && values[index] > floor {
+            values[index] -= 1;
+            let next = (index + 1) % values.len();
+            values[next] += 1;
+        }
+    }
+    Some(ChangeSplitProposal { values, extra_fee })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        privacy::{
+            privacy_score, split_change_randomized, PrivacyMetric, PrivacyScore, ROUND_AMOUNT,
+        },
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, SelectionMetric,
+            SelectionOutput, WasteMetric,
+        },
+        utils::calculate_fee,
+    };
+
+    fn clustered(value: u64, cluster: Option<u32>) -> OutputGroup {
+        OutputGroup {
+            value,
+            weight: 100,
+            input_count: 1,
+            cluster,
+            ..Default::default()
+        }
+    }
+
+    fn output(selected_inputs: Vec<usize>) -> SelectionOutput {
+        SelectionOutput {
+            selected_inputs,
+            waste: WasteMetric(100),
+            fee: 1_000,
+            selected_value: 0,
+            selected_weight: 0,
+            effective_feerate: FeeRate::default(),
+            metrics: None,
+            dust_inputs: Vec::new(),
+        }
+    }
+
+    fn options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt::builder()
+            .target_value(target_value)
+            .target_feerate(FeeRate::from_sat_per_wu(1.0))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_privacy_score() {
+        let inputs = vec![
+            clustered(50_000, Some(1)),
+            clustered(30_000, Some(1)),
+            clustered(40_000, Some(2)),
+            clustered(20_000, None),
+        ];
+
+        // Links clusters 1 and 2, leaves part of cluster 1 behind, and pays a round 60_000 with
+        // 29_000 of change, below the smallest input.
+        let leaky = privacy_score(&output(vec![0, 2]), &inputs, &options(60_000));
+        assert_eq!(
+            leaky,
+            PrivacyScore {
+                partial_spends: 1,
+                merged_clusters: 1,
+                change_detectable: true,
+                round_amount_leak: true,
+            }
+        );
+        assert_eq!(leaky.penalty(), 4);
+
+        // Spends all of cluster 1 and pays a non-round 75_000; only the 4_000 of change stands out.
+        let careful = privacy_score(&output(vec![0, 1]), &inputs, &options(75_000));
+        assert_eq!(
+            careful,
+            PrivacyScore {
+                change_detectable: true,
+                ..PrivacyScore::default()
+            }
+        );
+
+        let metric = PrivacyMetric { sats_per_point: 50 };
+        assert_eq!(
+            metric.score(&output(vec![0, 2]), &inputs, &options(60_000)),
+            100 + 4 * 50
+        );
+    }
+
+    #[test]
+    fn test_split_change_randomized() {
+        let inputs = vec![clustered(1_000_000, None)];
+        let options = options(200_000);
+        let change = 1_000_000 - 200_000 - 1_000;
+        let output_fee = calculate_fee(options.change_weight, options.target_feerate);
+
+        let mut counts = Vec::new();
+        for seed in 0..64 {
+            let proposal = split_change_randomized(&output(vec![0]), &inputs, &options, 4, seed)
+                .expect("the selection has change");
+            let count = proposal.values.len();
+            assert!((2..=4).contains(&count), "{proposal:?}");
+            assert_eq!(proposal.extra_fee, (count as u64 - 1) * output_fee);
+            assert_eq!(
+                proposal.values.iter().sum::<u64>(),
+                change - proposal.extra_fee
+            );
+            for &value in &proposal.values {
+                assert!(value >= options.min_change_value);
+                assert!(!value.is_multiple_of(ROUND_AMOUNT), "{proposal:?}");
+            }
+            assert_eq!(
+                split_change_randomized(&output(vec![0]), &inputs, &options, 4, seed),
+                Some(proposal)
+            );
+            counts.push(count);
+        }
+        assert!(counts.contains(&2) && counts.contains(&4));
+
+        // A single output when asked for one, or when the change cannot fund two.
+        let single = split_change_randomized(&output(vec![0]), &inputs, &options, 1, 7).unwrap();
+        assert_eq!((single.values, single.extra_fee), (vec![change], 0));
+        let small = CoinSelectionOpt {
+            min_change_value: change / 2,
+            ..options.clone()
+        };
+        let single = split_change_randomized(&output(vec![0]), &inputs, &small, 4, 7).unwrap();
+        assert_eq!(single.values, vec![change]);
+
+        // No change, nothing to split.
+        let to_fee = CoinSelectionOpt {
+            excess_strategy: ExcessStrategy::ToFee,
+            ..options
+        };
+        assert_eq!(
+            split_change_randomized(&output(vec![0]), &inputs, &to_fee, 4, 7),
+            None
+        );
+    }
+}
@@ -1,265 +1,3086 @@
-use std::thread;
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeSet, sync::Arc, vec::Vec};
+use core::{any::Any, cmp::Reverse, time::Duration};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+#[cfg(feature = "std")]
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+    thread,
+    time::Instant,
+};
 
 use crate::{
     algorithms::{
-        bnb::select_coin_bnb, coingrinder::select_coin_coingrinder, fifo::select_coin_fifo,
-        lowestlarger::select_coin_lowestlarger,
+        annealing::{select_coin_annealing, select_coin_annealing_with_context},
+        blackjack::{select_coin_blackjack, select_coin_blackjack_with_context},
+        bnb::{select_coin_bnb, select_coin_bnb_with_context},
+        coingrinder::{select_coin_coingrinder, select_coin_coingrinder_with_context},
+        fewestinputs::{select_coin_fewest_inputs, select_coin_fewest_inputs_with_context},
+        fifo::{select_coin_fifo, select_coin_fifo_with_context},
+        genetic::{select_coin_genetic, select_coin_genetic_with_context},
+        knapsack::{select_coin_knapsack, select_coin_knapsack_with_context},
+        lowestlarger::{select_coin_lowestlarger, select_coin_lowestlarger_with_context},
+        minlinkage::{select_coin_minlinkage, select_coin_minlinkage_with_context},
+        srd::{select_coin_srd, select_coin_srd_with_context},
+        sweep::select_coin_sweep,
+    },
+    privacy::privacy_score,
+    types::{
+        AgePreference, Algorithm, AlgorithmOutcome, CoinSelectionOpt, Constraint, ExcessStrategy,
+        FeeRate, FeeRateBandSelection, OutputGroup, RankStrategy, SelectionError, SelectionOutput,
+        SelectionReport, SweepOutput, UnconfirmedInputs, UnconfirmedPolicy,
+    },
+    utils::{
+        insufficient_funds, is_dust, is_eligible, reprice_selection, split_change, unfunded_error,
+        Deadline, FundsTally, SelectionContext,
     },
-    types::{CoinSelectionOpt, OutputGroup, SelectionAlgorithm, SelectionError, SelectionOutput},
-    utils::insufficient_funds,
 };
+#[cfg(feature = "std")]
+use crate::{cache::SelectionCache, types::CancellationToken};
+
+/// A coin selection algorithm that can take part in the best-of search of [`select_coin`].
+///
+/// Every built-in [`Algorithm`] implements this trait. Downstream crates can implement it for their
+/// own algorithms and run them next to the built-in ones through an [`AlgorithmRegistry`].
+pub trait SelectionAlgorithm: Sync {
+    /// Human readable name of the algorithm.
+    fn name(&self) -> &'static str;
+
+    /// Tag attached to the results of this algorithm. Defaults to [`Algorithm::Custom`] carrying
+    /// [`SelectionAlgorithm::name`].
+    fn tag(&self) -> Algorithm {
+        Algorithm::Custom(self.name())
+    }
+
+    /// Selects inputs from `inputs` that fund `options`.
+    fn select(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Result<SelectionOutput, SelectionError>;
+}
+
+impl SelectionAlgorithm for Algorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Blackjack => "blackjack",
+            Algorithm::BranchAndBound => "bnb",
+            Algorithm::CoinGrinder => "coingrinder",
+            Algorithm::FewestInputs => "fewestinputs",
+            Algorithm::Fifo => "fifo",
+            Algorithm::Genetic => "genetic",
+            Algorithm::Knapsack => "knapsack",
+            Algorithm::LowestLarger => "lowestlarger",
+            Algorithm::MinimumLinkage => "minlinkage",
+            Algorithm::SimulatedAnnealing => "annealing",
+            Algorithm::SingleRandomDraw => "srd",
+            Algorithm::Custom(name) => name,
+        }
+    }
+
+    fn tag(&self) -> Algorithm {
+        *self
+    }
+
+    fn select(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Result<SelectionOutput, SelectionError> {
+        match self {
+            Algorithm::Blackjack => select_coin_blackjack(inputs, options),
+            Algorithm::BranchAndBound => select_coin_bnb(inputs, options),
+            Algorithm::CoinGrinder => select_coin_coingrinder(inputs, options),
+            Algorithm::FewestInputs => select_coin_fewest_inputs(inputs, options),
+            Algorithm::Fifo => select_coin_fifo(inputs, options),
+            Algorithm::Genetic => select_coin_genetic(inputs, options),
+            Algorithm::Knapsack => select_coin_knapsack(inputs, options),
+            Algorithm::LowestLarger => select_coin_lowestlarger(inputs, options),
+            Algorithm::MinimumLinkage => select_coin_minlinkage(inputs, options),
+            Algorithm::SimulatedAnnealing => select_coin_annealing(inputs, options),
+            Algorithm::SingleRandomDraw => select_coin_srd(inputs, options),
+            // A bare tag carries no implementation; register the algorithm itself instead.
+            Algorithm::Custom(_) => Err(SelectionError::NoSolutionFound {
+                constraint: Constraint::NoAlgorithm,
+            }),
+        }
+    }
+}
+
+impl Algorithm {
+    /// Like [`SelectionAlgorithm::select`], but reads the inputs off the shared `context` instead of
+    /// preparing and sorting them again. `context` must have been built from `inputs` and
+    /// `options`, which must not preselect any input.
+    fn select_with_context(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+        context: &Result<Arc<SelectionContext>, SelectionError>,
+    ) -> Result<SelectionOutput, SelectionError> {
+        if let Algorithm::Custom(_) = self {
+            return self.select(inputs, options);
+        }
+        let context = context.as_ref().map_err(|error| *error)?;
+        match self {
+            Algorithm::Blackjack => select_coin_blackjack_with_context(inputs, options, context),
+            Algorithm::BranchAndBound => select_coin_bnb_with_context(inputs, options, context),
+            Algorithm::CoinGrinder => {
+                select_coin_coingrinder_with_context(inputs, options, context)
+            }
+            Algorithm::FewestInputs => {
+                select_coin_fewest_inputs_with_context(inputs, options, context)
+            }
+            Algorithm::Fifo => select_coin_fifo_with_context(inputs, options, context),
+            Algorithm::Genetic => select_coin_genetic_with_context(inputs, options, context),
+            Algorithm::Knapsack => select_coin_knapsack_with_context(inputs, options, context),
+            Algorithm::LowestLarger => {
+                select_coin_lowestlarger_with_context(inputs, options, context)
+            }
+            Algorithm::MinimumLinkage => {
+                select_coin_minlinkage_with_context(inputs, options, context)
+            }
+            Algorithm::SimulatedAnnealing => {
+                select_coin_annealing_with_context(inputs, options, context)
+            }
+            Algorithm::SingleRandomDraw => select_coin_srd_with_context(inputs, options, context),
+            Algorithm::Custom(_) => unreachable!("handled above"),
+        }
+    }
+}
+
+/// The algorithms run by [`select_coin`].
+pub const DEFAULT_ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::Blackjack,
+    Algorithm::BranchAndBound,
+    Algorithm::CoinGrinder,
+    Algorithm::FewestInputs,
+    Algorithm::Fifo,
+    Algorithm::LowestLarger,
+    Algorithm::SimulatedAnnealing,
+];
+
+/// Reported for an algorithm that panicked, rather than poisoning the whole selection.
+#[cfg(feature = "std")]
+const PANICKED: SelectionError = SelectionError::NoSolutionFound {
+    constraint: Constraint::AlgorithmPanicked,
+};
+
+/// `options` with the budget of `algorithm` in [`CoinSelectionOpt::algorithm_durations`], if it
+/// has one, as the `max_duration`.
+fn budgeted_options(options: &CoinSelectionOpt, algorithm: Algorithm) -> Cow<'_, CoinSelectionOpt> {
+    match options
+        .algorithm_durations
+        .iter()
+        .find(|(budgeted, _)| *budgeted == algorithm)
+    {
+        Some(&(_, duration)) if options.max_duration != Some(duration) => {
+            Cow::Owned(CoinSelectionOpt {
+                max_duration: Some(duration),
+                ..options.clone()
+            })
+        }
+        _ => Cow::Borrowed(options),
+    }
+}
+
+/// Reported for an exhaustive search skipped on a pool above
+/// [`CoinSelectionOpt::max_search_pool_size`].
+const POOL_TOO_LARGE: SelectionError = SelectionError::NoSolutionFound {
+    constraint: Constraint::PoolTooLarge,
+};
+
+/// Whether the wrapper API skips `algorithm` on a pool of `pool_size` inputs, see
+/// [`CoinSelectionOpt::max_search_pool_size`].
+fn skips_search(options: &CoinSelectionOpt, algorithm: Algorithm, pool_size: usize) -> bool {
+    matches!(
+        algorithm,
+        Algorithm::BranchAndBound | Algorithm::CoinGrinder | Algorithm::FewestInputs
+    ) && options
+        .max_search_pool_size
+        .is_some_and(|max_search_pool_size| pool_size > max_search_pool_size)
+}
+
+/// A set of [`SelectionAlgorithm`]s that are run together and ranked against each other.
+#[derive(Default)]
+pub struct AlgorithmRegistry {
+    algorithms: Vec<Registered>,
+    #[cfg(feature = "std")]
+    cache: Option<SelectionCache>,
+}
+
+/// An algorithm of an [`AlgorithmRegistry`].
+struct Registered {
+    algorithm: Box<dyn SelectionAlgorithm>,
+    /// Set for the built-in algorithms, which share one [`SelectionContext`] per selection.
+    builtin: Option<Algorithm>,
+}
+
+impl Registered {
+    fn tag(&self) -> Algorithm {
+        self.algorithm.tag()
+    }
+
+    /// Runs the algorithm, through `context` when it is built-in and one was prepared.
+    fn select(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+        context: Option<&Result<Arc<SelectionContext>, SelectionError>>,
+    ) -> Result<SelectionOutput, SelectionError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("algorithm", name = self.algorithm.name()).entered();
+        let options = &*budgeted_options(options, self.tag());
+        let result = match (self.builtin, context) {
+            (Some(algorithm), _) if skips_search(options, algorithm, inputs.len()) => {
+                Err(POOL_TOO_LARGE)
+            }
+            (Some(algorithm), Some(context)) => {
+                algorithm.select_with_context(inputs, options, context)
+            }
+            _ => self.algorithm.select(inputs, options),
+        };
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(output) => tracing::debug!(
+                selected = output.selected_inputs.len(),
+                fee = output.fee,
+                waste = output.waste.0,
+                tries = output.metrics.map(|metrics| metrics.tries),
+                pruned = output.metrics.map(|metrics| metrics.pruned),
+                "selection found"
+            ),
+            Err(error) => tracing::debug!(%error, "no selection"),
+        }
+        result
+    }
+}
+
+impl AlgorithmRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry holding [`DEFAULT_ALGORITHMS`], i.e. what [`select_coin`] runs.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        for algorithm in DEFAULT_ALGORITHMS {
+            registry.register(algorithm);
+        }
+        registry
+    }
+
+    /// Adds `algorithm` to the registry. An algorithm whose [`SelectionAlgorithm::tag`] is already
+    /// registered is ignored, so every tag runs at most once.
+    pub fn register(&mut self, algorithm: impl SelectionAlgorithm + 'static) -> &mut Self {
+        let tag = algorithm.tag();
+        if self
+            .algorithms
+            .iter()
+            .all(|registered| registered.tag() != tag)
+        {
+            let builtin = (&algorithm as &dyn Any)
+                .downcast_ref::<Algorithm>()
+                .copied();
+            self.algorithms.push(Registered {
+                algorithm: Box::new(algorithm),
+                builtin,
+            });
+        }
+        self
+    }
+
+    /// Keeps the prepared pools of this registry's selections in `cache`, so that selecting again
+    /// from an unchanged pool skips the preparation. See [`SelectionCache`].
+    #[cfg(feature = "std")]
+    pub fn set_cache(&mut self, cache: SelectionCache) -> &mut Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The cache set by [`AlgorithmRegistry::set_cache`], if any.
+    #[cfg(feature = "std")]
+    pub fn cache(&self) -> Option<&SelectionCache> {
+        self.cache.as_ref()
+    }
+
+    /// Tags of the registered algorithms, in registration order.
+    pub fn algorithms(&self) -> impl Iterator<Item = Algorithm> + '_ {
+        self.algorithms.iter().map(|algorithm| algorithm.tag())
+    }
+
+    /// Runs every registered algorithm in parallel and returns all successful results, ordered
+    /// best-first as described on [`select_coin`].
+    ///
+    /// Returns [`SelectionError::NoSolutionFound`] when the registry is empty.
+    pub fn select_coin(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError> {
+        let report = self.select_coin_detailed(inputs, options)?;
+        Ok(report
+            .outcomes
+            .into_iter()
+            .filter_map(|outcome| {
+                outcome
+                    .result
+                    .ok()
+                    .map(|output| (outcome.algorithm, output))
+            })
+            .collect())
+    }
+
+    /// Like [`AlgorithmRegistry::select_coin`], but takes the inputs as an iterator, which it
+    /// buffers, see [`select_coin_from_iter`].
+    pub fn select_coin_from_iter<I>(
+        &self,
+        inputs: I,
+        options: &CoinSelectionOpt,
+    ) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError>
+    where
+        I: IntoIterator<Item = OutputGroup>,
+    {
+        options.validate()?;
+
+        // Keep only the economically spendable inputs, remembering their position in the stream.
+        // Preselected inputs are always kept, and their indices moved to the kept positions.
+        let mut tally = FundsTally::default();
+        let mut candidates = Vec::new();
+        let mut positions = Vec::new();
+        let mut preselected_inputs = Vec::new();
+        let mut dust = Vec::new();
+        let mut stream_len = 0;
+        for (position, input) in inputs.into_iter().enumerate() {
+            stream_len += 1;
+            tally.add(&input, options);
+            let preselected = options.preselected_inputs.contains(&position);
+            if preselected {
+                preselected_inputs.push(candidates.len());
+            }
+            if !preselected && !is_eligible(&input, options) {
+                continue;
+            }
+            if preselected || !is_dust(&input, options) {
+                candidates.push(input);
+                positions.push(position);
+            } else {
+                dust.push(position);
+            }
+        }
+        if options
+            .preselected_inputs
+            .iter()
+            .any(|&index| index >= stream_len)
+        {
+            return Err(SelectionError::PreselectedInputOutOfRange);
+        }
+        let options = &CoinSelectionOpt {
+            preselected_inputs,
+            ..options.clone()
+        };
+
+        let mut ranked = match self.select_coin(&candidates, options) {
+            // Report the totals of the whole stream, not just of the kept candidates.
+            Err(SelectionError::InsufficientFunds { .. }) => {
+                return Err(tally.insufficient_funds(options))
+            }
+            result => result?,
+        };
+        for (_, output) in &mut ranked {
+            for index in &mut output.selected_inputs {
+                *index = positions[*index];
+            }
+            output.dust_inputs.clone_from(&dust);
+        }
+        Ok(ranked)
+    }
+
+    /// Like [`AlgorithmRegistry::select_coin`], but ranks the results by `score` (lowest first)
+    /// instead of [`CoinSelectionOpt::rank_strategy`], see [`select_coin_scored`].
+    pub fn select_coin_scored<F>(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+        score: F,
+    ) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError>
+    where
+        F: Fn(&SelectionOutput, &[OutputGroup], &CoinSelectionOpt) -> f64,
+    {
+        let mut scored: Vec<_> = self
+            .select_coin(inputs, options)?
+            .into_iter()
+            .map(|(algorithm, output)| (score(&output, inputs, options), algorithm, output))
+            .collect();
+        scored.sort_by(|(a, ..), (b, ..)| a.total_cmp(b));
+        Ok(scored
+            .into_iter()
+            .map(|(_, algorithm, output)| (algorithm, output))
+            .collect())
+    }
+
+    /// Like [`AlgorithmRegistry::select_coin`], but reports the outcome of every algorithm,
+    /// including the ones that failed.
+    pub fn select_coin_detailed(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Result<SelectionReport, SelectionError> {
+        if options.unconfirmed_policy.prefer_confirmed
+            && options.unconfirmed_policy.allowed != UnconfirmedInputs::ConfirmedOnly
+        {
+            let confirmed_options = CoinSelectionOpt {
+                unconfirmed_policy: UnconfirmedPolicy {
+                    allowed: UnconfirmedInputs::ConfirmedOnly,
+                    ..options.unconfirmed_policy
+                },
+                ..options.clone()
+            };
+            match self.select_coin_detailed_once(inputs, &confirmed_options) {
+                Err(error) if !is_fatal(&error) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(%error, "falling back to unconfirmed inputs");
+                }
+                result => return result,
+            }
+        }
+        self.select_coin_detailed_once(inputs, options)
+    }
+
+    /// [`AlgorithmRegistry::select_coin_detailed`] without the confirmed-first attempt of
+    /// [`UnconfirmedPolicy::prefer_confirmed`].
+    fn select_coin_detailed_once(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Result<SelectionReport, SelectionError> {
+        if self.algorithms.is_empty() {
+            return Err(SelectionError::NoSolutionFound {
+                constraint: Constraint::NoAlgorithm,
+            });
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "select_coin",
+            inputs = inputs.len(),
+            target_value = options.target_value,
+            target_feerate = options.target_feerate.as_sat_per_kwu(),
+            algorithms = self.algorithms.len()
+        )
+        .entered();
+
+        let outcomes = self.run_all(inputs, options);
+        if let Some(error) = outcomes.iter().find_map(|(_, (outcome, _))| match outcome {
+            Err(error) if is_fatal(error) => Some(*error),
+            _ => None,
+        }) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%error, "selection aborted");
+            return Err(error);
+        }
+        if outcomes.iter().all(|(_, (outcome, _))| outcome.is_err()) {
+            return Err(unfunded_error(options, insufficient_funds(inputs, options)));
+        }
+
+        let mut outcomes: Vec<AlgorithmOutcome> = outcomes
+            .into_iter()
+            .map(|(algorithm, (result, runtime))| AlgorithmOutcome {
+                algorithm,
+                runtime,
+                change_value: result
+                    .as_ref()
+                    .ok()
+                    .map(|output| change_value(output, inputs, options)),
+                privacy: result
+                    .as_ref()
+                    .ok()
+                    .map(|output| privacy_score(output, inputs, options)),
+                result,
+            })
+            .collect();
+        // Successes best-first, failures after them in registration order.
+        outcomes.sort_by_key(|outcome| match &outcome.result {
+            Ok(output) => (false, rank_key(output, inputs, options)),
+            Err(_) => (true, (false, 0, 0, 0, 0)),
+        });
+        #[cfg(feature = "tracing")]
+        for (rank, outcome) in outcomes.iter().enumerate() {
+            tracing::debug!(
+                rank,
+                algorithm = outcome.algorithm.name(),
+                runtime = ?outcome.runtime,
+                succeeded = outcome.result.is_ok(),
+                rank_strategy = ?options.rank_strategy,
+                "ranked"
+            );
+        }
+        Ok(SelectionReport {
+            winner: outcomes[0].algorithm,
+            outcomes,
+        })
+    }
+
+    /// Prepares the [`SelectionContext`] shared by the built-in algorithms, or takes it from the
+    /// cache, unless none is registered or `options` preselects inputs, which every algorithm then
+    /// handles on its own.
+    fn shared_context(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Option<Result<Arc<SelectionContext>, SelectionError>> {
+        let any_builtin = self.algorithms.iter().any(|algo| algo.builtin.is_some());
+        if !any_builtin || !options.preselected_inputs.is_empty() {
+            return None;
+        }
+        #[cfg(feature = "std")]
+        if let Some(cache) = &self.cache {
+            return Some(cache.context(inputs, options));
+        }
+        Some(SelectionContext::new(inputs, options).map(Arc::new))
+    }
+
+    /// Runs every registered algorithm concurrently, along with how long each took. Checks only
+    /// after all threads return and join.
+    #[cfg(feature = "std")]
+    fn run_all(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Vec<(Algorithm, Timed)> {
+        // With `early_exit`, the algorithms poll a child of the caller's token, which the first
+        // changeless selection cancels.
+        let early_exit = options
+            .early_exit
+            .then(|| CancellationToken::child(options.cancellation.as_ref()));
+        let early_exit_options;
+        let run_options = match &early_exit {
+            Some(token) => {
+                early_exit_options = CoinSelectionOpt {
+                    cancellation: Some(token.clone()),
+                    ..options.clone()
+                };
+                &early_exit_options
+            }
+            None => options,
+        };
+        let context = self.shared_context(inputs, run_options);
+        // Threads do not inherit the subscriber nor the current span, so hand both to each of them.
+        #[cfg(feature = "tracing")]
+        let (dispatch, parent) = (
+            &tracing::dispatcher::get_default(Clone::clone),
+            &tracing::Span::current(),
+        );
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .algorithms
+                .iter()
+                .map(|algo| {
+                    let (early_exit, context) = (&early_exit, &context);
+                    let handle = scope.spawn(move || {
+                        #[cfg(feature = "tracing")]
+                        let _dispatch = tracing::dispatcher::set_default(dispatch);
+                        #[cfg(feature = "tracing")]
+                        let _parent = parent.enter();
+                        let started = Instant::now();
+                        let result = algo.select(inputs, run_options, context.as_ref());
+                        if let Some(token) = early_exit {
+                            if result
+                                .as_ref()
+                                .is_ok_and(|output| is_changeless(output, inputs, options))
+                            {
+                                token.cancel();
+                            }
+                        }
+                        (stopped_early(result, options), started.elapsed())
+                    });
+                    (algo.tag(), handle)
+                })
+                .collect();
+            handles
+                .into_iter()
+                // A panicking algorithm is treated as "no solution" rather than poisoning the API.
+                .map(|(name, handle)| {
+                    (
+                        name,
+                        handle.join().unwrap_or((Err(PANICKED), Duration::ZERO)),
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// Runs every registered algorithm one after the other. Without threads or a clock, every
+    /// runtime is reported as zero. With `early_exit`, the algorithms after the first changeless
+    /// selection are skipped.
+    #[cfg(not(feature = "std"))]
+    fn run_all(
+        &self,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> Vec<(Algorithm, Timed)> {
+        let context = self.shared_context(inputs, options);
+        let mut found_changeless = false;
+        self.algorithms
+            .iter()
+            .map(|algo| {
+                let result = if found_changeless {
+                    Err(EARLY_EXIT)
+                } else {
+                    algo.select(inputs, options, context.as_ref())
+                };
+                found_changeless = options.early_exit
+                    && result
+                        .as_ref()
+                        .is_ok_and(|output| is_changeless(output, inputs, options));
+                (algo.tag(), (result, Duration::ZERO))
+            })
+            .collect()
+    }
+}
+
+/// Reported for an algorithm skipped or stopped by [`CoinSelectionOpt::early_exit`].
+const EARLY_EXIT: SelectionError = SelectionError::NoSolutionFound {
+    constraint: Constraint::EarlyExit,
+};
+
+/// Reports an algorithm cancelled by [`CoinSelectionOpt::early_exit`] rather than by the caller
+/// as [`EARLY_EXIT`], so that it does not abort the whole selection.
+#[cfg(feature = "std")]
+fn stopped_early(
+    result: Result<SelectionOutput, SelectionError>,
+    options: &CoinSelectionOpt,
+) -> Result<SelectionOutput, SelectionError> {
+    match result {
+        Err(SelectionError::Cancelled)
+            if !options
+                .cancellation
+                .as_ref()
+                .is_some_and(|token| token.is_cancelled()) =>
+        {
+            Err(EARLY_EXIT)
+        }
+        result => result,
+    }
+}
+
+/// The outcome of one algorithm along with its runtime.
+type Timed = (Result<SelectionOutput, SelectionError>, Duration);
+
+/// Whether `error` stems from the request itself, so that every algorithm fails the same way and
+/// the whole selection must be aborted.
+fn is_fatal(error: &SelectionError) -> bool {
+    match error {
+        SelectionError::NonPositiveTarget
+        | SelectionError::NonPositiveFeeRate
+        | SelectionError::AbnormallyHighFeeRate
+        | SelectionError::InvalidFeeRateBand
+        | SelectionError::ZeroChangeWeight
+        | SelectionError::ZeroMinChangeValue
+        | SelectionError::PreselectedInputOutOfRange
+        | SelectionError::PreselectedInputFrozen
+        | SelectionError::PreselectedInputLocked
+        | SelectionError::ArithmeticOverflow
+        | SelectionError::ZeroInputCount
+        | SelectionError::Cancelled => true,
+        SelectionError::InsufficientFunds { .. } | SelectionError::NoSolutionFound { .. } => false,
+    }
+}
+
+/// The global coin selection API. Runs every algorithm in [`DEFAULT_ALGORITHMS`] and returns *all*
+/// successful results, each tagged with the [`Algorithm`] that produced it, ordered best-first.
+///
+/// The best-result policy is chosen by [`CoinSelectionOpt::rank_strategy`]. The default,
+/// [`RankStrategy::MinInputs`], is: fewest real UTXOs, then fewest groups, then least waste. So the
+/// first element is the overall best selection. (`selected_inputs.len()` counts the chosen
+/// [`OutputGroup`]s, whereas the `input_count` sum counts the actual UTXOs they bundle; the two
+/// differ only when a group holds more than one UTXO.)
+pub fn select_coin(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError> {
+    AlgorithmRegistry::with_defaults().select_coin(inputs, options)
+}
+
+/// Like [`select_coin`], but takes the inputs as any iterator of [`OutputGroup`]s, e.g. rows read
+/// lazily from a database.
+///
+/// The iterator is read to the end and the inputs it yields are buffered in a `Vec` before any
+/// algorithm runs. Ineligible inputs and inputs whose effective value is below
+/// [`CoinSelectionOpt::dust_threshold`] are dropped as they are read (the latter reported in
+/// [`SelectionOutput::dust_inputs`]), and the remaining ones are moved rather than cloned, so only
+/// the spendable part of the pool is held in memory. `selected_inputs` refer to positions in the
+/// iteration order.
+pub fn select_coin_from_iter<I>(
+    inputs: I,
+    options: &CoinSelectionOpt,
+) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError>
+where
+    I: IntoIterator<Item = OutputGroup>,
+{
+    AlgorithmRegistry::with_defaults().select_coin_from_iter(inputs, options)
+}
+
+/// Like [`select_coin`], but ranks the results with a caller-supplied `score`, lowest first.
+///
+/// This lets wallets weigh criteria the built-in [`RankStrategy`]s cannot express, such as privacy
+/// or the effect on their UTXO pool. Results with equal scores keep their
+/// [`CoinSelectionOpt::rank_strategy`] order. Scores are compared with [`f64::total_cmp`].
+pub fn select_coin_scored<F>(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    score: F,
+) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError>
+where
+    F: Fn(&SelectionOutput, &[OutputGroup], &CoinSelectionOpt) -> f64,
+{
+    AlgorithmRegistry::with_defaults().select_coin_scored(inputs, options, score)
+}
+
+/// Runs the same algorithms as [`select_coin`] and reports which one won, together with every
+/// algorithm's selection (with its waste and change) or the error it returned.
+pub fn select_coin_detailed(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<SelectionReport, SelectionError> {
+    AlgorithmRegistry::with_defaults().select_coin_detailed(inputs, options)
+}
+
+/// Like [`select_coin`], but only runs the given `algorithms`, so callers do not pay for the ones
+/// they are not interested in. Repeated entries run once.
+///
+/// Returns [`SelectionError::NoSolutionFound`] when `algorithms` is empty.
+pub fn select_coin_with(
+    algorithms: &[Algorithm],
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError> {
+    let mut registry = AlgorithmRegistry::new();
+    for &algorithm in algorithms {
+        registry.register(algorithm);
+    }
+    registry.select_coin(inputs, options)
+}
+
+/// Like [`select_coin`], but returns selections that stay valid at every feerate from
+/// `min_feerate` to `max_feerate`, for transactions broadcast later or bumped through RBF without
+/// selecting again. `options.target_feerate` is ignored.
+///
+/// The selections are made at `max_feerate`. Spending the same inputs at a lower feerate only
+/// lowers the fee, raises every effective value and leaves more excess, so each selection still
+/// covers the target, spends no uneconomical or dust input and keeps its change above
+/// [`CoinSelectionOpt::min_change_value`] across the whole band. Each one is also priced again at
+/// `min_feerate`, and returned with its fee, waste and change at both ends; a selection that does
+/// not hold there, e.g. because a larger change falls outside [`CoinSelectionOpt::change_range`],
+/// is left out.
+///
+/// Returns [`SelectionError::InvalidFeeRateBand`] if `min_feerate` is above `max_feerate`, and
+/// [`SelectionError::NonPositiveFeeRate`] if `min_feerate` is zero.
+pub fn select_coin_feerate_band(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    min_feerate: FeeRate,
+    max_feerate: FeeRate,
+) -> Result<Vec<FeeRateBandSelection>, SelectionError> {
+    if min_feerate > max_feerate {
+        return Err(SelectionError::InvalidFeeRateBand);
+    }
+    if min_feerate == FeeRate::default() {
+        return Err(SelectionError::NonPositiveFeeRate);
+    }
+    let at_max = CoinSelectionOpt {
+        target_feerate: max_feerate,
+        ..options.clone()
+    };
+    let at_min = CoinSelectionOpt {
+        target_feerate: min_feerate,
+        ..options.clone()
+    };
+    let mut band = Vec::new();
+    let mut last_error = None;
+    for (algorithm, output) in select_coin(inputs, &at_max)? {
+        match reprice_selection(inputs, &at_min, &output.selected_inputs) {
+            Ok(repriced) => band.push(FeeRateBandSelection {
+                algorithm,
+                at_max: output,
+                at_min: repriced,
+            }),
+            Err(error) if is_fatal(&error) => return Err(error),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    match last_error {
+        Some(error) if band.is_empty() => Err(error),
+        _ => Ok(band),
+    }
+}
+
+/// Outcome of [`select_coin_or_partial`].
+#[derive(Debug)]
+pub enum Funding {
+    /// The payment is funded, by the ranked selections of [`select_coin`].
+    Full(Vec<(Algorithm, SelectionOutput)>),
+    /// The inputs fall short of the payment. `sweep` spends every economical input, as
+    /// [`select_coin_sweep`] does, leaving `sweep.max_sendable` for the recipient, `deficit` sats
+    /// short of the target.
+    Partial { sweep: SweepOutput, deficit: u64 },
+}
+
+/// Like [`select_coin`], but on [`SelectionError::InsufficientFunds`] returns the most the inputs
+/// can send and how far it falls short, so a wallet can offer to send the maximum available or ask
+/// for a top-up instead of failing.
+///
+/// Every other error is returned as is, as is the insufficient funds error when nothing can be sent
+/// at all.
+pub fn select_coin_or_partial(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> Result<Funding, SelectionError> {
+    match select_coin(inputs, options) {
+        Ok(selections) => Ok(Funding::Full(selections)),
+        Err(error @ SelectionError::InsufficientFunds { .. }) => {
+            match select_coin_sweep(inputs, options) {
+                Ok(sweep) if sweep.max_sendable < options.target_value => Ok(Funding::Partial {
+                    deficit: options.target_value - sweep.max_sendable,
+                    sweep,
+                }),
+                _ => Err(error),
+            }
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Runs [`select_coin`] on a dedicated thread and returns a future resolving to its result.
+///
+/// The future is runtime agnostic and never blocks the polling task, so a long BnB or CoinGrinder
+/// search does not tie up an async worker. `options.cancellation` is set to a fresh
+/// [`CancellationToken`] if it is `None`; cancelling the token, or dropping the future before it
+/// completes, aborts the search and the future resolves to [`SelectionError::Cancelled`].
+#[cfg(feature = "async")]
+pub fn select_coin_async(
+    inputs: Vec<OutputGroup>,
+    mut options: CoinSelectionOpt,
+) -> SelectCoinFuture {
+    let cancellation = options
+        .cancellation
+        .get_or_insert_with(CancellationToken::new)
+        .clone();
+    let shared = Arc::new(Mutex::new(AsyncState::default()));
+    let worker_state = Arc::clone(&shared);
+    thread::spawn(move || {
+        let result = select_coin(&inputs, &options);
+        let mut state = worker_state.lock().unwrap_or_else(|e| e.into_inner());
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    SelectCoinFuture {
+        shared,
+        cancellation,
+    }
+}
+
+#[cfg(feature = "async")]
+type RankedSelections = Result<Vec<(Algorithm, SelectionOutput)>, SelectionError>;
+
+#[cfg(feature = "async")]
+#[derive(Default)]
+struct AsyncState {
+    result: Option<RankedSelections>,
+    waker: Option<Waker>,
+}
+
+/// Future returned by [`select_coin_async`].
+#[cfg(feature = "async")]
+pub struct SelectCoinFuture {
+    shared: Arc<Mutex<AsyncState>>,
+    cancellation: CancellationToken,
+}
 
-/// Signature shared by every individual coin selection algorithm.
-type CoinSelectionFn =
-    fn(&[OutputGroup], &CoinSelectionOpt) -> Result<SelectionOutput, SelectionError>;
+#[cfg(feature = "async")]
+impl SelectCoinFuture {
+    /// Aborts the running selection; the future then resolves to [`SelectionError::Cancelled`].
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+}
+
+#[cfg(feature = "async")]
+impl Future for SelectCoinFuture {
+    type Output = RankedSelections;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for SelectCoinFuture {
+    fn drop(&mut self) {
+        // Nobody is waiting for the result anymore, so stop burning CPU on it.
+        self.cancellation.cancel();
+    }
+}
+
+/// Runs every algorithm in [`DEFAULT_ALGORITHMS`] in the background and returns an iterator that
+/// yields each algorithm's outcome as soon as it completes, fastest first.
+///
+/// The caller can stop iterating once an acceptable selection shows up instead of waiting for the
+/// slowest search. Algorithms still running keep going in the background until they finish; set
+/// [`CoinSelectionOpt::cancellation`] and cancel it to stop them as well. As with [`select_coin`],
+/// the inputs are prepared once for all the algorithms, and [`CoinSelectionOpt::early_exit`] stops
+/// the others after the first changeless selection. A panicking algorithm yields
+/// [`SelectionError::NoSolutionFound`].
+#[cfg(feature = "std")]
+pub fn select_coin_iter(inputs: Vec<OutputGroup>, options: CoinSelectionOpt) -> SelectionIter {
+    let inputs: Arc<[OutputGroup]> = inputs.into();
+    // With `early_exit`, the algorithms poll a child of the caller's token, which the first
+    // changeless selection cancels.
+    let early_exit = options
+        .early_exit
+        .then(|| CancellationToken::child(options.cancellation.as_ref()));
+    let run_options = Arc::new(CoinSelectionOpt {
+        cancellation: early_exit.clone().or_else(|| options.cancellation.clone()),
+        ..options.clone()
+    });
+    let options = Arc::new(options);
+    // Preselected inputs are handled by every algorithm on its own, as in `select_coin`.
+    let context = run_options
+        .preselected_inputs
+        .is_empty()
+        .then(|| SelectionContext::new(&inputs, &run_options).map(Arc::new));
+    let (sender, receiver) = mpsc::channel();
+    for algorithm in DEFAULT_ALGORITHMS {
+        let inputs = Arc::clone(&inputs);
+        let (options, run_options) = (Arc::clone(&options), Arc::clone(&run_options));
+        let (early_exit, context) = (early_exit.clone(), context.clone());
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let budgeted = budgeted_options(&run_options, algorithm);
+            let result = if skips_search(&budgeted, algorithm, inputs.len()) {
+                Err(POOL_TOO_LARGE)
+            } else {
+                panic::catch_unwind(AssertUnwindSafe(|| match &context {
+                    Some(context) => algorithm.select_with_context(&inputs, &budgeted, context),
+                    None => algorithm.select(&inputs, &budgeted),
+                }))
+                .unwrap_or(Err(PANICKED))
+            };
+            if let Some(token) = early_exit {
+                if result
+                    .as_ref()
+                    .is_ok_and(|output| is_changeless(output, &inputs, &options))
+                {
+                    token.cancel();
+                }
+            }
+            // The receiver is gone if the caller stopped iterating; nothing left to do then.
+            let _ = sender.send((algorithm, stopped_early(result, &options)));
+        });
+    }
+    SelectionIter { receiver }
+}
+
+/// Iterator returned by [`select_coin_iter`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SelectionIter {
+    receiver: mpsc::Receiver<(Algorithm, Result<SelectionOutput, SelectionError>)>,
+}
+
+#[cfg(feature = "std")]
+impl Iterator for SelectionIter {
+    type Item = (Algorithm, Result<SelectionOutput, SelectionError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Ends once every worker has reported and dropped its sender.
+        self.receiver.recv().ok()
+    }
+}
+
+/// Returns up to `n` distinct selections across all algorithms, ordered by the waste metric plus
+/// the [`OutputGroup::signing_cost`] of the selected inputs and any
+/// [`AgePreference::Weighted`] and [`PoolSizeTarget`](crate::types::PoolSizeTarget) penalties.
+///
+/// Several algorithms often converge on the same input set; such duplicates are collapsed into one
+/// entry, tagged with the algorithm ranked first for it by [`select_coin`]. Selections with equal
+/// waste keep their [`CoinSelectionOpt::rank_strategy`] order. This lets interactive wallets offer
+/// alternatives (e.g. fewer inputs vs. less change) instead of a single answer.
+///
+/// When the algorithms of [`select_coin`] yield fewer than `n` distinct selections, the randomized
+/// ones ([`SEEDED_ALGORITHMS`]) are run again with other seeds, derived from
+/// [`CoinSelectionOpt::seed`], until `n` are found, [`TOP_N_RESEEDS`] rounds have run or
+/// [`CoinSelectionOpt::max_duration`] has elapsed since the call. Fewer than `n` selections are
+/// returned when the pool does not hold that many.
+pub fn select_coin_top_n(
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+    n: usize,
+) -> Result<Vec<(Algorithm, SelectionOutput)>, SelectionError> {
+    let deadline = Deadline::start(options);
+    let mut results = select_coin(inputs, options)?;
+    let mut seen: BTreeSet<Vec<usize>> = BTreeSet::new();
+    results.retain(|(_, output)| seen.insert(sorted_selection(output)));
+
+    let base_seed = options.seed.unwrap_or_default();
+    for round in 1..=TOP_N_RESEEDS {
+        if results.len() >= n || deadline.has_passed() {
+            break;
+        }
+        let reseeded = CoinSelectionOpt {
+            seed: Some(base_seed.wrapping_add(round)),
+            ..options.clone()
+        };
+        // The request was valid for the first run, so only a search that found nothing fails here.
+        let Ok(more) = select_coin_with(&SEEDED_ALGORITHMS, inputs, &reseeded) else {
+            continue;
+        };
+        results.extend(
+            more.into_iter()
+                .filter(|(_, output)| seen.insert(sorted_selection(output))),
+        );
+    }
+
+    results.sort_by_key(|(_, output)| ranked_waste(output, inputs, options));
+    results.truncate(n);
+    Ok(results)
+}
+
+/// The randomized algorithms [`select_coin_top_n`] runs again with other seeds.
+pub const SEEDED_ALGORITHMS: [Algorithm; 3] = [
+    Algorithm::Genetic,
+    Algorithm::SimulatedAnnealing,
+    Algorithm::SingleRandomDraw,
+];
+
+/// Number of times [`select_coin_top_n`] reruns the [`SEEDED_ALGORITHMS`] at most.
+pub const TOP_N_RESEEDS: u64 = 32;
+
+/// The indices of the inputs `output` selects, in ascending order.
+fn sorted_selection(output: &SelectionOutput) -> Vec<usize> {
+    let mut selected = output.selected_inputs.clone();
+    selected.sort_unstable();
+    selected
+}
+
+/// Sort key of a selection under `options.rank_strategy`; smaller ranks first. With
+/// `options.avoid_change`, changeless selections rank ahead of everything else, and with
+/// [`AgePreference::StrictOldestFirst`], the ones passing over the fewest older groups come next.
+fn rank_key(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> (bool, u64, i64, i64, i64) {
+    let avoided_change = options.avoid_change && !is_changeless(output, inputs, options);
+    let passed_over = match options.age_preference {
+        AgePreference::StrictOldestFirst => older_groups_passed_over(output, inputs, options),
+        AgePreference::Ignore | AgePreference::Weighted { .. } => 0,
+    };
+    let (first, second, third) = strategy_key(output, inputs, options);
+    (avoided_change, passed_over, first, second, third)
+}
+
+/// Sort key of a selection under `options.rank_strategy` alone.
+pub(crate) fn strategy_key(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> (i64, i64, i64) {
+    let total_input_count = output
+        .selected_inputs
+        .iter()
+        .map(|&idx| inputs[idx].input_count)
+        .sum::<usize>() as i64;
+    let waste = ranked_waste(output, inputs, options);
+    if let Some(metric) = &options.selection_metric {
+        return (
+            metric.score(output, inputs, options),
+            waste,
+            total_input_count,
+        );
+    }
+    match options.rank_strategy {
+        RankStrategy::MinInputs => (
+            total_input_count,
+            output.selected_inputs.len() as i64,
+            waste,
+        ),
+        RankStrategy::MinWaste => (waste, total_input_count, 0),
+        RankStrategy::MinChange => (
+            change_value(output, inputs, options) as i64,
+            waste,
+            total_input_count,
+        ),
+        RankStrategy::MinFee => (output.fee as i64, waste, total_input_count),
+        RankStrategy::MinWeight => (output.selected_weight as i64, waste, total_input_count),
+    }
+}
+
+/// Waste of a selection as ranked: its waste metric plus the signing cost of its inputs, the
+/// [`AgePreference::Weighted`] penalty for the older groups it passes over and the
+/// [`PoolSizeTarget`](crate::types::PoolSizeTarget) penalty for the pool it leaves behind.
+fn ranked_waste(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> i64 {
+    let signing_cost: u64 = output
+        .selected_inputs
+        .iter()
+        .map(|&idx| inputs[idx].signing_cost)
+        .fold(0u64, u64::saturating_add);
+    let age_penalty = match options.age_preference {
+        AgePreference::Weighted { sats_per_group } => {
+            sats_per_group.saturating_mul(older_groups_passed_over(output, inputs, options))
+        }
+        AgePreference::Ignore | AgePreference::StrictOldestFirst => 0,
+    };
+    let pool_penalty = options.pool_size_target.map_or(0, |target| {
+        let distance = pool_size_after(output, inputs, options).abs_diff(target.utxos);
+        target.sats_per_utxo.saturating_mul(distance as u64)
+    });
+    output
+        .waste
+        .0
+        .saturating_add(signing_cost.min(i64::MAX as u64) as i64)
+        .saturating_add(age_penalty.min(i64::MAX as u64) as i64)
+        .saturating_add(pool_penalty.min(i64::MAX as u64) as i64)
+}
+
+/// Number of UTXOs the wallet holds after a selection: those of the eligible groups worth more
+/// than dust it leaves unspent, plus its change outputs.
+pub(crate) fn pool_size_after(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> usize {
+    let selected: BTreeSet<usize> = output.selected_inputs.iter().copied().collect();
+    let unspent: usize = inputs
+        .iter()
+        .enumerate()
+        .filter(|(idx, input)| {
+            !selected.contains(idx) && is_eligible(input, options) && !is_dust(input, options)
+        })
+        .map(|(_, input)| input.input_count)
+        .sum();
+    let change = change_value(output, inputs, options);
+    let change_outputs = if change > 0 {
+        split_change(options, change).outputs
+    } else {
+        0
+    };
+    unspent + change_outputs
+}
+
+/// Number of eligible groups a selection leaves unspent while spending a younger one, counted once
+/// per younger group spent, see [`AgePreference`].
+pub(crate) fn older_groups_passed_over(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> u64 {
+    let age = |input: &OutputGroup| {
+        (
+            Reverse(input.confirmations),
+            input.creation_sequence.unwrap_or(u32::MAX),
+        )
+    };
+    let selected: BTreeSet<usize> = output.selected_inputs.iter().copied().collect();
+    let mut unspent: Vec<_> = inputs
+        .iter()
+        .enumerate()
+        .filter(|(idx, input)| {
+            !selected.contains(idx) && is_eligible(input, options) && !is_dust(input, options)
+        })
+        .map(|(_, input)| age(input))
+        .collect();
+    unspent.sort_unstable();
+    selected
+        .iter()
+        .map(|&idx| unspent.partition_point(|&older| older < age(&inputs[idx])) as u64)
+        .sum()
+}
+
+/// Value a selection holds beyond the target and its fee.
+fn excess_value(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> u64 {
+    // The output and options come from the caller, so their totals saturate rather than overflow.
+    let selected_value = output
+        .selected_inputs
+        .iter()
+        .fold(0u64, |value, &idx| value.saturating_add(inputs[idx].value));
+    selected_value.saturating_sub(options.target_value.saturating_add(output.fee))
+}
+
+/// Value of the change output a selection creates, or `0` if the excess is not turned into change.
+pub fn change_value(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> u64 {
+    let excess = excess_value(output, inputs, options);
+    if options.excess_strategy == ExcessStrategy::ToChange && excess >= options.min_change_value {
+        excess
+    } else {
+        0
+    }
+}
+
+/// Whether a selection creates no change and drops at most `change_cost` to do so, i.e. the
+/// window Branch and Bound searches.
+pub(crate) fn is_changeless(
+    output: &SelectionOutput,
+    inputs: &[OutputGroup],
+    options: &CoinSelectionOpt,
+) -> bool {
+    change_value(output, inputs, options) == 0
+        && excess_value(output, inputs, options) <= options.change_cost
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{
+        algorithms::{
+            annealing::select_coin_annealing,
+            blackjack::select_coin_blackjack,
+            bnb::select_coin_bnb,
+            coingrinder::select_coin_coingrinder,
+            fewestinputs::select_coin_fewest_inputs,
+            fifo::select_coin_fifo,
+            lowestlarger::select_coin_lowestlarger,
+            sweep::{max_sendable, select_coin_sweep},
+        },
+        selectcoin::{
+            change_value, older_groups_passed_over, pool_size_after, ranked_waste, select_coin,
+            select_coin_detailed, select_coin_feerate_band, select_coin_from_iter,
+            select_coin_or_partial, select_coin_scored, select_coin_top_n, select_coin_with,
+            AlgorithmRegistry, Funding, SelectionAlgorithm, DEFAULT_ALGORITHMS,
+        },
+        types::{
+            AgePreference, Algorithm, CancellationToken, CoinSelectionOpt, Constraint,
+            ExcessStrategy, FeeRate, LabelPolicy, OutputGroup, PoolSizeTarget, RankStrategy,
+            Reserve, SelectionError, SelectionOutput, UnconfirmedInputs, UnconfirmedPolicy,
+            WasteMetric, WasteScore, DEFAULT_MAX_SEARCH_POOL_SIZE,
+        },
+        utils::{calculate_fee, effective_value},
+    };
+    use std::sync::Arc;
+
+    fn setup_basic_output_groups() -> Vec<OutputGroup> {
+        vec![
+            OutputGroup {
+                value: 1_500_000,
+                weight: 50,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_000_000,
+                weight: 200,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_000_000,
+                weight: 300,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_500_000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 4_000_000,
+                weight: 150,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 500_000,
+                weight: 250,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 6_000_000,
+                weight: 120,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 70_000,
+                weight: 50,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 800_000,
+                weight: 60,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 900_000,
+                weight: 70,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 100_000,
+                weight: 80,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 1_000_000,
+                weight: 90,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn setup_options(target_value: u64) -> CoinSelectionOpt {
+        CoinSelectionOpt {
+            target_value,
+            target_feerate: FeeRate::from_sat_per_wu(2.0), // Simplified feerate
+            long_term_feerate: Some(FeeRate::from_sat_per_wu(0.4)),
+            min_absolute_fee: 0,
+            base_weight: 10,
+            change_weight: 50,
+            change_cost: 10,
+            min_change_value: 500,
+            excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::MinInputs,
+            ..Default::default()
+        }
+    }
+
+    /// Asserts that a set of selected inputs actually covers the target plus the total fee.
+    fn assert_covers_target(
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+        selected: &[usize],
+    ) {
+        let value: u64 = selected.iter().map(|&i| inputs[i].value).sum();
+        let selected_weight: u64 = selected.iter().map(|&i| inputs[i].weight).sum();
+        let total_fee = calculate_fee(
+            options.base_weight + selected_weight,
+            options.target_feerate,
+        )
+        .max(options.min_absolute_fee);
+        assert!(
+            value >= options.target_value + total_fee,
+            "selection {:?} (value {}) does not cover target {} + fee {}",
+            selected,
+            value,
+            options.target_value,
+            total_fee
+        );
+    }
+
+    #[test]
+    fn test_select_coin_rejects_zero_target() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(0);
+        let result = select_coin(&inputs, &options);
+        assert!(matches!(result, Err(SelectionError::NonPositiveTarget)));
+    }
+
+    #[test]
+    fn test_select_coin_successful() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+        let result = select_coin(&inputs, &options);
+        assert!(result.is_ok());
+        let ranked = result.unwrap();
+        let best = &ranked[0].1;
+        assert!(!best.selected_inputs.is_empty());
+        assert_covers_target(&inputs, &options, &best.selected_inputs);
+    }
+
+    #[test]
+    fn test_select_coin_insufficient_funds() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(999_999_999); // Set a target value higher than the sum of all inputs
+        let result = select_coin(&inputs, &options);
+        assert!(matches!(
+            result,
+            Err(SelectionError::InsufficientFunds {
+                available: 22_370_000,
+                required: 1_000_003_059,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_select_coin_keeps_success_when_another_algorithm_is_insufficient() {
+        let inputs = vec![OutputGroup {
+            value: 1_000,
+            ..Default::default()
+        }];
+        let mut options = setup_options(1_000);
+        options.target_feerate = FeeRate::from_sat_per_wu(1.0);
+        options.long_term_feerate = Some(FeeRate::from_sat_per_wu(1.0));
+        options.base_weight = 0;
+        options.change_cost = 20;
+        options.min_change_value = 100;
+
+        let ranked = select_coin(&inputs, &options).expect("BnB should find the exact match");
+        assert_eq!(ranked[0].1.selected_inputs, vec![0]);
+    }
+
+    #[test]
+    fn test_select_coin_respects_max_input_count() {
+        let inputs = setup_basic_output_groups();
+        let mut options = setup_options(7_000_000);
+        options.max_input_count = Some(2);
+        let ranked = select_coin(&inputs, &options).expect("two coins cover the target");
+        for (algorithm, output) in &ranked {
+            assert!(
+                output.selected_inputs.len() <= 2,
+                "{:?} selected {:?}",
+                algorithm,
+                output.selected_inputs
+            );
+            assert_covers_target(&inputs, &options, &output.selected_inputs);
+        }
+
+        // The two largest coins hold 10M; only a third one would reach the target.
+        options.target_value = 12_000_000;
+        assert_eq!(
+            select_coin(&inputs, &options).unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::MaxInputCount
+            }
+        );
+    }
+
+    #[test]
+    fn test_select_coin_spends_preselected_inputs() {
+        let inputs = setup_basic_output_groups();
+        let mut options = setup_options(3_654_321);
+        options.preselected_inputs = vec![11, 7];
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        for (algorithm, output) in &ranked {
+            assert!(
+                output.selected_inputs.contains(&7) && output.selected_inputs.contains(&11),
+                "{:?} dropped a preselected input: {:?}",
+                algorithm,
+                output.selected_inputs
+            );
+            assert_covers_target(&inputs, &options, &output.selected_inputs);
+        }
+
+        // A preselected coin that covers the payment on its own is all that is spent.
+        options.preselected_inputs = vec![6];
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        assert!(ranked
+            .iter()
+            .all(|(_, output)| output.selected_inputs == vec![6]));
+
+        options.preselected_inputs = vec![inputs.len()];
+        assert_eq!(
+            select_coin(&inputs, &options).unwrap_err(),
+            SelectionError::PreselectedInputOutOfRange
+        );
+    }
+
+    #[test]
+    fn test_select_coin_pays_for_uneconomical_preselected_inputs() {
+        // The preselected coin holds 300 sats but costs 544 to spend at 2 sat/WU, so the rest of
+        // the selection has to pay the 244 sats it lacks.
+        let inputs = vec![
+            OutputGroup {
+                value: 300,
+                weight: 272,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 50_000,
+                weight: 272,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 21_188,
+                weight: 462,
+                ..Default::default()
+            },
+        ];
+        let mut options = setup_options(20_000);
+        options.preselected_inputs = vec![0];
+        // Coin 2 pays the target and the whole fee of a changeless transaction exactly.
+        for output in [
+            select_coin_bnb(&inputs, &options),
+            select_coin_blackjack(&inputs, &options),
+        ] {
+            let output = output.expect("coin 2 funds the payment without change");
+            assert_eq!(output.selected_inputs, vec![0, 2]);
+            assert_eq!(output.selected_value, options.target_value + output.fee);
+        }
+
+        // With change, the change is what is left once the whole fee is paid.
+        options.target_value = 10_000;
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        for (algorithm, output) in &ranked {
+            assert!(
+                output.selected_inputs.contains(&0),
+                "{:?} dropped the preselected input: {:?}",
+                algorithm,
+                output.selected_inputs
+            );
+            assert!(
+                output.selected_value >= options.target_value + output.fee,
+                "{:?} underpays the fee: {:?}",
+                algorithm,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn test_select_coin_never_spends_frozen_inputs() {
+        let mut inputs = setup_basic_output_groups();
+        inputs[6].frozen = true;
+        inputs[4].frozen = true;
+        let options = setup_options(9_000_000);
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        for (algorithm, output) in &ranked {
+            assert!(
+                !output.selected_inputs.contains(&6) && !output.selected_inputs.contains(&4),
+                "{:?} spent a frozen input: {:?}",
+                algorithm,
+                output.selected_inputs
+            );
+        }
+
+        // Frozen funds are not available either.
+        let options = setup_options(13_000_000);
+        assert!(matches!(
+            select_coin(&inputs, &options),
+            Err(SelectionError::InsufficientFunds {
+                available: 12_370_000,
+                ..
+            })
+        ));
+
+        let mut options = setup_options(1_000_000);
+        options.preselected_inputs = vec![6];
+        assert_eq!(
+            select_coin(&inputs, &options).unwrap_err(),
+            SelectionError::PreselectedInputFrozen
+        );
+    }
+
+    /// The core contract of the wrapper: it must return the fewest inputs achievable by any of the
+    /// individual algorithms (and a selection that actually covers the target).
+    #[test]
+    fn test_select_coin_returns_minimum_inputs() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+
+        let individual_min_inputs = [
+            select_coin_blackjack(&inputs, &options),
+            select_coin_bnb(&inputs, &options),
+            select_coin_coingrinder(&inputs, &options),
+            select_coin_fewest_inputs(&inputs, &options),
+            select_coin_fifo(&inputs, &options),
+            select_coin_lowestlarger(&inputs, &options),
+            select_coin_annealing(&inputs, &options),
+        ]
+        .into_iter()
+        .filter_map(|r| r.ok())
+        .map(|r| r.selected_inputs.len())
+        .min()
+        .expect("at least one algorithm should succeed");
+
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        let chosen = &ranked[0].1;
+        assert_eq!(
+            chosen.selected_inputs.len(),
+            individual_min_inputs,
+            "wrapper did not return the minimum-input selection"
+        );
+        assert_covers_target(&inputs, &options, &chosen.selected_inputs);
+    }
+
+    /// `select_coin` must return every successful algorithm's result, ordered best-first by the
+    /// policy: fewest real UTXOs, then fewest groups, then waste.
+    #[test]
+    fn test_select_coin_orders_best_first() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        assert!(!ranked.is_empty());
+
+        // The list is sorted best-first by the rank key (non-decreasing keys).
+        let key = |output: &SelectionOutput| {
+            let total_input_count: usize = output
+                .selected_inputs
+                .iter()
+                .map(|&idx| inputs[idx].input_count)
+                .sum();
+            (
+                total_input_count,
+                output.selected_inputs.len(),
+                output.waste.0,
+            )
+        };
+        let keys: Vec<_> = ranked.iter().map(|(_, output)| key(output)).collect();
+        assert!(
+            keys.windows(2).all(|w| w[0] <= w[1]),
+            "ranked results are not ordered best-first: {keys:?}"
+        );
+    }
+
+    #[test]
+    fn test_select_coin_rank_strategy_min_waste() {
+        let inputs = setup_basic_output_groups();
+        let mut options = setup_options(654321);
+        options.rank_strategy = RankStrategy::MinWaste;
+
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        let wastes: Vec<_> = ranked.iter().map(|(_, output)| output.waste.0).collect();
+        assert!(
+            wastes.windows(2).all(|w| w[0] <= w[1]),
+            "ranked results are not ordered by waste: {wastes:?}"
+        );
+    }
+
+    #[test]
+    fn test_select_coin_ranks_with_signing_cost() {
+        let mut inputs = setup_basic_output_groups();
+        let mut options = setup_options(654321);
+        options.rank_strategy = RankStrategy::MinWaste;
+        let unpenalized = select_coin(&inputs, &options).expect("selection should succeed");
+
+        // Make the winner's inputs expensive to sign, e.g. held by a slow hardware wallet.
+        for &index in &unpenalized[0].1.selected_inputs {
+            inputs[index].signing_cost = 1_000_000;
+        }
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        let costs: Vec<i64> = ranked
+            .iter()
+            .map(|(_, output)| {
+                output.waste.0
+                    + output
+                        .selected_inputs
+                        .iter()
+                        .map(|&index| inputs[index].signing_cost as i64)
+                        .sum::<i64>()
+            })
+            .collect();
+        assert!(
+            costs.windows(2).all(|w| w[0] <= w[1]),
+            "ranked results are not ordered by waste and signing cost: {costs:?}"
+        );
+        assert!(costs[0] < 1_000_000, "a cheaper selection to sign exists");
+    }
+
+    #[test]
+    fn test_select_coin_ranks_with_saturating_signing_cost() {
+        let mut inputs = setup_basic_output_groups();
+        let mut options = setup_options(654321);
+        options.rank_strategy = RankStrategy::MinWaste;
+        for input in &mut inputs {
+            input.signing_cost = u64::MAX;
+        }
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        for (_, output) in &ranked {
+            assert_eq!(ranked_waste(output, &inputs, &options), i64::MAX);
+        }
+    }
+
+    #[test]
+    fn test_select_coin_unconfirmed_policy() {
+        let inputs = vec![
+            OutputGroup {
+                value: 1_000_000,
+                weight: 100,
+                confirmations: 6,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 3_000_000,
+                weight: 100,
+                self_transfer: true,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_000_000,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 500_000,
+                weight: 100,
+                ..Default::default()
+            },
+        ];
+        let with_policy = |target_value, unconfirmed_policy| CoinSelectionOpt {
+            unconfirmed_policy,
+            ..setup_options(target_value)
+        };
+        let unconfirmed_spent = |output: &SelectionOutput| -> (usize, u64) {
+            let unconfirmed = output
+                .selected_inputs
+                .iter()
+                .filter(|&&index| inputs[index].confirmations == 0);
+            (
+                unconfirmed.clone().count(),
+                unconfirmed.map(|&index| inputs[index].value).sum(),
+            )
+        };
+
+        let confirmed_only = UnconfirmedPolicy {
+            allowed: UnconfirmedInputs::ConfirmedOnly,
+            ..Default::default()
+        };
+        assert!(matches!(
+            select_coin(&inputs, &with_policy(2_500_000, confirmed_only)),
+            Err(SelectionError::InsufficientFunds { .. })
+        ));
+
+        let self_transfers = UnconfirmedPolicy {
+            allowed: UnconfirmedInputs::SelfTransfersOnly,
+            ..Default::default()
+        };
+        for (_, output) in select_coin(&inputs, &with_policy(2_500_000, self_transfers)).unwrap() {
+            assert!(output.selected_inputs.iter().all(|&index| index < 2));
+        }
+
+        // Only the most valuable unconfirmed group fits a cap of one input.
+        let one_input = UnconfirmedPolicy {
+            max_inputs: Some(1),
+            ..Default::default()
+        };
+        assert!(select_coin(&inputs, &with_policy(4_500_000, one_input)).is_err());
+        for (_, output) in select_coin(&inputs, &with_policy(3_500_000, one_input)).unwrap() {
+            assert!(unconfirmed_spent(&output).0 <= 1);
+        }
+
+        let capped_value = UnconfirmedPolicy {
+            max_value: Some(2_400_000),
+            ..Default::default()
+        };
+        for (_, output) in select_coin(&inputs, &with_policy(2_500_000, capped_value)).unwrap() {
+            assert!(unconfirmed_spent(&output).1 <= 2_400_000);
+        }
+
+        // The confirmed group is tried alone first, and the unconfirmed ones only once it fails.
+        let prefer_confirmed = UnconfirmedPolicy {
+            prefer_confirmed: true,
+            ..Default::default()
+        };
+        for (_, output) in select_coin(&inputs, &with_policy(500_000, prefer_confirmed)).unwrap() {
+            assert_eq!(output.selected_inputs, vec![0]);
+        }
+        let fallback = select_coin(&inputs, &with_policy(2_500_000, prefer_confirmed)).unwrap();
+        assert!(unconfirmed_spent(&fallback[0].1).0 > 0);
+    }
+
+    #[test]
+    fn test_select_coin_or_partial() {
+        let inputs = setup_basic_output_groups();
+        let available = max_sendable(&inputs, &setup_options(1)).unwrap();
+
+        let funded = select_coin_or_partial(&inputs, &setup_options(available / 2)).unwrap();
+        assert!(matches!(funded, Funding::Full(selections) if !selections.is_empty()));
+
+        let Funding::Partial { sweep, deficit } =
+            select_coin_or_partial(&inputs, &setup_options(available + 5_000)).unwrap()
+        else {
+            panic!("expected a partial funding");
+        };
+        assert_eq!(sweep.max_sendable, available);
+        assert_eq!(deficit, 5_000);
+
+        // Errors other than insufficient funds are not turned into a partial funding.
+        assert_eq!(
+            select_coin_or_partial(&inputs, &setup_options(0)).unwrap_err(),
+            SelectionError::NonPositiveTarget
+        );
+    }
+
+    #[test]
+    fn test_select_coin_label_policy() {
+        let labelled = |value, labels: &[&str]| OutputGroup {
+            value,
+            weight: 100,
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            ..Default::default()
+        };
+        let inputs = vec![
+            labelled(2_000_000, &["exchange"]),
+            labelled(1_500_000, &["p2p"]),
+            labelled(1_000_000, &["exchange"]),
+            labelled(3_000_000, &[]),
+        ];
+        let with_policy = |label_policy| CoinSelectionOpt {
+            label_policy,
+            ..setup_options(2_500_000)
+        };
+        let labels = |output: &SelectionOutput| {
+            output
+                .selected_inputs
+                .iter()
+                .flat_map(|&index| inputs[index].labels.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+        };
+
+        let only_exchange = with_policy(LabelPolicy {
+            only: vec!["exchange".to_string()],
+            ..Default::default()
+        });
+        for (_, output) in select_coin(&inputs, &only_exchange).unwrap() {
+            let mut selected = output.selected_inputs.clone();
+            selected.sort_unstable();
+            assert_eq!(selected, vec![0, 2]);
+        }
+        let only_p2p = with_policy(LabelPolicy {
+            only: vec!["p2p".to_string()],
+            ..Default::default()
+        });
+        assert!(select_coin(&inputs, &only_p2p).is_err());
+
+        let apart = with_policy(LabelPolicy {
+            never_mix: vec![("exchange".to_string(), "p2p".to_string())],
+            ..Default::default()
+        });
+        for (_, output) in select_coin(&inputs, &apart).unwrap() {
+            let labels = labels(&output);
+            assert!(!(labels.contains(&"exchange") && labels.contains(&"p2p")));
+        }
+        // Spending the whole pool would mix them.
+        assert_eq!(
+            select_coin_sweep(&inputs, &apart).unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::MixedLabels
+            }
+        );
+    }
+
+    #[test]
+    fn test_select_coin_age_preference() {
+        // Three old coins and a young one that pays the target alone.
+        let inputs: Vec<OutputGroup> = [1_000_000, 1_200_000, 900_000, 5_000_000]
+            .into_iter()
+            .zip(0..)
+            .map(|(value, sequence)| OutputGroup {
+                value,
+                weight: 100,
+                creation_sequence: Some(sequence),
+                ..Default::default()
+            })
+            .collect();
+        let with_preference = |age_preference| CoinSelectionOpt {
+            age_preference,
+            rank_strategy: RankStrategy::MinWaste,
+            ..setup_options(2_500_000)
+        };
+        let passed_over = |options: &CoinSelectionOpt| {
+            let ranked = select_coin(&inputs, options).unwrap();
+            older_groups_passed_over(&ranked[0].1, &inputs, options)
+        };
+
+        assert!(passed_over(&with_preference(AgePreference::Ignore)) > 0);
+        assert_eq!(
+            passed_over(&with_preference(AgePreference::Weighted {
+                sats_per_group: 10_000_000
+            })),
+            0
+        );
+        let strict = with_preference(AgePreference::StrictOldestFirst);
+        assert_eq!(passed_over(&strict), 0);
+        let ranked = select_coin(&inputs, &strict).unwrap();
+        let mut selected = ranked[0].1.selected_inputs.clone();
+        selected.sort_unstable();
+        assert_eq!(selected, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_select_coin_pool_size_target() {
+        // Eight small coins and a big one that pays the target alone.
+        let inputs: Vec<OutputGroup> = core::iter::repeat_n(300_000, 8)
+            .chain([5_000_000])
+            .map(|value| OutputGroup {
+                value,
+                weight: 100,
+                ..Default::default()
+            })
+            .collect();
+        let with_target = |utxos| CoinSelectionOpt {
+            pool_size_target: Some(PoolSizeTarget {
+                utxos,
+                sats_per_utxo: 1_000_000,
+            }),
+            rank_strategy: RankStrategy::MinWaste,
+            ..setup_options(2_000_000)
+        };
+        let pool_sizes = |options: &CoinSelectionOpt| {
+            select_coin(&inputs, options)
+                .unwrap()
+                .iter()
+                .map(|(_, output)| pool_size_after(output, &inputs, options))
+                .collect::<Vec<_>>()
+        };
+
+        // Too big a pool: the selection consolidating the most coins ranks first.
+        let consolidating = pool_sizes(&with_target(1));
+        assert_eq!(consolidating[0], *consolidating.iter().min().unwrap());
+        // Too small a pool: the one spending the fewest does.
+        let sparing = pool_sizes(&with_target(20));
+        assert_eq!(sparing[0], *sparing.iter().max().unwrap());
+        assert!(consolidating[0] < sparing[0]);
+    }
+
+    #[test]
+    fn test_select_coin_min_relay_feerate() {
+        let inputs = setup_basic_output_groups();
+        let floor = FeeRate::from_sat_per_vb(1.0);
+        let low = CoinSelectionOpt {
+            target_feerate: FeeRate::from_sat_per_vb(0.1),
+            min_relay_feerate: Some(floor),
+            ..setup_options(654321)
+        };
+        let at_floor = CoinSelectionOpt {
+            target_feerate: floor,
+            ..setup_options(654321)
+        };
+        // A target feerate below the floor selects exactly as one at the floor.
+        let floored = select_coin(&inputs, &low).unwrap();
+        let expected = select_coin(&inputs, &at_floor).unwrap();
+        assert_eq!(floored.len(), expected.len());
+        for ((_, floored), (_, expected)) in floored.iter().zip(&expected) {
+            assert_eq!(floored.selected_inputs, expected.selected_inputs);
+            assert_eq!(floored.fee, expected.fee);
+            assert!(floored.fee >= calculate_fee(low.base_weight + floored.selected_weight, floor));
+        }
+
+        // Above the floor, the target feerate is kept.
+        let high = CoinSelectionOpt {
+            target_feerate: FeeRate::from_sat_per_vb(5.0),
+            min_relay_feerate: Some(floor),
+            ..setup_options(654321)
+        };
+        let unfloored = CoinSelectionOpt {
+            min_relay_feerate: None,
+            ..high.clone()
+        };
+        assert_eq!(
+            select_coin(&inputs, &high).unwrap()[0].1.fee,
+            select_coin(&inputs, &unfloored).unwrap()[0].1.fee
+        );
+    }
+
+    #[test]
+    fn test_select_coin_rank_strategy_min_fee() {
+        let inputs = setup_basic_output_groups();
+        let mut options = setup_options(654321);
+        options.rank_strategy = RankStrategy::MinFee;
+
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        let fees: Vec<_> = ranked.iter().map(|(_, output)| output.fee).collect();
+        assert!(
+            fees.windows(2).all(|w| w[0] <= w[1]),
+            "ranked results are not ordered by fee: {fees:?}"
+        );
+    }
+
+    #[test]
+    fn test_select_coin_avoid_change_prefers_changeless() {
+        // One coin pays with change; 6000 + 4000 lands in BnB's changeless window.
+        let inputs = vec![
+            OutputGroup {
+                value: 20_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 6_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 4_000,
+                ..Default::default()
+            },
+        ];
+        let mut options = setup_options(9_975);
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        assert_eq!(ranked[0].1.selected_inputs, vec![0]);
+
+        options.avoid_change = true;
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        let mut best = ranked[0].1.selected_inputs.clone();
+        best.sort();
+        assert_eq!(best, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_select_coin_ranks_by_selection_metric() {
+        let inputs = setup_basic_output_groups();
+        let options = CoinSelectionOpt {
+            rank_strategy: RankStrategy::MinInputs,
+            selection_metric: Some(Arc::new(WasteScore)),
+            ..setup_options(654321)
+        };
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        let wastes: Vec<_> = ranked.iter().map(|(_, output)| output.waste.0).collect();
+        assert!(
+            wastes.windows(2).all(|w| w[0] <= w[1]),
+            "results are not ordered by the metric: {wastes:?}"
+        );
+    }
+
+    #[test]
+    fn test_select_coin_rejects_groups_without_inputs() {
+        let options = setup_options(1_000);
+        // A group built from the defaults spends one UTXO.
+        let defaulted = OutputGroup {
+            value: 5_000,
+            weight: 272,
+            ..Default::default()
+        };
+        assert_eq!(defaulted.input_count, 1);
+        let mut inputs = vec![defaulted.clone()];
+        let result = select_coin(&inputs, &options).unwrap();
+        assert_eq!(result[0].1.selected_inputs, vec![0]);
+
+        inputs.push(OutputGroup {
+            input_count: 0,
+            ..defaulted.clone()
+        });
+        assert_eq!(
+            select_coin(&inputs, &options).unwrap_err(),
+            SelectionError::ZeroInputCount
+        );
+        assert_eq!(
+            select_coin_bnb(&inputs, &options).unwrap_err(),
+            SelectionError::ZeroInputCount
+        );
+        assert_eq!(
+            select_coin_sweep(&inputs, &options).unwrap_err(),
+            SelectionError::ZeroInputCount
+        );
+
+        // Preselecting the empty group does not get it past the check.
+        let inputs = vec![
+            OutputGroup {
+                value: 50_000,
+                input_count: 0,
+                ..defaulted.clone()
+            },
+            OutputGroup {
+                value: 100_000,
+                ..defaulted
+            },
+        ];
+        let options = CoinSelectionOpt {
+            preselected_inputs: vec![0],
+            ..options
+        };
+        assert_eq!(
+            select_coin_detailed(&inputs, &options).unwrap_err(),
+            SelectionError::ZeroInputCount
+        );
+        for algorithm in DEFAULT_ALGORITHMS {
+            assert_eq!(
+                algorithm.select(&inputs, &options).unwrap_err(),
+                SelectionError::ZeroInputCount,
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_select_coin_rejects_overflowing_inputs() {
+        let options = setup_options(1_000);
+        let huge_values = vec![
+            OutputGroup {
+                value: u64::MAX / 2,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: u64::MAX / 2,
+                weight: 100,
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            select_coin(&huge_values, &options).unwrap_err(),
+            SelectionError::ArithmeticOverflow
+        );
+        // Each value fits an `i64`, but together they do not.
+        let signed_overflow = vec![
+            OutputGroup {
+                value: i64::MAX as u64 / 2 + 1,
+                weight: 100,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: i64::MAX as u64 / 2 + 1,
+                weight: 100,
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            select_coin(&signed_overflow, &options).unwrap_err(),
+            SelectionError::ArithmeticOverflow
+        );
+        let huge_weight = vec![OutputGroup {
+            value: 1_000_000,
+            weight: u64::MAX / 1_000,
+            ..Default::default()
+        }];
+        assert_eq!(
+            select_coin(&huge_weight, &options).unwrap_err(),
+            SelectionError::ArithmeticOverflow
+        );
+    }
+
+    #[test]
+    fn test_select_coin_change_range() {
+        let inputs = setup_basic_output_groups();
+        let options = CoinSelectionOpt {
+            change_range: Some((100_000, 400_000)),
+            ..setup_options(2_300_000)
+        };
+        let results = select_coin(&inputs, &options).unwrap();
+        assert!(!results.is_empty());
+        for (algorithm, output) in &results {
+            let change = change_value(output, &inputs, &options);
+            assert!(
+                change == 0 || (100_000..=400_000).contains(&change),
+                "{algorithm:?} created {change} sats of change"
+            );
+        }
+
+        // No selection leaves change in a range this narrow, nor none at all.
+        let options = CoinSelectionOpt {
+            change_range: Some((1, 2)),
+            ..setup_options(2_300_000)
+        };
+        assert_eq!(
+            select_coin_lowestlarger(&inputs, &options).unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::ChangeRange
+            }
+        );
+        assert!(select_coin(&inputs, &options).is_err());
+    }
+
+    #[test]
+    fn test_select_coin_keeps_the_reserve() {
+        let inputs = setup_basic_output_groups();
+        let total: u64 = inputs.iter().map(|input| input.value).sum();
+        let mut options = CoinSelectionOpt {
+            reserve: Reserve {
+                utxos: 10,
+                value: 15_000_000,
+            },
+            ..setup_options(3_000_000)
+        };
+        for (algorithm, output) in select_coin(&inputs, &options).unwrap() {
+            assert!(
+                inputs.len() - output.selected_inputs.len() >= 10,
+                "{algorithm:?} spent the reserved UTXOs"
+            );
+            assert!(
+                total - output.selected_value >= 15_000_000,
+                "{algorithm:?} spent the reserved value"
+            );
+        }
+
+        // Leaving every coin unspent funds nothing.
+        options.reserve = Reserve {
+            utxos: inputs.len(),
+            value: 0,
+        };
+        assert_eq!(
+            select_coin_fifo(&inputs, &options).unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::Reserve
+            }
+        );
+        assert!(select_coin(&inputs, &options).is_err());
+    }
+
+    /// Asserts that the stochastic searches select the same with the [`WasteScore`] metric as
+    /// without, which ranks like it, though scoring with a metric finds out the candidates breaking
+    /// a constraint of `options`.
+    fn assert_waste_score_selects_like_default(inputs: &[OutputGroup], options: &CoinSelectionOpt) {
+        let scored = CoinSelectionOpt {
+            selection_metric: Some(Arc::new(WasteScore)),
+            ..options.clone()
+        };
+        for algorithm in [
+            Algorithm::Genetic,
+            Algorithm::SimulatedAnnealing,
+            Algorithm::SingleRandomDraw,
+        ] {
+            let default = algorithm.select(inputs, options).unwrap();
+            let metric = algorithm
+                .select(inputs, &scored)
+                .unwrap_or_else(|error| panic!("{algorithm:?} failed: {error:?}"));
+            assert_eq!(metric.selected_inputs, default.selected_inputs);
+            assert_eq!(metric.waste, default.waste);
+        }
+    }
+
+    #[test]
+    fn test_selection_metric_skips_candidates_spending_the_reserve() {
+        // Only two coins may be spent, which many of the candidates of the searches break.
+        let options = CoinSelectionOpt {
+            reserve: Reserve {
+                utxos: 10,
+                value: 15_000_000,
+            },
+            ..setup_options(3_000_000)
+        };
+        assert_waste_score_selects_like_default(&setup_basic_output_groups(), &options);
+    }
+
+    #[test]
+    fn test_selection_metric_skips_candidates_mixing_labels() {
+        let mut inputs = setup_basic_output_groups();
+        for (index, input) in inputs.iter_mut().enumerate() {
+            let label = if index % 2 == 0 { "exchange" } else { "p2p" };
+            input.labels = vec![label.to_string()];
+        }
+        let options = CoinSelectionOpt {
+            label_policy: LabelPolicy {
+                never_mix: vec![("exchange".to_string(), "p2p".to_string())],
+                ..Default::default()
+            },
+            ..setup_options(3_000_000)
+        };
+        assert_waste_score_selects_like_default(&inputs, &options);
+    }
+
+    #[test]
+    fn test_selection_metric_skips_candidates_outside_the_change_range() {
+        // A 4M or 6M coin alone leaves change within the range; candidates spending more leave too
+        // much.
+        let options = CoinSelectionOpt {
+            change_range: Some((500_000, 3_500_000)),
+            ..setup_options(3_000_000)
+        };
+        assert_waste_score_selects_like_default(&setup_basic_output_groups(), &options);
+    }
+
+    #[test]
+    fn test_select_coin_feerate_band() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(2_500_000);
+        let (low, high) = (
+            FeeRate::from_sat_per_vb(2.0),
+            FeeRate::from_sat_per_vb(20.0),
+        );
+
+        // Selecting for the top of the band keeps every input economical across it.
+        let band = select_coin_feerate_band(&inputs, &options, low, high).unwrap();
+        let at_high = CoinSelectionOpt {
+            target_feerate: high,
+            ..options.clone()
+        };
+        let at_low = CoinSelectionOpt {
+            target_feerate: low,
+            ..options.clone()
+        };
+        assert_eq!(
+            band.iter()
+                .map(|selection| (
+                    selection.algorithm,
+                    selection.at_max.selected_inputs.clone()
+                ))
+                .collect::<Vec<_>>(),
+            select_coin(&inputs, &at_high)
+                .unwrap()
+                .into_iter()
+                .map(|(algorithm, output)| (algorithm, output.selected_inputs))
+                .collect::<Vec<_>>()
+        );
+        for selection in &band {
+            let (max, min) = (&selection.at_max, &selection.at_min);
+            assert_eq!(min.selected_inputs, max.selected_inputs);
+            assert_eq!(min.selected_value, max.selected_value);
+            // Both ends cover the target and their own fee, with every input economical.
+            for (output, feerate, options) in [(max, high, &at_high), (min, low, &at_low)] {
+                assert!(output.selected_value >= options.target_value + output.fee);
+                assert!(output
+                    .selected_inputs
+                    .iter()
+                    .all(|&index| effective_value(&inputs[index], feerate) > 0));
+            }
+            // The lower feerate leaves a smaller fee and at least as much change.
+            assert!(min.fee < max.fee);
+            assert!(change_value(min, &inputs, &at_low) >= change_value(max, &inputs, &at_high));
+        }
+
+        assert_eq!(
+            select_coin_feerate_band(&inputs, &options, high, low).unwrap_err(),
+            SelectionError::InvalidFeeRateBand
+        );
+    }
+
+    #[test]
+    fn test_select_coin_top_n_distinct_by_waste() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+
+        let all = select_coin(&inputs, &options).expect("selection should succeed");
+        let top =
+            select_coin_top_n(&inputs, &options, all.len()).expect("selection should succeed");
+        assert!(!top.is_empty() && top.len() <= all.len());
+
+        let wastes: Vec<_> = top.iter().map(|(_, output)| output.waste.0).collect();
+        assert!(
+            wastes.windows(2).all(|w| w[0] <= w[1]),
+            "top-n results are not ordered by waste: {wastes:?}"
+        );
+        let mut sets: Vec<Vec<usize>> = top
+            .iter()
+            .map(|(_, output)| {
+                let mut selected = output.selected_inputs.clone();
+                selected.sort();
+                selected
+            })
+            .collect();
+        sets.sort();
+        sets.dedup();
+        assert_eq!(sets.len(), top.len(), "top-n results contain duplicates");
+
+        let best = select_coin_top_n(&inputs, &options, 1).expect("selection should succeed");
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].1.waste, top[0].1.waste);
+    }
+
+    #[test]
+    fn test_select_coin_top_n_reseeds_for_more_than_one_selection_per_algorithm() {
+        let inputs: Vec<OutputGroup> = (0..24)
+            .map(|i| OutputGroup {
+                value: 100_000 + i * 7_919,
+                weight: 272,
+                ..Default::default()
+            })
+            .collect();
+        let options = setup_options(450_000);
+        let n = DEFAULT_ALGORITHMS.len() + 5;
+
+        let top = select_coin_top_n(&inputs, &options, n).expect("selection should succeed");
+        assert_eq!(top.len(), n);
+        let mut sets: Vec<Vec<usize>> = top
+            .iter()
+            .map(|(_, output)| {
+                let mut selected = output.selected_inputs.clone();
+                selected.sort();
+                selected
+            })
+            .collect();
+        sets.sort();
+        sets.dedup();
+        assert_eq!(sets.len(), n, "top-n results contain duplicates");
+
+        let wastes: Vec<_> = top.iter().map(|(_, output)| output.waste.0).collect();
+        assert!(
+            wastes.windows(2).all(|w| w[0] <= w[1]),
+            "top-n results are not ordered by waste: {wastes:?}"
+        );
+        for (_, output) in &top {
+            assert!(output.selected_value >= options.target_value + output.fee);
+        }
+        let again = select_coin_top_n(&inputs, &options, n).expect("selection should succeed");
+        assert!(
+            again
+                .iter()
+                .zip(&top)
+                .all(|((_, a), (_, b))| a.selected_inputs == b.selected_inputs),
+            "reseeding is not deterministic"
+        );
+    }
+
+    #[test]
+    fn test_shared_context_matches_standalone_algorithms() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+        let algorithms = [
+            Algorithm::Blackjack,
+            Algorithm::BranchAndBound,
+            Algorithm::CoinGrinder,
+            Algorithm::FewestInputs,
+            Algorithm::Fifo,
+            Algorithm::Genetic,
+            Algorithm::Knapsack,
+            Algorithm::LowestLarger,
+            Algorithm::MinimumLinkage,
+            Algorithm::SimulatedAnnealing,
+            Algorithm::SingleRandomDraw,
+        ];
+        let mut registry = AlgorithmRegistry::new();
+        for algorithm in algorithms {
+            registry.register(algorithm);
+        }
+        let report = registry.select_coin_detailed(&inputs, &options).unwrap();
+        for algorithm in algorithms {
+            let shared = report
+                .outcomes
+                .iter()
+                .find(|outcome| outcome.algorithm == algorithm)
+                .unwrap();
+            let standalone = algorithm.select(&inputs, &options);
+            match (&shared.result, &standalone) {
+                (Ok(shared), Ok(standalone)) => {
+                    assert_eq!(shared.selected_inputs, standalone.selected_inputs);
+                    assert_eq!(
+                        (shared.fee, shared.waste),
+                        (standalone.fee, standalone.waste)
+                    );
+                }
+                (shared, standalone) => assert_eq!(
+                    shared.as_ref().unwrap_err(),
+                    standalone.as_ref().unwrap_err(),
+                    "{algorithm:?}"
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_coin_with_runs_only_requested_algorithms() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+
+        let requested = [Algorithm::Fifo, Algorithm::Genetic, Algorithm::Fifo];
+        let ranked =
+            select_coin_with(&requested, &inputs, &options).expect("selection should succeed");
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked
+            .iter()
+            .all(|(algorithm, _)| [Algorithm::Fifo, Algorithm::Genetic].contains(algorithm)));
+        for (_, output) in &ranked {
+            assert_covers_target(&inputs, &options, &output.selected_inputs);
+        }
+
+        let result = select_coin_with(&[], &inputs, &options);
+        assert_eq!(
+            result.unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::NoAlgorithm
+            }
+        );
+    }
 
-/// The algorithms run by [`select_coin`], tagged with their identity.
-const ALGORITHMS: [(SelectionAlgorithm, CoinSelectionFn); 4] = [
-    (SelectionAlgorithm::BranchAndBound, select_coin_bnb),
-    (SelectionAlgorithm::CoinGrinder, select_coin_coingrinder),
-    (SelectionAlgorithm::Fifo, select_coin_fifo),
-    (SelectionAlgorithm::LowestLarger, select_coin_lowestlarger),
-];
+    /// Spends the single largest input, whatever the target.
+    struct LargestOnly;
 
-/// The global coin selection API. Runs every algorithm and returns *all* successful results, each
-/// tagged with the [`SelectionAlgorithm`] that produced it, ordered best-first.
-///
-/// The best-result policy is: fewest real UTXOs, then fewest groups, then least waste. So the first
-/// element is the overall best selection. (`selected_inputs.len()` counts the chosen
-/// [`OutputGroup`]s, whereas the `input_count` sum counts the actual UTXOs they bundle; the two
-/// differ only when a group holds more than one UTXO.)
-pub fn select_coin(
-    inputs: &[OutputGroup],
-    options: &CoinSelectionOpt,
-) -> Result<Vec<(SelectionAlgorithm, SelectionOutput)>, SelectionError> {
-    // Run all algorithms concurrently. Checks only after all threads return and join.
-    let outcomes: Vec<(SelectionAlgorithm, Result<SelectionOutput, SelectionError>)> =
-        thread::scope(|scope| {
-            let handles: Vec<_> = ALGORITHMS
-                .into_iter()
-                .map(|(name, algo)| scope.spawn(move || (name, algo(inputs, options))))
-                .collect();
-            handles
-                .into_iter()
-                // A panicking algorithm is treated as "no solution" rather than poisoning the API.
-                .map(|handle| {
-                    handle.join().unwrap_or((
-                        SelectionAlgorithm::BranchAndBound,
-                        Err(SelectionError::NoSolutionFound),
-                    ))
-                })
-                .collect()
-        });
+    impl SelectionAlgorithm for LargestOnly {
+        fn name(&self) -> &'static str {
+            "largest-only"
+        }
 
-    let mut results = Vec::new();
-    for (name, outcome) in outcomes {
-        match outcome {
-            Ok(result) => results.push((name, result)),
-            Err(
-                error @ (SelectionError::NonPositiveTarget
-                | SelectionError::NonPositiveFeeRate
-                | SelectionError::AbnormallyHighFeeRate),
-            ) => return Err(error),
-            Err(SelectionError::InsufficientFunds { .. } | SelectionError::NoSolutionFound) => {
-                continue
-            }
+        fn select(
+            &self,
+            inputs: &[OutputGroup],
+            options: &CoinSelectionOpt,
+        ) -> Result<SelectionOutput, SelectionError> {
+            let index = (0..inputs.len()).max_by_key(|&i| inputs[i].value).ok_or(
+                SelectionError::InsufficientFunds {
+                    available: 0,
+                    required: options.target_value,
+                },
+            )?;
+            Ok(SelectionOutput {
+                selected_inputs: vec![index],
+                waste: WasteMetric(0),
+                fee: 0,
+                selected_value: inputs[index].value,
+                selected_weight: inputs[index].weight,
+                effective_feerate: FeeRate::default(),
+                metrics: None,
+                dust_inputs: Vec::new(),
+            })
         }
     }
 
-    if results.is_empty() {
-        return Err(insufficient_funds(inputs, options));
+    #[test]
+    fn test_registry_runs_custom_algorithms() {
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+
+        let mut registry = AlgorithmRegistry::with_defaults();
+        registry.register(LargestOnly).register(Algorithm::Fifo);
+        assert_eq!(registry.algorithms().count(), 8);
+        assert!(registry
+            .algorithms()
+            .any(|algorithm| algorithm == Algorithm::Custom("largest-only")));
+
+        let ranked = registry
+            .select_coin(&inputs, &options)
+            .expect("selection should succeed");
+        let (_, custom) = ranked
+            .iter()
+            .find(|(algorithm, _)| *algorithm == Algorithm::Custom("largest-only"))
+            .expect("custom algorithm should be ranked");
+        assert_eq!(custom.selected_inputs, vec![6]);
+
+        let result = AlgorithmRegistry::new().select_coin(&inputs, &options);
+        assert_eq!(
+            result.unwrap_err(),
+            SelectionError::NoSolutionFound {
+                constraint: Constraint::NoAlgorithm
+            }
+        );
     }
 
-    // Order best-first: fewest real UTXOs, then fewest groups, then waste.
-    results.sort_by_key(|(_, output)| {
-        let total_input_count = output
-            .selected_inputs
+    #[test]
+    fn test_select_coin_detailed_reports_every_algorithm() {
+        // 1000 + 2000 lands in BnB's changeless window [2995, 3005]; most algorithms create change.
+        let inputs = vec![
+            OutputGroup {
+                value: 1_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 50_000,
+                ..Default::default()
+            },
+        ];
+        let mut options = setup_options(2_995);
+        options.target_feerate = FeeRate::from_sat_per_wu(1.0);
+        options.long_term_feerate = Some(FeeRate::from_sat_per_wu(1.0));
+        options.base_weight = 0;
+
+        let report = select_coin_detailed(&inputs, &options).expect("selection should succeed");
+        assert_eq!(report.outcomes.len(), DEFAULT_ALGORITHMS.len());
+        assert_eq!(report.outcomes[0].algorithm, report.winner);
+
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        assert_eq!(ranked[0].0, report.winner);
+        let successes = report
+            .outcomes
             .iter()
-            .map(|&idx| inputs[idx].input_count)
-            .sum::<usize>();
-        (
-            total_input_count,
-            output.selected_inputs.len(),
-            output.waste.0,
-        )
-    });
-    Ok(results)
-}
+            .take_while(|outcome| outcome.result.is_ok())
+            .count();
+        assert_eq!(successes, ranked.len());
+        for outcome in &report.outcomes {
+            assert_eq!(outcome.result.is_ok(), outcome.change_value.is_some());
+        }
+        let bnb = report
+            .outcomes
+            .iter()
+            .find(|outcome| outcome.algorithm == Algorithm::BranchAndBound)
+            .expect("bnb should be reported");
+        assert_eq!(bnb.change_value, Some(0));
+        assert!(bnb.result.as_ref().unwrap().metrics.is_some());
 
-#[cfg(test)]
-mod test {
+        let fifo = report
+            .outcomes
+            .iter()
+            .find(|outcome| outcome.algorithm == Algorithm::Fifo)
+            .expect("fifo should be reported");
+        // FIFO spends all three coins and pays for the 50 WU change output.
+        assert_eq!(fifo.change_value, Some(53_000 - 2_995 - 50));
+    }
 
-    use crate::{
-        algorithms::{
-            bnb::select_coin_bnb, coingrinder::select_coin_coingrinder, fifo::select_coin_fifo,
-            lowestlarger::select_coin_lowestlarger,
-        },
-        selectcoin::select_coin,
-        types::{
-            basic_output_group, CoinSelectionOpt, ExcessStrategy, OutputGroup, SelectionError,
-            SelectionOutput,
-        },
-        utils::calculate_fee,
-    };
+    /// Searches until cancelled or out of its `max_duration`, or gives up after ten seconds.
+    struct Stubborn;
 
-    fn setup_basic_output_groups() -> Vec<OutputGroup> {
-        vec![
-            basic_output_group(1_500_000, 50),
-            basic_output_group(2_000_000, 200),
-            basic_output_group(3_000_000, 300),
-            basic_output_group(2_500_000, 100),
-            basic_output_group(4_000_000, 150),
-            basic_output_group(500_000, 250),
-            basic_output_group(6_000_000, 120),
-            basic_output_group(70_000, 50),
-            basic_output_group(800_000, 60),
-            basic_output_group(900_000, 70),
-            basic_output_group(100_000, 80),
-            basic_output_group(1_000_000, 90),
-        ]
+    impl SelectionAlgorithm for Stubborn {
+        fn name(&self) -> &'static str {
+            "stubborn"
+        }
+
+        fn select(
+            &self,
+            _inputs: &[OutputGroup],
+            options: &CoinSelectionOpt,
+        ) -> Result<SelectionOutput, SelectionError> {
+            let started = std::time::Instant::now();
+            while started.elapsed() < std::time::Duration::from_secs(10) {
+                if options
+                    .cancellation
+                    .as_ref()
+                    .is_some_and(|token| token.is_cancelled())
+                {
+                    return Err(SelectionError::Cancelled);
+                }
+                if options
+                    .max_duration
+                    .is_some_and(|max_duration| started.elapsed() >= max_duration)
+                {
+                    break;
+                }
+                std::thread::yield_now();
+            }
+            Err(SelectionError::NoSolutionFound {
+                constraint: Constraint::SearchLimit,
+            })
+        }
     }
 
-    fn setup_options(target_value: u64) -> CoinSelectionOpt {
-        CoinSelectionOpt {
-            target_value,
-            target_feerate: 2.0, // Simplified feerate
-            long_term_feerate: Some(0.4),
-            min_absolute_fee: 0,
-            base_weight: 10,
-            change_weight: 50,
-            change_cost: 10,
-            min_change_value: 500,
-            excess_strategy: ExcessStrategy::ToChange,
+    #[test]
+    fn test_select_coin_max_search_pool_size() {
+        let inputs = setup_basic_output_groups();
+        let searches = [
+            Algorithm::BranchAndBound,
+            Algorithm::CoinGrinder,
+            Algorithm::FewestInputs,
+        ];
+        let skipped = CoinSelectionOpt {
+            max_search_pool_size: Some(inputs.len() - 1),
+            ..setup_options(654321)
+        };
+        let too_large = SelectionError::NoSolutionFound {
+            constraint: Constraint::PoolTooLarge,
+        };
+        // The greedy algorithms still run and fund the payment.
+        let report = select_coin_detailed(&inputs, &skipped).unwrap();
+        for outcome in &report.outcomes {
+            let was_skipped = outcome.result.as_ref().err() == Some(&too_large);
+            assert_eq!(
+                was_skipped,
+                searches.contains(&outcome.algorithm),
+                "{:?}",
+                outcome.algorithm
+            );
         }
+
+        // A pool at the threshold is searched.
+        let searched = CoinSelectionOpt {
+            max_search_pool_size: Some(inputs.len()),
+            ..setup_options(654321)
+        };
+        let report = select_coin_detailed(&inputs, &searched).unwrap();
+        assert!(report
+            .outcomes
+            .iter()
+            .all(|outcome| outcome.result.as_ref().err() != Some(&too_large)));
     }
 
-    /// Asserts that a set of selected inputs actually covers the target plus the total fee.
-    fn assert_covers_target(
-        inputs: &[OutputGroup],
-        options: &CoinSelectionOpt,
-        selected: &[usize],
-    ) {
-        let value: u64 = selected.iter().map(|&i| inputs[i].value).sum();
-        let selected_weight: u64 = selected.iter().map(|&i| inputs[i].weight).sum();
-        let total_fee = calculate_fee(
-            options.base_weight + selected_weight,
-            options.target_feerate,
-        )
-        .max(options.min_absolute_fee);
-        assert!(
-            value >= options.target_value + total_fee,
-            "selection {:?} (value {}) does not cover target {} + fee {}",
-            selected,
-            value,
-            options.target_value,
-            total_fee
-        );
+    #[test]
+    fn test_select_coin_skips_searches_on_large_pools_by_default() {
+        // The target takes nearly every coin, the case that makes the exhaustive searches deep.
+        let inputs: Vec<OutputGroup> = (0..DEFAULT_MAX_SEARCH_POOL_SIZE as u64 + 1)
+            .map(|value| OutputGroup {
+                value: 10_000 + value,
+                ..Default::default()
+            })
+            .collect();
+        let total: u64 = inputs.iter().map(|input| input.value).sum();
+        let options = setup_options(total - 20_000);
+        let too_large = SelectionError::NoSolutionFound {
+            constraint: Constraint::PoolTooLarge,
+        };
+        let report = select_coin_detailed(&inputs, &options).unwrap();
+        for outcome in &report.outcomes {
+            let was_skipped = outcome.result.as_ref().err() == Some(&too_large);
+            assert_eq!(
+                was_skipped,
+                matches!(
+                    outcome.algorithm,
+                    Algorithm::BranchAndBound | Algorithm::CoinGrinder | Algorithm::FewestInputs
+                ),
+                "{:?}",
+                outcome.algorithm
+            );
+        }
+        // The greedy algorithms still fund the payment.
+        let winner = report.outcomes[0].result.as_ref().unwrap();
+        assert_covers_target(&inputs, &options, &winner.selected_inputs);
     }
 
     #[test]
-    fn test_select_coin_rejects_zero_target() {
+    fn test_select_coin_algorithm_durations() {
         let inputs = setup_basic_output_groups();
-        let options = setup_options(0);
-        let result = select_coin(&inputs, &options);
-        assert!(matches!(result, Err(SelectionError::NonPositiveTarget)));
+        let options = CoinSelectionOpt {
+            algorithm_durations: vec![(
+                Algorithm::Custom("stubborn"),
+                std::time::Duration::from_millis(20),
+            )],
+            ..setup_options(654321)
+        };
+        let mut registry = AlgorithmRegistry::new();
+        registry.register(Algorithm::Fifo).register(Stubborn);
+        let report = registry
+            .select_coin_detailed(&inputs, &options)
+            .expect("fifo should succeed");
+        assert_eq!(report.winner, Algorithm::Fifo);
+        // The stubborn search gets its own budget rather than stalling the selection.
+        let stubborn = &report.outcomes[1];
+        assert_eq!(stubborn.algorithm, Algorithm::Custom("stubborn"));
+        assert_eq!(
+            stubborn.result.as_ref().unwrap_err(),
+            &SelectionError::NoSolutionFound {
+                constraint: Constraint::SearchLimit
+            }
+        );
+        assert!(stubborn.runtime < std::time::Duration::from_secs(10));
     }
 
     #[test]
-    fn test_select_coin_successful() {
+    fn test_select_coin_early_exit_on_changeless_match() {
+        // 1000 + 2000 lands in BnB's changeless window, so BnB stops the stubborn search.
+        let inputs = vec![
+            OutputGroup {
+                value: 1_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 50_000,
+                ..Default::default()
+            },
+        ];
+        let mut options = setup_options(2_995);
+        options.target_feerate = FeeRate::from_sat_per_wu(1.0);
+        options.long_term_feerate = Some(FeeRate::from_sat_per_wu(1.0));
+        options.base_weight = 0;
+        options.early_exit = true;
+
+        let mut registry = AlgorithmRegistry::new();
+        registry
+            .register(Algorithm::BranchAndBound)
+            .register(Stubborn);
+        let report = registry
+            .select_coin_detailed(&inputs, &options)
+            .expect("selection should succeed");
+        assert_eq!(report.winner, Algorithm::BranchAndBound);
+        let stubborn = &report.outcomes[1];
+        assert_eq!(stubborn.algorithm, Algorithm::Custom("stubborn"));
+        assert_eq!(
+            stubborn.result.as_ref().unwrap_err(),
+            &SelectionError::NoSolutionFound {
+                constraint: Constraint::EarlyExit
+            }
+        );
+        assert!(stubborn.runtime < std::time::Duration::from_secs(10));
+
+        // Cancellation by the caller still aborts the selection.
+        let token = CancellationToken::new();
+        token.cancel();
+        options.cancellation = Some(token);
+        assert_eq!(
+            registry.select_coin(&inputs, &options).unwrap_err(),
+            SelectionError::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_select_coin_scored_uses_callback() {
         let inputs = setup_basic_output_groups();
         let options = setup_options(654321);
-        let result = select_coin(&inputs, &options);
-        assert!(result.is_ok());
-        let ranked = result.unwrap();
-        let best = &ranked[0].1;
-        assert!(!best.selected_inputs.is_empty());
-        assert_covers_target(&inputs, &options, &best.selected_inputs);
+
+        // Prefer the selection spending the most inputs, e.g. to consolidate.
+        let ranked = select_coin_scored(&inputs, &options, |output, _, _| {
+            -(output.selected_inputs.len() as f64)
+        })
+        .expect("selection should succeed");
+        let counts: Vec<_> = ranked
+            .iter()
+            .map(|(_, output)| output.selected_inputs.len())
+            .collect();
+        assert!(
+            counts.windows(2).all(|w| w[0] >= w[1]),
+            "ranked results are not ordered by the score: {counts:?}"
+        );
+
+        // A constant score keeps the rank strategy order.
+        let constant = select_coin_scored(&inputs, &options, |_, _, _| 0.0).unwrap();
+        let default = select_coin(&inputs, &options).unwrap();
+        let algorithms = |ranked: &[(Algorithm, SelectionOutput)]| {
+            ranked
+                .iter()
+                .map(|(algorithm, _)| *algorithm)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(algorithms(&constant), algorithms(&default));
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn test_select_coin_insufficient_funds() {
+    fn test_select_coin_iter_yields_every_algorithm() {
+        use crate::selectcoin::select_coin_iter;
+
         let inputs = setup_basic_output_groups();
-        let options = setup_options(999_999_999); // Set a target value higher than the sum of all inputs
-        let result = select_coin(&inputs, &options);
+        let options = setup_options(654321);
+
+        let outcomes: Vec<_> = select_coin_iter(inputs.clone(), options.clone()).collect();
+        assert_eq!(outcomes.len(), DEFAULT_ALGORITHMS.len());
+        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
+        for (algorithm, output) in &ranked {
+            let (_, streamed) = outcomes
+                .iter()
+                .find(|(streamed, _)| streamed == algorithm)
+                .expect("every algorithm should be yielded");
+            assert_eq!(
+                streamed.as_ref().unwrap().selected_inputs,
+                output.selected_inputs
+            );
+        }
+
+        // Stopping at the first acceptable selection does not wait for the others.
+        let first = select_coin_iter(inputs.clone(), options.clone())
+            .find_map(|(_, result)| result.ok())
+            .expect("some algorithm should succeed");
+        assert_covers_target(&inputs, &options, &first.selected_inputs);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_select_coin_iter_early_exit_on_changeless_match() {
+        use crate::selectcoin::select_coin_iter;
+
+        // 1000 + 2000 lands in BnB's changeless window.
+        let inputs = vec![
+            OutputGroup {
+                value: 1_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 2_000,
+                ..Default::default()
+            },
+            OutputGroup {
+                value: 50_000,
+                ..Default::default()
+            },
+        ];
+        let mut options = setup_options(2_995);
+        options.target_feerate = FeeRate::from_sat_per_wu(1.0);
+        options.long_term_feerate = Some(FeeRate::from_sat_per_wu(1.0));
+        options.base_weight = 0;
+        options.early_exit = true;
+
+        let outcomes: Vec<_> = select_coin_iter(inputs.clone(), options.clone()).collect();
+        assert_eq!(outcomes.len(), DEFAULT_ALGORITHMS.len());
+        // Whichever algorithm finds the changeless selection first stops the others.
+        assert!(outcomes.iter().any(|(_, result)| result
+            .as_ref()
+            .is_ok_and(|output| change_value(output, &inputs, &options) == 0)));
+        // The algorithms stopped by the changeless match report it, not a cancellation.
+        assert!(outcomes
+            .iter()
+            .all(|(_, result)| result.as_ref().err() != Some(&SelectionError::Cancelled)));
+
+        // Cancellation by the caller still reaches every algorithm.
+        let token = CancellationToken::new();
+        token.cancel();
+        options.cancellation = Some(token);
+        assert!(select_coin_iter(inputs, options)
+            .all(|(_, result)| result.unwrap_err() == SelectionError::Cancelled));
+    }
+
+    #[test]
+    fn test_select_coin_from_iter_maps_stream_positions() {
+        let pool = setup_basic_output_groups();
+        let options = setup_options(654321);
+
+        // Interleave dust that is filtered out while streaming.
+        let stream = pool.iter().flat_map(|group| {
+            [
+                OutputGroup {
+                    value: 10,
+                    weight: 100,
+                    ..Default::default()
+                },
+                group.clone(),
+            ]
+        });
+        let ranked =
+            select_coin_from_iter(stream.clone(), &options).expect("selection should succeed");
+        let direct = select_coin(&pool, &options).expect("selection should succeed");
+        assert_eq!(ranked.len(), direct.len());
+        for ((_, streamed), (_, output)) in ranked.iter().zip(&direct) {
+            let mapped: Vec<_> = output.selected_inputs.iter().map(|&i| 2 * i + 1).collect();
+            assert_eq!(streamed.selected_inputs, mapped);
+            let dust: Vec<_> = (0..pool.len()).map(|i| 2 * i).collect();
+            assert_eq!(streamed.dust_inputs, dust);
+        }
+
+        let options = setup_options(999_999_999);
+        let result = select_coin_from_iter(stream, &options);
         assert!(matches!(
             result,
             Err(SelectionError::InsufficientFunds {
-                available: 22_370_000,
-                required: 1_000_003_059,
+                available: 22_370_120,
+                ..
             })
         ));
     }
 
     #[test]
-    fn test_select_coin_keeps_success_when_another_algorithm_is_insufficient() {
-        let inputs = vec![basic_output_group(1_000, 0)];
-        let mut options = setup_options(1_000);
-        options.target_feerate = 1.0;
-        options.long_term_feerate = Some(1.0);
-        options.base_weight = 0;
-        options.change_cost = 20;
-        options.min_change_value = 100;
+    fn test_select_coin_cancelled() {
+        let inputs = setup_basic_output_groups();
+        let mut options = setup_options(654321);
+        let token = CancellationToken::new();
+        options.cancellation = Some(token.clone());
+        assert!(select_coin(&inputs, &options).is_ok());
 
-        let ranked = select_coin(&inputs, &options).expect("BnB should find the exact match");
-        assert_eq!(ranked[0].1.selected_inputs, vec![0]);
+        token.cancel();
+        let result = select_coin(&inputs, &options);
+        assert!(matches!(result, Err(SelectionError::Cancelled)));
     }
 
-    /// The core contract of the wrapper: it must return the fewest inputs achievable by any of the
-    /// individual algorithms (and a selection that actually covers the target).
     #[test]
-    fn test_select_coin_returns_minimum_inputs() {
+    fn test_select_coin_is_reproducible_with_seed() {
         let inputs = setup_basic_output_groups();
-        let options = setup_options(654321);
+        let mut options = setup_options(654321);
+        options.seed = Some(1234);
 
-        let individual_min_inputs = [
-            select_coin_bnb(&inputs, &options),
-            select_coin_coingrinder(&inputs, &options),
-            select_coin_fifo(&inputs, &options),
-            select_coin_lowestlarger(&inputs, &options),
-        ]
-        .into_iter()
-        .filter_map(|r| r.ok())
-        .map(|r| r.selected_inputs.len())
-        .min()
-        .expect("at least one algorithm should succeed");
+        let summarize = |ranked: Vec<(Algorithm, SelectionOutput)>| {
+            let mut summary: Vec<_> = ranked
+                .into_iter()
+                .map(|(algorithm, output)| (algorithm.name(), output.selected_inputs, output.waste))
+                .collect();
+            summary.sort();
+            summary
+        };
+        let first = summarize(select_coin(&inputs, &options).expect("selection should succeed"));
+        let second = summarize(select_coin(&inputs, &options).expect("selection should succeed"));
+        assert_eq!(first, second);
+    }
 
-        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
-        let chosen = &ranked[0].1;
-        assert_eq!(
-            chosen.selected_inputs.len(),
-            individual_min_inputs,
-            "wrapper did not return the minimum-input selection"
-        );
-        assert_covers_target(&inputs, &options, &chosen.selected_inputs);
+    #[cfg(feature = "async")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::{
+            sync::Arc,
+            task::{Context, Poll, Wake, Waker},
+            thread::{self, Thread},
+        };
+
+        struct ThreadWaker(Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
     }
 
-    /// `select_coin` must return every successful algorithm's result, ordered best-first by the
-    /// policy: fewest real UTXOs, then fewest groups, then waste.
+    #[cfg(feature = "async")]
     #[test]
-    fn test_select_coin_orders_best_first() {
+    fn test_select_coin_async_matches_sync() {
         let inputs = setup_basic_output_groups();
         let options = setup_options(654321);
+        let sync = select_coin(&inputs, &options).expect("selection should succeed");
+        let ranked = block_on(crate::selectcoin::select_coin_async(inputs, options))
+            .expect("selection should succeed");
+        assert_eq!(ranked[0].0, sync[0].0);
+        assert_eq!(ranked[0].1.selected_inputs, sync[0].1.selected_inputs);
+    }
 
-        let ranked = select_coin(&inputs, &options).expect("selection should succeed");
-        assert!(!ranked.is_empty());
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_select_coin_async_cancel() {
+        let inputs = setup_basic_output_groups();
+        let mut options = setup_options(654321);
+        let token = CancellationToken::new();
+        token.cancel();
+        options.cancellation = Some(token);
+        let result = block_on(crate::selectcoin::select_coin_async(inputs, options));
+        assert!(matches!(result, Err(SelectionError::Cancelled)));
+    }
 
-        // The list is sorted best-first by the rank key (non-decreasing keys).
-        let key = |output: &SelectionOutput| {
-            let total_input_count: usize = output
-                .selected_inputs
-                .iter()
-                .map(|&idx| inputs[idx].input_count)
-                .sum();
-            (
-                total_input_count,
-                output.selected_inputs.len(),
-                output.waste.0,
-            )
-        };
-        let keys: Vec<_> = ranked.iter().map(|(_, output)| key(output)).collect();
-        assert!(
-            keys.windows(2).all(|w| w[0] <= w[1]),
-            "ranked results are not ordered best-first: {keys:?}"
+    /// Records the names of the spans created and the messages of the events emitted.
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct Recorder {
+        spans: std::sync::Mutex<Vec<&'static str>>,
+        events: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span.metadata().name());
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            struct Message<'a>(&'a mut String);
+            impl tracing::field::Visit for Message<'_> {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn core::fmt::Debug,
+                ) {
+                    if field.name() == "message" {
+                        *self.0 = format!("{value:?}");
+                    }
+                }
+            }
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            self.events.lock().unwrap().push(message);
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_select_coin_traces_every_algorithm() {
+        let recorder = Arc::new(Recorder::default());
+        let inputs = setup_basic_output_groups();
+        let options = setup_options(654321);
+        tracing::subscriber::with_default(Arc::clone(&recorder), || {
+            select_coin(&inputs, &options).unwrap();
+        });
+
+        // The worker threads report to the caller's subscriber.
+        let spans = recorder.spans.lock().unwrap();
+        assert_eq!(
+            spans.iter().filter(|&&name| name == "select_coin").count(),
+            1
+        );
+        assert_eq!(
+            spans.iter().filter(|&&name| name == "algorithm").count(),
+            DEFAULT_ALGORITHMS.len()
+        );
+        let events = recorder.events.lock().unwrap();
+        let count = |message: &str| events.iter().filter(|event| *event == message).count();
+        assert_eq!(count("ranked"), DEFAULT_ALGORITHMS.len());
+        assert_eq!(
+            count("selection found") + count("no selection"),
+            DEFAULT_ALGORITHMS.len()
         );
+        assert!(count("cut: target out of reach") > 0);
     }
 }
@@ -0,0 +1,343 @@
+//! Replaying a wallet's payment history against a selection algorithm.
+//!
+//! How an algorithm behaves over the lifetime of a wallet matters more than any single selection:
+//! one that always minimizes the fee now may fragment the UTXO pool and pay for it later. Following
+//! Erhardt's coin selection simulator, a scenario is a sequence of incoming and outgoing payments.
+//! [`Simulation`] replays it against a virtual wallet, funds every outgoing payment with the
+//! algorithm under test, keeps the change, and reports the fees, waste and UTXO count the algorithm
+//! led to, so strategies can be compared on evidence.
+//!
+//! Scenarios are written one payment per line as `amount[,feerate]`: a positive amount in sats is
+//! received, a negative one paid, at the optional feerate in sat/vB. Blank lines and lines
+//! starting with `#` are skipped.
+
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::fmt;
+
+use crate::{
+    selectcoin::{change_value, SelectionAlgorithm},
+    types::{Algorithm, CoinSelectionOpt, FeeRate, OutputGroup, ScriptType},
+};
+
+/// A payment of a scenario.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// `value` sats are received as a new coin.
+    Receive { value: u64 },
+    /// `value` sats are paid out, at `feerate` or, when `None`, at the simulation's target feerate.
+    Pay {
+        value: u64,
+        feerate: Option<FeeRate>,
+    },
+}
+
+/// Errors returned by [`parse_scenario`]. Lines are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScenarioError {
+    /// A line has more than the two columns `amount,feerate`.
+    ColumnCount { line: usize, found: usize },
+    /// A column of a line could not be parsed, or the amount is zero.
+    InvalidField { line: usize, column: &'static str },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::ColumnCount { line, found } => {
+                write!(f, "Line {line}: expected at most 2 columns, found {found}")
+            }
+            ScenarioError::InvalidField { line, column } => {
+                write!(f, "Line {line}: invalid {column}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ScenarioError {}
+
+/// Parses a scenario of `amount[,feerate]` lines into payments, in file order.
+pub fn parse_scenario(scenario: &str) -> Result<Vec<Event>, ScenarioError> {
+    let mut events = Vec::new();
+    for (index, record) in scenario.lines().enumerate() {
+        let line = index + 1;
+        let record = record.trim();
+        if record.is_empty() || record.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = record.split(',').map(str::trim).collect();
+        if fields.len() > 2 {
+            return Err(ScenarioError::ColumnCount {
+                line,
+                found: fields.len(),
+            });
+        }
+        let invalid = |column| ScenarioError::InvalidField { line, column };
+        let amount: i64 = fields[0].parse().map_err(|_| invalid("amount"))?;
+        let feerate = match fields.get(1) {
+            Some(feerate) => {
+                let sat_per_vb: f32 = feerate.parse().map_err(|_| invalid("feerate"))?;
+                if !sat_per_vb.is_finite() || sat_per_vb < 0.0 {
+                    return Err(invalid("feerate"));
+                }
+                Some(FeeRate::from_sat_per_vb(sat_per_vb))
+            }
+            None => None,
+        };
+        events.push(match amount {
+            0 => return Err(invalid("amount")),
+            1.. => Event::Receive {
+                value: amount.unsigned_abs(),
+            },
+            _ => Event::Pay {
+                value: amount.unsigned_abs(),
+                feerate,
+            },
+        });
+    }
+    Ok(events)
+}
+
+/// What replaying a scenario with one algorithm led to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationReport {
+    /// The algorithm that funded the payments.
+    pub algorithm: Algorithm,
+    /// Outgoing payments the algorithm funded.
+    pub payments: usize,
+    /// Outgoing payments it could not fund, which are skipped.
+    pub failed_payments: usize,
+    /// Fees paid by the funded payments.
+    pub total_fees: u64,
+    /// Excess the changeless payments dropped to the fee or the recipient, on top of `total_fees`.
+    pub total_excess: u64,
+    /// Summed [`SelectionOutput::waste`](crate::types::SelectionOutput::waste) of the funded
+    /// payments.
+    pub cumulative_waste: i64,
+    /// Coins spent by the funded payments.
+    pub inputs_spent: usize,
+    /// Change outputs the funded payments created.
+    pub change_outputs: usize,
+    /// Number of coins in the wallet after each event of the scenario.
+    pub utxo_counts: Vec<usize>,
+    /// Value left in the wallet at the end.
+    pub final_balance: u64,
+}
+
+impl SimulationReport {
+    /// Mean number of coins in the wallet over the scenario, `0.0` for an empty one.
+    pub fn average_utxo_count(&self) -> f64 {
+        if self.utxo_counts.is_empty() {
+            return 0.0;
+        }
+        self.utxo_counts.iter().sum::<usize>() as f64 / self.utxo_counts.len() as f64
+    }
+}
+
+/// Replays scenarios against a virtual wallet holding coins of a single [`ScriptType`].
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    options: CoinSelectionOpt,
+    script_type: ScriptType,
+}
+
+impl Simulation {
+    /// Creates a simulation funding every payment as described by `options`, with `target_value`
+    /// replaced by the payment and `target_feerate` by its feerate when it has one.
+    /// [`CoinSelectionOpt::preselected_inputs`] are ignored. Received coins and change are
+    /// `script_type` outputs.
+    pub fn new(options: CoinSelectionOpt, script_type: ScriptType) -> Self {
+        Simulation {
+            options: CoinSelectionOpt {
+                preselected_inputs: Vec::new(),
+                ..options
+            },
+            script_type,
+        }
+    }
+
+    /// Replays `events` from an empty wallet, funding the outgoing payments with `algorithm`.
+    pub fn run(&self, events: &[Event], algorithm: &dyn SelectionAlgorithm) -> SimulationReport {
+        let mut report = SimulationReport {
+            algorithm: algorithm.tag(),
+            payments: 0,
+            failed_payments: 0,
+            total_fees: 0,
+            total_excess: 0,
+            cumulative_waste: 0,
+            inputs_spent: 0,
+            change_outputs: 0,
+            utxo_counts: Vec::with_capacity(events.len()),
+            final_balance: 0,
+        };
+        let mut wallet = Vec::new();
+        let mut sequence = 0;
+        let mut receive = |wallet: &mut Vec<OutputGroup>, value| {
+            wallet.push(OutputGroup {
+                creation_sequence: Some(sequence),
+                confirmations: 1,
+                ..OutputGroup::from_script_type(value, self.script_type, 1)
+            });
+            sequence += 1;
+        };
+        for event in events {
+            match *event {
+                Event::Receive { value } => receive(&mut wallet, value),
+                Event::Pay { value, feerate } => {
+                    let options = CoinSelectionOpt {
+                        target_value: value,
+                        target_feerate: feerate.unwrap_or(self.options.target_feerate),
+                        ..self.options.clone()
+                    };
+                    match algorithm.select(&wallet, &options) {
+                        Ok(output) => {
+                            let change = change_value(&output, &wallet, &options);
+                            report.payments += 1;
+                            report.total_fees += output.fee;
+                            report.total_excess += output
+                                .selected_value
+                                .saturating_sub(value + output.fee + change);
+                            report.cumulative_waste += output.waste.0;
+                            report.inputs_spent += output.selected_inputs.len();
+                            let spent: BTreeSet<usize> =
+                                output.selected_inputs.into_iter().collect();
+                            let mut index = 0;
+                            wallet.retain(|_| {
+                                index += 1;
+                                !spent.contains(&(index - 1))
+                            });
+                            if change > 0 {
+                                receive(&mut wallet, change);
+                                report.change_outputs += 1;
+                            }
+                        }
+                        Err(_) => report.failed_payments += 1,
+                    }
+                }
+            }
+            report.utxo_counts.push(wallet.len());
+        }
+        report.final_balance = wallet.iter().map(|coin| coin.value).sum();
+        report
+    }
+
+    /// Replays `events` once per algorithm, each from an empty wallet, reporting in the order of
+    /// `algorithms`.
+    pub fn compare(
+        &self,
+        events: &[Event],
+        algorithms: &[&dyn SelectionAlgorithm],
+    ) -> Vec<SimulationReport> {
+        algorithms
+            .iter()
+            .map(|algorithm| self.run(events, *algorithm))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        selectcoin::SelectionAlgorithm,
+        simulation::{parse_scenario, Event, ScenarioError, Simulation},
+        types::{Algorithm, CoinSelectionOpt, FeeRate, ScriptType},
+    };
+
+    fn setup_simulation() -> Simulation {
+        let options = CoinSelectionOpt::builder()
+            .target_value(1)
+            .target_feerate(FeeRate::from_sat_per_vb(2.0))
+            .long_term_feerate(FeeRate::from_sat_per_vb(5.0))
+            .build()
+            .unwrap();
+        Simulation::new(options, ScriptType::P2wpkh)
+    }
+
+    #[test]
+    fn test_parse_scenario() {
+        let scenario = "# salary, then rent\n\
+                        500000\n\
+                        \n\
+                        -120000, 3.5\n\
+                        -20000\n";
+        assert_eq!(
+            parse_scenario(scenario).unwrap(),
+            vec![
+                Event::Receive { value: 500_000 },
+                Event::Pay {
+                    value: 120_000,
+                    feerate: Some(FeeRate::from_sat_per_vb(3.5))
+                },
+                Event::Pay {
+                    value: 20_000,
+                    feerate: None
+                },
+            ]
+        );
+        assert_eq!(
+            parse_scenario("100\n-50,1,2").unwrap_err(),
+            ScenarioError::ColumnCount { line: 2, found: 3 }
+        );
+        assert_eq!(
+            parse_scenario("0").unwrap_err(),
+            ScenarioError::InvalidField {
+                line: 1,
+                column: "amount"
+            }
+        );
+        assert_eq!(
+            parse_scenario("-50,cheap").unwrap_err(),
+            ScenarioError::InvalidField {
+                line: 1,
+                column: "feerate"
+            }
+        );
+    }
+
+    #[test]
+    fn test_simulation_accounts_for_every_sat() {
+        let mut events = Vec::new();
+        for round in 1..=20u64 {
+            events.push(Event::Receive {
+                value: 40_000 + 7_919 * round,
+            });
+            if round % 2 == 0 {
+                events.push(Event::Pay {
+                    value: 50_000 + 1_000 * round,
+                    feerate: Some(FeeRate::from_sat_per_vb(round as f32)),
+                });
+            }
+        }
+        // More than the wallet ever holds.
+        events.push(Event::Pay {
+            value: 10_000_000,
+            feerate: None,
+        });
+        let received: u64 = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Receive { value } => Some(value),
+                Event::Pay { .. } => None,
+            })
+            .sum();
+
+        let algorithms: [&dyn SelectionAlgorithm; 2] = [&Algorithm::Fifo, &Algorithm::LowestLarger];
+        let reports = setup_simulation().compare(&events, &algorithms);
+        assert_eq!(reports.len(), 2);
+        let paid: u64 = (1..=10).map(|round| 50_000 + 2_000 * round).sum();
+        for (report, algorithm) in reports.iter().zip(algorithms) {
+            assert_eq!(report.algorithm, algorithm.tag());
+            assert_eq!(report.utxo_counts.len(), events.len());
+            assert_eq!((report.payments, report.failed_payments), (10, 1));
+            assert!(report.total_fees > 0);
+            assert_eq!(
+                report.final_balance,
+                received - paid - report.total_fees - report.total_excess
+            );
+            assert_eq!(
+                report.utxo_counts.last(),
+                Some(&(20 + report.change_outputs - report.inputs_spent))
+            );
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! [`proptest`] strategies for property-testing code built on this crate.
+//!
+//! Wallets feeding their own UTXOs and payment parameters to the selection want to check their
+//! integration against more than a handful of hand-written cases. The strategies here generate
+//! [`OutputGroup`]s and [`CoinSelectionOpt`]s within realistic ranges: coins of the standard script
+//! types worth from a thousand sats to ten BTC, mostly confirmed, feerates from 1 to 200 sat/vB,
+//! and options that always pass [`CoinSelectionOpt::validate`]. [`funded_selection`] pairs a pool
+//! with a payment it can fund, for properties about successful selections.
+//!
+//! Both types also implement proptest's [`Arbitrary`], so `any::<OutputGroup>()` works too.
+
+use alloc::vec::Vec;
+
+use proptest::{
+    arbitrary::Arbitrary,
+    collection::{vec, SizeRange},
+    prelude::*,
+};
+
+use crate::{
+    types::{CoinSelectionOpt, ExcessStrategy, FeeRate, OutputGroup, RankStrategy, ScriptType},
+    utils::{base_fee, effective_value, is_dust, is_eligible, multisig_witness_size},
+};
+
+/// The standard script types, P2WSH as a 2-of-3 multisig.
+pub fn script_type() -> impl Strategy<Value = ScriptType> {
+    prop_oneof![
+        Just(ScriptType::P2pkh),
+        Just(ScriptType::P2shP2wpkh),
+        Just(ScriptType::P2wpkh),
+        Just(ScriptType::P2wsh {
+            witness_size: multisig_witness_size(2, 3),
+        }),
+        Just(ScriptType::P2tr),
+    ]
+}
+
+/// Feerates from 1 to 200 sat/vB, in whole sats per vbyte.
+pub fn feerate() -> impl Strategy<Value = FeeRate> {
+    (1u64..=200).prop_map(|sat_per_vb| FeeRate::from_sat_per_kwu(sat_per_vb * 250))
+}
+
+/// A group of one to three coins of one script type, worth 1,000 sats to 10 BTC.
+///
+/// One group in ten is unconfirmed, with unconfirmed ancestors to bump, and one in twenty is
+/// frozen. Half have a creation sequence and a cluster.
+pub fn output_group() -> impl Strategy<Value = OutputGroup> {
+    let value = prop_oneof![
+        1_000u64..100_000,
+        100_000u64..10_000_000,
+        10_000_000u64..=1_000_000_000,
+    ];
+    let confirmations = prop_oneof![1 => Just(0u32), 9 => 1u32..=1_000];
+    (
+        value,
+        script_type(),
+        1usize..=3,
+        proptest::option::of(0u32..10_000),
+        proptest::option::of(0u32..16),
+        confirmations,
+        (0u64..=2_000, 0u64..=2_000),
+        proptest::bool::weighted(0.05),
+    )
+        .prop_map(
+            |(
+                value,
+                script_type,
+                input_count,
+                creation_sequence,
+                cluster,
+                confirmations,
+                (ancestor_weight, ancestor_fee),
+                frozen,
+            )| {
+                let unconfirmed = confirmations == 0;
+                OutputGroup {
+                    creation_sequence,
+                    cluster,
+                    ancestor_weight: if unconfirmed { ancestor_weight } else { 0 },
+                    ancestor_fee: if unconfirmed { ancestor_fee } else { 0 },
+                    confirmations,
+                    frozen,
+                    ..OutputGroup::from_script_type(value, script_type, input_count)
+                }
+            },
+        )
+}
+
+/// A pool of [`output_group`]s with a number of groups in `size`.
+pub fn output_groups(size: impl Into<SizeRange>) -> impl Strategy<Value = Vec<OutputGroup>> {
+    vec(output_group(), size)
+}
+
+/// Options paying 1,000 sats to 5 BTC at a [`feerate`], built through
+/// [`CoinSelectionOpt::builder`] with a change output of a random [`script_type`], so the change
+/// cost and dust limit follow from it. Every excess and rank strategy is covered.
+pub fn coin_selection_opt() -> impl Strategy<Value = CoinSelectionOpt> {
+    let excess_strategy = prop_oneof![
+        Just(ExcessStrategy::ToChange),
+        Just(ExcessStrategy::ToFee),
+        Just(ExcessStrategy::ToRecipient),
+    ];
+    let rank_strategy = prop_oneof![
+        Just(RankStrategy::MinWaste),
+        Just(RankStrategy::MinChange),
+        Just(RankStrategy::MinFee),
+        Just(RankStrategy::MinInputs),
+        Just(RankStrategy::MinWeight),
+    ];
+    (
+        1_000u64..=500_000_000,
+        feerate(),
+        proptest::option::of(feerate()),
+        script_type(),
+        // The header and one to a few recipient outputs.
+        170u64..=1_000,
+        excess_strategy,
+        rank_strategy,
+        any::<bool>(),
+        prop_oneof![Just(0u32), Just(1u32), 2u32..=6],
+        proptest::option::of(1usize..=50),
+    )
+        .prop_map(
+            |(
+                target_value,
+                target_feerate,
+                long_term_feerate,
+                change_script_type,
+                base_weight,
+                excess_strategy,
+                rank_strategy,
+                avoid_change,
+                min_confirmations,
+                max_input_count,
+            )| {
+                let mut builder = CoinSelectionOpt::builder()
+                    .target_value(target_value)
+                    .target_feerate(target_feerate)
+                    .change_script_type(change_script_type)
+                    .base_weight(base_weight)
+                    .excess_strategy(excess_strategy)
+                    .rank_strategy(rank_strategy)
+                    .avoid_change(avoid_change)
+                    .min_confirmations(min_confirmations);
+                if let Some(long_term_feerate) = long_term_feerate {
+                    builder = builder.long_term_feerate(long_term_feerate);
+                }
+                if let Some(max_input_count) = max_input_count {
+                    builder = builder.max_input_count(max_input_count);
+                }
+                builder.build().expect("generated options are always valid")
+            },
+        )
+}
+
+/// A pool of up to `size` groups and options whose target is 1 to 90% of what the pool can fund:
+/// the effective value of its selectable groups, less the fee of the rest of the transaction. No
+/// input cap is set, so spending every selectable group always funds the payment.
+pub fn funded_selection(
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = (Vec<OutputGroup>, CoinSelectionOpt)> {
+    (output_groups(size), coin_selection_opt(), 1u64..=90).prop_filter_map(
+        "the pool cannot fund any payment",
+        |(inputs, options, percent)| {
+            let options = CoinSelectionOpt {
+                max_input_count: None,
+                ..options
+            };
+            let available: i64 = inputs
+                .iter()
+                .filter(|input| is_eligible(input, &options) && !is_dust(input, &options))
+                .map(|input| effective_value(input, options.target_feerate).max(0))
+                .sum();
+            let fundable = u64::try_from(available)
+                .ok()?
+                .checked_sub(base_fee(&options, true))?;
+            let target_value = fundable * percent / 100;
+            (target_value > 0).then_some((
+                inputs,
+                CoinSelectionOpt {
+                    target_value,
+                    ..options
+                },
+            ))
+        },
+    )
+}
+
+impl Arbitrary for OutputGroup {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        output_group().boxed()
+    }
+}
+
+impl Arbitrary for CoinSelectionOpt {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        coin_selection_opt().boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use crate::{
+        selectcoin::select_coin,
+        test_utils::funded_selection,
+        types::{CoinSelectionOpt, OutputGroup},
+    };
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_generated_options_are_valid(options in any::<CoinSelectionOpt>()) {
+            prop_assert!(options.validate().is_ok());
+        }
+
+        #[test]
+        fn test_generated_groups_are_consistent(group in any::<OutputGroup>()) {
+            prop_assert!((1..=3).contains(&group.input_count));
+            prop_assert!(group.confirmations == 0 || group.ancestor_weight == 0);
+        }
+
+        #[test]
+        fn test_funded_selection_is_funded((inputs, options) in funded_selection(1..20)) {
+            for (_, output) in select_coin(&inputs, &options).unwrap() {
+                prop_assert!(output.selected_value >= options.target_value + output.fee);
+            }
+        }
+    }
+}
@@ -1,3 +1,14 @@
+use crate::{
+    privacy::PrivacyScore,
+    utils::{calculate_fee, cost_of_change, excess_output, output_weight, validate_feerates},
+};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
 /// Represents an input candidate for Coinselection, either as a single UTXO or a group of UTXOs.
 ///
 /// A [`OutputGroup`] can be a single UTXO or a group that should be spent together.
@@ -5,6 +16,8 @@
 /// In the UTXO model the output of a transaction is used as the input for the new transaction and hence the name [`OutputGroup`]
 /// The library user must craft this structure correctly, as incorrect representation can lead to incorrect selection results.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct OutputGroup {
     /// Total value of the UTXO(s) that this `WeightedValue` represents.
     pub value: u64,
@@ -13,42 +26,299 @@ pub struct OutputGroup {
     /// The `txin` fields: `prevout`, `nSequence`, `scriptSigLen`, `scriptSig`, `scriptWitnessLen`,
     /// and `scriptWitness` should all be included.
     pub weight: u64,
-    /// The total number of inputs
+    /// The total number of inputs, at least `1`.
     pub input_count: usize,
     /// Specifies the relative creation sequence for this group, used only for FIFO selection.
     ///
     /// Set to `None` if FIFO selection is not required. Sequence numbers are arbitrary indices that denote the relative age of a UTXO group among a set of groups.
     /// To denote the oldest UTXO group, assign it a sequence number of `Some(0)`.
     pub creation_sequence: Option<u32>,
+    /// Ownership cluster this group belongs to, used only for privacy-aware selection.
+    ///
+    /// Groups sharing a cluster are already linked on-chain (e.g. received on the same address or
+    /// from the same counterparty), so spending them together leaks nothing new. Set to `None` when
+    /// the group is unrelated to every other group.
+    pub cluster: Option<u32>,
+    /// Total weight of the unconfirmed ancestor transactions of this group, used for CPFP.
+    ///
+    /// Leave at `0` for confirmed coins. When several groups share the same unconfirmed ancestors,
+    /// attribute them to only one of the groups (or group those coins together) to avoid paying
+    /// for the same ancestors twice.
+    pub ancestor_weight: u64,
+    /// Total fee already paid by the unconfirmed ancestors counted in `ancestor_weight`.
+    pub ancestor_fee: u64,
+    /// Confirmations of the least confirmed UTXO in this group, `0` if any of them is unconfirmed.
+    ///
+    /// Groups below [`CoinSelectionOpt::min_confirmations`] are never selected.
+    pub confirmations: u32,
+    /// Frozen groups stay in the inputs, so indices keep matching the caller's list, but are never
+    /// selected and are not counted as available funds.
+    pub frozen: bool,
+    /// Off-chain cost of signing for this group, in sats, as the wallet prices it: e.g. the time a
+    /// hardware wallet takes to confirm each input, or the fee an HSM charges per signature.
+    ///
+    /// It is never paid on-chain, so it is not part of [`SelectionOutput::waste`], but
+    /// [`crate::selectcoin::select_coin`] adds it to the waste of every selection spending the
+    /// group when ranking. A selection a few sats more wasteful can then win by needing far fewer
+    /// signer round-trips. `0` leaves the ranking unchanged.
+    pub signing_cost: u64,
+    /// Alternative ways of spending the group, e.g. a taproot key path and its script paths, each
+    /// with its own weight. When not empty, it replaces `weight`: the selection spends the group
+    /// through the lightest variant whose [`WeightVariant::path`] is among
+    /// [`CoinSelectionOpt::available_spend_paths`], and fees are computed with that weight. A group
+    /// none of whose variants is available is never selected, like a frozen one.
+    pub weight_variants: Vec<WeightVariant>,
+    /// Whether an unconfirmed group was received from the wallet's own transaction, e.g. the
+    /// change of an earlier payment, so that no third party can double-spend its ancestors. Only
+    /// matters for [`UnconfirmedInputs::SelfTransfersOnly`].
+    pub self_transfer: bool,
+    /// Whether the group holds coinbase outputs, which consensus only lets a transaction spend
+    /// once they have [`COINBASE_MATURITY`] confirmations. Immature groups are never selected.
+    pub coinbase: bool,
+    /// Chain height the tip must reach before the group can be spent by the next block, from the
+    /// latest of its CSV and CLTV locks. Unless [`CoinSelectionOpt::current_height`] has reached
+    /// it, the group is never selected. `None` for groups without a timelock.
+    pub available_height: Option<u32>,
+    /// Labels the wallet attached to the group, e.g. the counterparty or purpose of its coins,
+    /// which the [`CoinSelectionOpt::label_policy`] enforces. A group of several coins carries the
+    /// labels of all of them.
+    pub labels: Vec<String>,
 }
 
-#[cfg(test)]
-pub(crate) fn basic_output_group(value: u64, weight: u64) -> OutputGroup {
-    OutputGroup {
-        value,
-        weight,
-        input_count: 1,
-        creation_sequence: None,
+impl Default for OutputGroup {
+    /// An empty single-UTXO group: every field is zero or empty except `input_count`, which is
+    /// `1` since a group always spends at least one UTXO.
+    fn default() -> Self {
+        OutputGroup {
+            value: 0,
+            weight: 0,
+            input_count: 1,
+            creation_sequence: None,
+            cluster: None,
+            ancestor_weight: 0,
+            ancestor_fee: 0,
+            confirmations: 0,
+            frozen: false,
+            signing_cost: 0,
+            weight_variants: Vec::new(),
+            self_transfer: false,
+            coinbase: false,
+            available_height: None,
+            labels: Vec::new(),
+        }
+    }
+}
+
+/// A way of spending an [`OutputGroup`], see [`OutputGroup::weight_variants`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightVariant {
+    /// The spend path, as the number of its bit in [`CoinSelectionOpt::available_spend_paths`]
+    /// (`0` to `31`), e.g. `0` for a taproot key path and `1` and up for its script leaves.
+    pub path: u8,
+    /// Total weight of spending the group through this path, like [`OutputGroup::weight`].
+    pub weight: u64,
+}
+
+impl OutputGroup {
+    /// Weight of spending the group given the spend paths the signer can satisfy, a bitmask of
+    /// [`WeightVariant::path`]s (`None` for all of them): its lightest available variant, or
+    /// `weight` if it has none. `None` when no variant is available.
+    pub fn spend_weight(&self, available_spend_paths: Option<u32>) -> Option<u64> {
+        if self.weight_variants.is_empty() {
+            return Some(self.weight);
+        }
+        self.weight_variants
+            .iter()
+            .filter(|variant| {
+                available_spend_paths.is_none_or(|paths| {
+                    paths
+                        .checked_shr(variant.path.into())
+                        .is_some_and(|paths| paths & 1 == 1)
+                })
+            })
+            .map(|variant| variant.weight)
+            .min()
+    }
+
+    /// Creates a group of `input_count` UTXOs of the same `script_type`, holding `value` in total.
+    ///
+    /// The weight is computed from the script type, so it covers the outpoint, sequence, script
+    /// sig and witness of every input.
+    pub fn from_script_type(value: u64, script_type: ScriptType, input_count: usize) -> Self {
+        OutputGroup {
+            value,
+            weight: script_type.input_weight() * input_count as u64,
+            input_count,
+            ..Default::default()
+        }
+    }
+}
+
+/// The script type of a spent output, which determines how much weight spending it adds.
+///
+/// Signatures are assumed to be of maximal size (72 bytes for ECDSA), so estimates never fall
+/// short of the real weight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScriptType {
+    /// Pay-to-public-key-hash, spent with a signature and public key in the script sig.
+    P2pkh,
+    /// Pay-to-witness-public-key-hash nested in pay-to-script-hash.
+    P2shP2wpkh,
+    /// Native pay-to-witness-public-key-hash.
+    P2wpkh,
+    /// Pay-to-witness-script-hash. `witness_size` is the serialized size in bytes of the
+    /// satisfying witness, including its item count, length prefixes and the witness script.
+    P2wsh { witness_size: u64 },
+    /// Pay-to-taproot, spent through the key path with a default sighash signature.
+    P2tr,
+}
+
+impl ScriptType {
+    /// Weight of spending one output of this type: the outpoint, sequence, script sig and
+    /// witness.
+    pub const fn input_weight(&self) -> u64 {
+        // Outpoint (36) + sequence (4) + script sig length (1), all non-witness bytes.
+        const BASE: u64 = 41 * 4;
+        // Item count (1) + signature (1 + 72) + public key (1 + 33).
+        const P2WPKH_WITNESS: u64 = 108;
+        match self {
+            // A 107 byte script sig: signature (1 + 72) and public key (1 + 33).
+            ScriptType::P2pkh => BASE + 107 * 4,
+            // A 23 byte script sig pushing the witness program.
+            ScriptType::P2shP2wpkh => BASE + 23 * 4 + P2WPKH_WITNESS,
+            ScriptType::P2wpkh => BASE + P2WPKH_WITNESS,
+            ScriptType::P2wsh { witness_size } => BASE + *witness_size,
+            // Item count (1) + Schnorr signature (1 + 64).
+            ScriptType::P2tr => BASE + 66,
+        }
+    }
+
+    /// Weight of an output paying to this type: the amount, script length and script pubkey.
+    ///
+    /// Use it for [`CoinSelectionOpt::change_weight`] and the outputs counted in
+    /// [`CoinSelectionOpt::base_weight`]; [`crate::utils::output_weight`] covers other scripts.
+    pub const fn output_weight(&self) -> u64 {
+        output_weight(self.script_pubkey_len())
+    }
+
+    /// Length in bytes of the script pubkey of an output paying to this type.
+    pub const fn script_pubkey_len(&self) -> u64 {
+        match self {
+            ScriptType::P2pkh => 25,
+            ScriptType::P2shP2wpkh => 23,
+            ScriptType::P2wpkh => 22,
+            ScriptType::P2wsh { .. } | ScriptType::P2tr => 34,
+        }
+    }
+
+    /// Smallest value an output of this type can hold without being dust at
+    /// `dust_relay_feerate`, following Bitcoin Core's `GetDustThreshold`.
+    ///
+    /// An output is dust when it is worth less than the fee, at the dust relay feerate, of creating
+    /// it and of later spending it with a standard input (148 vbytes, or 67 for witness programs).
+    /// At [`DUST_RELAY_FEERATE`] this gives the familiar 546 sats for P2PKH and 294 for P2WPKH.
+    pub fn dust_limit(&self, dust_relay_feerate: FeeRate) -> u64 {
+        let spend_size = match self {
+            ScriptType::P2pkh | ScriptType::P2shP2wpkh => 148,
+            ScriptType::P2wpkh | ScriptType::P2wsh { .. } | ScriptType::P2tr => 67,
+        };
+        calculate_fee(self.output_weight() + spend_size * 4, dust_relay_feerate)
+    }
+}
+
+/// Bitcoin Core's default `-dustrelayfee` of 3 sat/vB, see [`ScriptType::dust_limit`].
+pub const DUST_RELAY_FEERATE: FeeRate = FeeRate::from_sat_per_kwu(750);
+
+/// A feerate with an explicit unit.
+///
+/// Fees in this crate are computed per weight unit, while wallets and fee estimators usually
+/// quote sats per virtual byte (1 vB = 4 WU) or sats per 1000 weight units. Constructing a
+/// [`FeeRate`] through the matching constructor makes the unit part of the call, instead of
+/// silently paying a fee off by a factor of four.
+///
+/// The rate is stored as an integer number of sats per 1000 weight units (i.e. millisats per
+/// weight unit), so fee and waste computations are exact and identical on every platform. The
+/// floating point constructors round to the nearest sat/kWU; NaN and negative rates become zero
+/// and infinite rates saturate, both of which [`CoinSelectionOpt::validate`] rejects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeRate(u64);
+
+impl FeeRate {
+    /// Creates a feerate from sats per 1000 weight units.
+    pub const fn from_sat_per_kwu(sat_per_kwu: u64) -> Self {
+        FeeRate(sat_per_kwu)
+    }
+
+    /// Creates a feerate from sats per weight unit.
+    pub fn from_sat_per_wu(sat_per_wu: f32) -> Self {
+        FeeRate::from_sat_per_kwu_f64(sat_per_wu as f64 * 1000.0)
+    }
+
+    /// Creates a feerate from sats per virtual byte.
+    pub fn from_sat_per_vb(sat_per_vb: f32) -> Self {
+        FeeRate::from_sat_per_kwu_f64(sat_per_vb as f64 * 250.0)
+    }
+
+    /// Rounds `sat_per_kwu` to the nearest integer. `core` has no `f64::round`; negative and NaN
+    /// rates saturate to zero either way, so adding a half before truncating is equivalent.
+    fn from_sat_per_kwu_f64(sat_per_kwu: f64) -> Self {
+        FeeRate((sat_per_kwu + 0.5) as u64)
+    }
+
+    /// Returns the feerate in sats per 1000 weight units.
+    pub const fn as_sat_per_kwu(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the feerate in sats per weight unit.
+    pub fn as_sat_per_wu(self) -> f32 {
+        self.0 as f32 / 1000.0
+    }
+
+    /// Returns the feerate in sats per virtual byte.
+    pub fn as_sat_per_vb(self) -> f32 {
+        self.0 as f32 / 250.0
     }
 }
 
 /// Options required to compute fees and waste metric.
+///
+/// Build them with [`CoinSelectionOpt::builder`], or with a struct literal ending in
+/// `..Default::default()`. The struct is not `#[non_exhaustive]`, so that such literals work
+/// outside this crate too; adding a field is therefore a semver-breaking change for literals that
+/// list every field, and new options are only added with a default.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CoinSelectionOpt {
     /// The value we need to select.
     pub target_value: u64,
 
-    /// The target feerate we should try and achieve in sats per weight unit.
-    pub target_feerate: f32,
+    /// The target feerate we should try and achieve.
+    pub target_feerate: FeeRate,
 
     /// The long term fee-rate is an estimate of the future transaction fee rate that a wallet might need to pay to spend its UTXOs.
     /// If the current fee rates are less than the long term fee rate, it is optimal to consolidate UTXOs to make the spend.
     /// It affects how the [`WasteMetric`] is computed.
-    pub long_term_feerate: Option<f32>,
+    pub long_term_feerate: Option<FeeRate>,
+
+    /// Optional source of the long-term feerate, e.g. a moving average of recent block feerates
+    /// kept by the wallet. When set, it takes precedence over the static `long_term_feerate`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub long_term_feerate_provider: Option<Arc<dyn LongTermFeeRateProvider>>,
 
     /// Lowest possible transaction fee required to get a transaction included in a block
     pub min_absolute_fee: u64,
 
+    /// Lowest feerate nodes relay, e.g. 1 sat/vB. Selections are made at whichever of this and
+    /// `target_feerate` is higher, so that a `target_feerate` set below it still yields a
+    /// transaction that pays it on its final weight. Unlike `min_absolute_fee`, the floor grows
+    /// with the size of the transaction. `None` trusts `target_feerate`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_relay_feerate: Option<FeeRate>,
+
     /// Weights of data in transaction other than the list of inputs that would be selected.
     ///
     /// This includes weight of the header, total weight out outputs, weight of fields used
@@ -67,11 +337,728 @@ pub struct CoinSelectionOpt {
     pub min_change_value: u64,
 
     /// Strategy to use the excess value other than fee and target
+    #[cfg_attr(feature = "serde", serde(default))]
     pub excess_strategy: ExcessStrategy,
+
+    /// Decides which of the successful selections [`crate::selectcoin::select_coin`] ranks first.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rank_strategy: RankStrategy,
+
+    /// Optional custom objective, e.g. privacy or UTXO age. When set, it replaces `rank_strategy`
+    /// in [`crate::selectcoin::select_coin`] and the waste metric as the objective of the
+    /// optimizing searches (Simulated Annealing, Genetic). `None` keeps both as they are, which
+    /// [`WasteScore`] reproduces for the searches.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub selection_metric: Option<Arc<dyn SelectionMetric>>,
+
+    /// Rank changeless selections ahead of every selection with change, whatever the
+    /// `rank_strategy`. A selection counts as changeless when it creates no change output and
+    /// drops at most `change_cost` of excess to the fee, the window Branch and Bound searches.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub avoid_change: bool,
+
+    /// Inclusive `(min, max)` bounds on the change value of a selection that creates change, e.g.
+    /// for a Lightning node wanting change big enough to fund a future channel but not so big it
+    /// concentrates funds. Changeless selections are always accepted; an algorithm whose
+    /// selection would create change outside the range fails with [`Constraint::ChangeRange`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub change_range: Option<(u64, u64)>,
+
+    /// UTXOs and value every selection must leave unspent, e.g. for a Lightning node that always
+    /// needs coins to bump its anchor outputs. The default reserves nothing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reserve: Reserve,
+
+    /// Which labelled groups a selection may spend, and which labels it must not spend together,
+    /// see [`OutputGroup::labels`]. The default ignores labels.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub label_policy: LabelPolicy,
+
+    /// Let [`crate::selectcoin::select_coin`] return as soon as one algorithm finds a changeless
+    /// selection (in the sense of `avoid_change`), stopping the algorithms still running instead
+    /// of waiting for them. Trades a possibly lower waste for latency in the common case where an
+    /// exact match exists. The stopped algorithms report [`Constraint::EarlyExit`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub early_exit: bool,
+
+    /// Optional token to abort the selection from another thread or task. The search algorithms
+    /// poll it and return [`SelectionError::Cancelled`] once it is cancelled.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cancellation: Option<CancellationToken>,
+
+    /// Optional wall-clock budget for every search algorithm, counted from the moment it starts.
+    /// Once exhausted, a search stops and returns the best selection found so far, or
+    /// [`SelectionError::NoSolutionFound`] if it has none yet. Ignored without the `std` feature,
+    /// which provides the clock.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_duration: Option<Duration>,
+
+    /// Budgets of individual algorithms, taking precedence over `max_duration` for them, e.g. 20 ms
+    /// for [`Algorithm::BranchAndBound`] while the greedy algorithms run unbounded. Applied by the
+    /// wrapper API, so that one pathological search cannot stall the whole selection. Not
+    /// serialized: like [`Algorithm::Custom`] names, the budgets are process-local, and
+    /// deserialized options start without any.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub algorithm_durations: Vec<(Algorithm, Duration)>,
+
+    /// Budget of search nodes BnB, CoinGrinder and FewestInputs may explore before returning the
+    /// best selection found so far. `None` uses [`TOTAL_TRIES`], and at least one node is always
+    /// explored. Constrained devices can lower it to bound the work per selection, servers with
+    /// large pools can raise it to search further.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_tries: Option<u32>,
+
+    /// Pool size above which the wrapper API skips the exhaustive searches, Branch and Bound,
+    /// CoinGrinder and Fewest Inputs, and relies on the other algorithms, whose cost grows with the
+    /// pool rather than exponentially. Skipped searches report [`Constraint::PoolTooLarge`].
+    /// Defaults to [`DEFAULT_MAX_SEARCH_POOL_SIZE`]; `None` runs them on pools of any size.
+    #[cfg_attr(feature = "serde", serde(default = "default_max_search_pool_size"))]
+    pub max_search_pool_size: Option<usize>,
+
+    /// Seed for the randomized algorithms (Simulated Annealing, Genetic) when they run with their
+    /// default parameters, e.g. through [`crate::selectcoin::select_coin`]. `None` uses the default
+    /// seed of each algorithm. Either way, the same inputs and options always give the same result.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+
+    /// Minimum [`OutputGroup::confirmations`] a group needs to be selected. `0` allows
+    /// unconfirmed groups. Excluded groups are not counted as available funds either.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_confirmations: u32,
+
+    /// Which unconfirmed groups a selection may spend, how many and how much of them, and whether
+    /// to try without them first. The default allows every unconfirmed group `min_confirmations`
+    /// lets through.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unconfirmed_policy: UnconfirmedPolicy,
+
+    /// Height of the current chain tip, against which [`OutputGroup::available_height`] is
+    /// checked. `None` when unknown, in which case no timelocked group is selected.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub current_height: Option<u32>,
+
+    /// How strongly the ranking of [`crate::selectcoin::select_coin`] favors selections spending
+    /// the oldest groups, for age-based UTXO hygiene without switching to FIFO wholesale. The
+    /// default ignores age.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub age_preference: AgePreference,
+
+    /// UTXO pool size the ranking steers the wallet toward, consolidating coins while the pool is
+    /// too big and sparing them while it is too small, see [`PoolSizeTarget`]. The waste metric
+    /// only weighs the current transaction; this weighs the pool it leaves behind. `None` leaves
+    /// the pool size out of the ranking.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pool_size_target: Option<PoolSizeTarget>,
+
+    /// Further change outputs to split the change across, after the one described by
+    /// `change_weight` and `min_change_value`. Only used with [`ExcessStrategy::ToChange`].
+    ///
+    /// They are added in order while the change still covers the minimum value of every output
+    /// created so far plus the fee of the new one. Each added output pays its own fee and adds its
+    /// `cost` to the waste.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extra_change_outputs: Vec<ChangeOutput>,
+
+    /// Maximum number of inputs a selection may spend, counted as the summed
+    /// [`OutputGroup::input_count`] of the selected groups. `None` means no limit.
+    ///
+    /// Hardware wallets and interactive protocols such as dual funding cap the number of inputs
+    /// they accept. Selections above the cap are never returned; an algorithm that cannot cover
+    /// the target within it fails with [`Constraint::MaxInputCount`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_input_count: Option<usize>,
+
+    /// Indices of the inputs that every selection must spend, for coin control. The algorithms
+    /// only search the other inputs for whatever the preselected groups leave uncovered.
+    ///
+    /// Preselected groups are spent even when they are below `min_confirmations` or
+    /// `dust_threshold`, and they count towards `max_input_count`. Preselecting a
+    /// [`OutputGroup::frozen`] group is an error, and so is preselecting an immature coinbase or
+    /// still timelocked group, which no valid transaction could spend.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub preselected_inputs: Vec<usize>,
+
+    /// Minimum effective value at `target_feerate` an input needs to be selected. Inputs below it
+    /// cost more to spend than they are worth to the payment; they are dropped before any
+    /// algorithm runs and reported in [`SelectionOutput::dust_inputs`]. `None` uses
+    /// `min_change_value`. Uneconomical inputs, whose effective value is not positive, are always
+    /// dropped, whatever the threshold.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dust_threshold: Option<u64>,
+
+    /// Spend paths the signer can satisfy for this selection, as a bitmask of
+    /// [`WeightVariant::path`]s, e.g. only the script paths while the taproot internal key is
+    /// offline. `None` makes every path available. Only affects groups with
+    /// [`OutputGroup::weight_variants`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub available_spend_paths: Option<u32>,
+}
+
+/// The options the builder starts from: a P2WPKH change output, no fee floor and every optional
+/// constraint off. Not valid on their own, as `target_value` is zero and the change output has no
+/// `min_change_value`, which [`CoinSelectionOptBuilder::build`] derives.
+impl Default for CoinSelectionOpt {
+    fn default() -> Self {
+        CoinSelectionOpt {
+            target_value: 0,
+            target_feerate: FeeRate::default(),
+            long_term_feerate: None,
+            long_term_feerate_provider: None,
+            min_absolute_fee: 0,
+            base_weight: 0,
+            change_weight: ScriptType::P2wpkh.output_weight(),
+            change_cost: 0,
+            min_change_value: 0,
+            excess_strategy: ExcessStrategy::ToChange,
+            rank_strategy: RankStrategy::default(),
+            selection_metric: None,
+            cancellation: None,
+            max_duration: None,
+            seed: None,
+            min_confirmations: 0,
+            extra_change_outputs: Vec::new(),
+            max_input_count: None,
+            preselected_inputs: Vec::new(),
+            dust_threshold: None,
+            avoid_change: false,
+            early_exit: false,
+            max_tries: None,
+            available_spend_paths: None,
+            unconfirmed_policy: Default::default(),
+            current_height: None,
+            age_preference: Default::default(),
+            change_range: None,
+            reserve: Default::default(),
+            pool_size_target: None,
+            label_policy: Default::default(),
+            min_relay_feerate: None,
+            algorithm_durations: Vec::new(),
+            max_search_pool_size: default_max_search_pool_size(),
+        }
+    }
+}
+
+/// An additional change output, see [`CoinSelectionOpt::extra_change_outputs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangeOutput {
+    /// Weight of the output, in weight units.
+    pub weight: u64,
+    /// Smallest value this output may hold.
+    pub min_value: u64,
+    /// Cost of creating and later spending this output, like [`CoinSelectionOpt::change_cost`].
+    pub cost: u64,
+}
+
+impl CoinSelectionOpt {
+    /// Starts building options from defaults suited to a Bitcoin P2WPKH wallet, see
+    /// [`CoinSelectionOptBuilder`].
+    pub fn builder() -> CoinSelectionOptBuilder {
+        CoinSelectionOptBuilder::default()
+    }
+
+    /// Rejects nonsensical options with a specific error, before any algorithm runs.
+    ///
+    /// Every selection function calls this, so it only needs to be called directly to check
+    /// options up front, e.g. when they come from user input.
+    pub fn validate(&self) -> Result<(), SelectionError> {
+        if self.target_value == 0 {
+            return Err(SelectionError::NonPositiveTarget);
+        }
+        validate_feerates(self)?;
+        if let Some(output) = excess_output(self) {
+            if output.weight == 0 {
+                return Err(SelectionError::ZeroChangeWeight);
+            }
+            if output.min_value == 0 {
+                return Err(SelectionError::ZeroMinChangeValue);
+            }
+        }
+        if self.excess_strategy == ExcessStrategy::ToChange {
+            if self
+                .extra_change_outputs
+                .iter()
+                .any(|output| output.weight == 0)
+            {
+                return Err(SelectionError::ZeroChangeWeight);
+            }
+            if self
+                .extra_change_outputs
+                .iter()
+                .any(|output| output.min_value == 0)
+            {
+                return Err(SelectionError::ZeroMinChangeValue);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`CoinSelectionOpt`], created by [`CoinSelectionOpt::builder`].
+///
+/// Only `target_value` and `target_feerate` have to be set. Everything else defaults to a P2WPKH
+/// change output: `change_weight` of 124 WU, `min_change_value` at its 294 sat dust limit, and
+/// [`ExcessStrategy::ToChange`]. [`CoinSelectionOptBuilder::change_script_type`] switches to
+/// another type of change output. Unless set explicitly, `min_change_value` is derived on
+/// [`CoinSelectionOptBuilder::build`] as the dust limit of the change output at the dust relay
+/// feerate, and `change_cost` as the fee of the change output plus the fee of later spending it at
+/// the long-term feerate. `long_term_feerate` defaults to `None`,
+/// i.e. the target feerate. New options will be added with defaults, so code using the builder
+/// keeps compiling.
+#[derive(Debug, Clone)]
+pub struct CoinSelectionOptBuilder {
+    options: CoinSelectionOpt,
+    change_cost: Option<u64>,
+    min_change_value: Option<u64>,
+    change_script_type: ScriptType,
+    dust_relay_feerate: FeeRate,
+}
+
+impl Default for CoinSelectionOptBuilder {
+    fn default() -> Self {
+        CoinSelectionOptBuilder {
+            options: CoinSelectionOpt::default(),
+            change_cost: None,
+            min_change_value: None,
+            change_script_type: ScriptType::P2wpkh,
+            dust_relay_feerate: DUST_RELAY_FEERATE,
+        }
+    }
+}
+
+impl CoinSelectionOptBuilder {
+    /// Sets [`CoinSelectionOpt::target_value`].
+    pub fn target_value(mut self, target_value: u64) -> Self {
+        self.options.target_value = target_value;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::target_feerate`].
+    pub fn target_feerate(mut self, target_feerate: impl Into<FeeRate>) -> Self {
+        self.options.target_feerate = target_feerate.into();
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::long_term_feerate`].
+    pub fn long_term_feerate(mut self, long_term_feerate: impl Into<FeeRate>) -> Self {
+        self.options.long_term_feerate = Some(long_term_feerate.into());
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::long_term_feerate_provider`].
+    pub fn long_term_feerate_provider(
+        mut self,
+        provider: impl LongTermFeeRateProvider + 'static,
+    ) -> Self {
+        self.options.long_term_feerate_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::min_absolute_fee`].
+    pub fn min_absolute_fee(mut self, min_absolute_fee: u64) -> Self {
+        self.options.min_absolute_fee = min_absolute_fee;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::min_relay_feerate`].
+    pub fn min_relay_feerate(mut self, min_relay_feerate: impl Into<FeeRate>) -> Self {
+        self.options.min_relay_feerate = Some(min_relay_feerate.into());
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::base_weight`].
+    pub fn base_weight(mut self, base_weight: u64) -> Self {
+        self.options.base_weight = base_weight;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::change_weight`].
+    pub fn change_weight(mut self, change_weight: u64) -> Self {
+        self.options.change_weight = change_weight;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::change_cost`] instead of deriving it.
+    pub fn change_cost(mut self, change_cost: u64) -> Self {
+        self.change_cost = Some(change_cost);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::min_change_value`] instead of deriving it.
+    pub fn min_change_value(mut self, min_change_value: u64) -> Self {
+        self.min_change_value = Some(min_change_value);
+        self
+    }
+
+    /// Sets the script type of the change output, and [`CoinSelectionOpt::change_weight`] to the
+    /// weight of such an output. The derived `min_change_value` and `change_cost` follow it.
+    pub fn change_script_type(mut self, change_script_type: ScriptType) -> Self {
+        self.change_script_type = change_script_type;
+        self.options.change_weight = change_script_type.output_weight();
+        self
+    }
+
+    /// Sets the dust relay feerate the derived `min_change_value` is computed at, by default
+    /// [`DUST_RELAY_FEERATE`].
+    pub fn dust_relay_feerate(mut self, dust_relay_feerate: impl Into<FeeRate>) -> Self {
+        self.dust_relay_feerate = dust_relay_feerate.into();
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::excess_strategy`].
+    pub fn excess_strategy(mut self, excess_strategy: ExcessStrategy) -> Self {
+        self.options.excess_strategy = excess_strategy;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::rank_strategy`].
+    pub fn rank_strategy(mut self, rank_strategy: RankStrategy) -> Self {
+        self.options.rank_strategy = rank_strategy;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::selection_metric`].
+    pub fn selection_metric(mut self, metric: impl SelectionMetric + 'static) -> Self {
+        self.options.selection_metric = Some(Arc::new(metric));
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::change_range`].
+    pub fn change_range(mut self, min: u64, max: u64) -> Self {
+        self.options.change_range = Some((min, max));
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::reserve`].
+    pub fn reserve(mut self, reserve: Reserve) -> Self {
+        self.options.reserve = reserve;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::label_policy`].
+    pub fn label_policy(mut self, label_policy: LabelPolicy) -> Self {
+        self.options.label_policy = label_policy;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::avoid_change`].
+    pub fn avoid_change(mut self, avoid_change: bool) -> Self {
+        self.options.avoid_change = avoid_change;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::early_exit`].
+    pub fn early_exit(mut self, early_exit: bool) -> Self {
+        self.options.early_exit = early_exit;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::cancellation`].
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.options.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::available_spend_paths`].
+    pub fn available_spend_paths(mut self, available_spend_paths: u32) -> Self {
+        self.options.available_spend_paths = Some(available_spend_paths);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::max_tries`].
+    pub fn max_tries(mut self, max_tries: u32) -> Self {
+        self.options.max_tries = Some(max_tries);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::max_search_pool_size`].
+    pub fn max_search_pool_size(mut self, max_search_pool_size: usize) -> Self {
+        self.options.max_search_pool_size = Some(max_search_pool_size);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::max_duration`].
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.options.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Sets the budget of `algorithm` in [`CoinSelectionOpt::algorithm_durations`], replacing any
+    /// earlier one.
+    pub fn algorithm_duration(mut self, algorithm: Algorithm, duration: Duration) -> Self {
+        self.options
+            .algorithm_durations
+            .retain(|(budgeted, _)| *budgeted != algorithm);
+        self.options.algorithm_durations.push((algorithm, duration));
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::seed`].
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.options.seed = Some(seed);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::min_confirmations`].
+    pub fn min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.options.min_confirmations = min_confirmations;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::current_height`].
+    pub fn current_height(mut self, current_height: u32) -> Self {
+        self.options.current_height = Some(current_height);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::age_preference`].
+    pub fn age_preference(mut self, age_preference: AgePreference) -> Self {
+        self.options.age_preference = age_preference;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::pool_size_target`].
+    pub fn pool_size_target(mut self, pool_size_target: PoolSizeTarget) -> Self {
+        self.options.pool_size_target = Some(pool_size_target);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::unconfirmed_policy`].
+    pub fn unconfirmed_policy(mut self, unconfirmed_policy: UnconfirmedPolicy) -> Self {
+        self.options.unconfirmed_policy = unconfirmed_policy;
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::max_input_count`].
+    pub fn max_input_count(mut self, max_input_count: usize) -> Self {
+        self.options.max_input_count = Some(max_input_count);
+        self
+    }
+
+    /// Sets [`CoinSelectionOpt::dust_threshold`].
+    pub fn dust_threshold(mut self, dust_threshold: u64) -> Self {
+        self.options.dust_threshold = Some(dust_threshold);
+        self
+    }
+
+    /// Appends an input index to [`CoinSelectionOpt::preselected_inputs`].
+    pub fn preselect_input(mut self, index: usize) -> Self {
+        self.options.preselected_inputs.push(index);
+        self
+    }
+
+    /// Appends an output to [`CoinSelectionOpt::extra_change_outputs`].
+    pub fn extra_change_output(mut self, output: ChangeOutput) -> Self {
+        self.options.extra_change_outputs.push(output);
+        self
+    }
+
+    /// Derives the remaining defaults and returns the options if
+    /// [`CoinSelectionOpt::validate`] accepts them.
+    pub fn build(self) -> Result<CoinSelectionOpt, SelectionError> {
+        let mut options = self.options;
+        options.min_change_value = self
+            .min_change_value
+            .unwrap_or_else(|| self.change_script_type.dust_limit(self.dust_relay_feerate));
+        options.validate()?;
+        options.change_cost = self
+            .change_cost
+            .unwrap_or_else(|| cost_of_change(&options, self.change_script_type.input_weight()));
+        Ok(options)
+    }
+}
+
+/// Shared flag used to abort a running selection, see [`CoinSelectionOpt::cancellation`].
+///
+/// Clones share the same flag, so a clone kept by the caller can cancel a selection running
+/// elsewhere.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    /// Flag of the token this one was derived from with [`CancellationToken::child`].
+    parent: Option<Arc<AtomicBool>>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a token that is cancelled along with `parent`, but whose own cancellation leaves
+    /// `parent` untouched.
+    #[cfg(feature = "std")]
+    pub(crate) fn child(parent: Option<&CancellationToken>) -> Self {
+        CancellationToken {
+            flag: Arc::default(),
+            parent: parent.map(|parent| Arc::clone(&parent.flag)),
+        }
+    }
+
+    /// Requests cancellation of every selection using this token.
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`CancellationToken::cancel`] has been called on this token or one of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.load(Ordering::Relaxed))
+    }
 }
 
-/// Strategy to decide what to do with the excess amount.
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Source of the long-term feerate used by the waste metric, see
+/// [`CoinSelectionOpt::long_term_feerate_provider`].
+///
+/// The waste metric weighs the fee of spending an input now against spending it later at the
+/// long-term feerate, so the comparison is only meaningful with a realistic estimate. The
+/// provider is queried while the algorithms search, so implementations should return a cached
+/// value and refresh it out of band, e.g. whenever a new block arrives.
+pub trait LongTermFeeRateProvider: fmt::Debug + Send + Sync {
+    /// Returns the current estimate of the long-term feerate.
+    fn long_term_feerate(&self) -> FeeRate;
+}
+
+/// A fixed feerate is the simplest provider.
+impl LongTermFeeRateProvider for FeeRate {
+    fn long_term_feerate(&self) -> FeeRate {
+        *self
+    }
+}
+
+/// Objective a selection is scored by, see [`CoinSelectionOpt::selection_metric`].
+///
+/// Implement it to optimize for something other than fees, such as privacy, coin age or the
+/// health of the wallet's UTXO set, without forking the crate.
+pub trait SelectionMetric: fmt::Debug + Send + Sync {
+    /// Scores `output`, a selection from `inputs`; lower is better.
+    fn score(
+        &self,
+        output: &SelectionOutput,
+        inputs: &[OutputGroup],
+        options: &CoinSelectionOpt,
+    ) -> i64;
+}
+
+/// The default objective: the selection's [`WasteMetric`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WasteScore;
+
+impl SelectionMetric for WasteScore {
+    fn score(&self, output: &SelectionOutput, _: &[OutputGroup], _: &CoinSelectionOpt) -> i64 {
+        output.waste.0
+    }
+}
+
+/// Limits on the unconfirmed groups (`confirmations == 0`) a selection spends, see
+/// [`CoinSelectionOpt::unconfirmed_policy`].
+///
+/// The caps hold for every selection: when the eligible unconfirmed groups exceed them, only the
+/// ones with the highest effective value that fit are offered to the algorithms, after any
+/// preselected ones, which always count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct UnconfirmedPolicy {
+    /// Which unconfirmed groups may be spent at all.
+    pub allowed: UnconfirmedInputs,
+    /// Most unconfirmed inputs a selection may spend, counted as their summed
+    /// [`OutputGroup::input_count`]. `None` means no limit.
+    pub max_inputs: Option<usize>,
+    /// Most value a selection may spend from unconfirmed groups. `None` means no limit.
+    pub max_value: Option<u64>,
+    /// Select from the confirmed groups alone first, and only fall back to the unconfirmed ones
+    /// the policy allows when that fails. Honored by [`crate::selectcoin::select_coin`] and the
+    /// other functions of [`crate::selectcoin`] ranking several algorithms.
+    pub prefer_confirmed: bool,
+}
+
+/// Which unconfirmed groups may be spent, see [`UnconfirmedPolicy::allowed`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnconfirmedInputs {
+    /// Every unconfirmed group.
+    #[default]
+    Any,
+    /// Only unconfirmed groups received from the wallet's own transactions, see
+    /// [`OutputGroup::self_transfer`].
+    SelfTransfersOnly,
+    /// No unconfirmed group: only confirmed ones are spent.
+    ConfirmedOnly,
+}
+
+/// Coins a selection must leave unspent, see [`CoinSelectionOpt::reserve`].
+///
+/// Only the coins a selection could have spent count towards the reserve: the eligible groups
+/// worth more than dust, and any preselected ones. An algorithm whose selection would leave fewer
+/// fails with [`Constraint::Reserve`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Reserve {
+    /// Fewest UTXOs to leave unspent, counted as the [`OutputGroup::input_count`] of the groups.
+    pub utxos: usize,
+    /// Least value to leave unspent, in sats.
+    pub value: u64,
+}
+
+/// Labels a selection must respect, see [`CoinSelectionOpt::label_policy`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LabelPolicy {
+    /// When not empty, only groups carrying at least one of these labels are selected; the others
+    /// are left out like frozen ones. Preselected groups are spent regardless.
+    pub only: Vec<String>,
+    /// Pairs of labels no selection may spend together, e.g. the coins of two identities. An
+    /// algorithm whose selection would spend a group labelled with one label of a pair and a
+    /// group labelled with the other fails with [`Constraint::MixedLabels`].
+    pub never_mix: Vec<(String, String)>,
+}
+
+/// How strongly the ranking favors selections spending the oldest groups, see
+/// [`CoinSelectionOpt::age_preference`].
+///
+/// Groups are ordered by age: more [`OutputGroup::confirmations`] first, then lower
+/// [`OutputGroup::creation_sequence`], groups without one last. A selection passes over an
+/// eligible group it leaves unspent for every younger group it spends, so one spending the oldest
+/// groups first, as FIFO does, passes over none.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AgePreference {
+    /// Age plays no part in the ranking.
+    #[default]
+    Ignore,
+    /// Adds `sats_per_group` to the ranked waste of a selection for every group it passes over,
+    /// so a younger selection only wins by saving more than that.
+    Weighted { sats_per_group: u64 },
+    /// Ranks the selections passing over the fewest groups first, ahead of the rank strategy.
+    /// With [`CoinSelectionOpt::avoid_change`], changeless selections still come first.
+    StrictOldestFirst,
+}
+
+/// UTXO pool size the ranking steers toward, see [`CoinSelectionOpt::pool_size_target`].
+///
+/// The pool a selection leaves behind holds the UTXOs of the eligible groups worth more than dust
+/// it does not spend, plus its change outputs. Every UTXO between that and `utxos` adds
+/// `sats_per_utxo` to its ranked waste: above the target, selections consolidating more coins
+/// rank better; below it, selections spending fewer coins do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolSizeTarget {
+    /// Number of UTXOs the wallet aims to hold, counted as [`OutputGroup::input_count`].
+    pub utxos: usize,
+    /// Penalty in sats for every UTXO the pool left behind is away from `utxos`.
+    pub sats_per_utxo: u64,
+}
+
+/// Strategy to decide what to do with the excess amount. Defaults to [`ExcessStrategy::ToChange`],
+/// like [`CoinSelectionOpt::default`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExcessStrategy {
     /// Adds the excess amount to the transaction fee. This increases the fee rate
     /// and may lead to faster confirmation, but wastes the excess amount.
@@ -85,21 +1072,138 @@ pub enum ExcessStrategy {
     /// Creates a change output with the excess amount. This preserves privacy and
     /// allows reuse of the excess amount in future transactions, but increases
     /// transaction size and creates dust UTXOs if the amount is too small.
+    #[default]
     ToChange,
+
+    /// Sends the excess to a caller-described drain output instead of the regular change output,
+    /// e.g. a donation or a re-deposit address. Only the fee of creating the drain output counts
+    /// as waste; below its `min_value` the excess goes to the fee instead.
+    ToDrain(DrainOutput),
+}
+
+/// An output that receives the excess of a selection, see [`ExcessStrategy::ToDrain`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DrainOutput {
+    /// Weight of the drain output, in weight units.
+    pub weight: u64,
+    /// Smallest excess worth creating the drain output for.
+    pub min_value: u64,
+}
+
+/// Criterion used to rank the selections returned by [`crate::selectcoin::select_coin`].
+///
+/// Every strategy falls back to the remaining criteria to break ties, so the order is total.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RankStrategy {
+    /// Lowest [`WasteMetric`] first.
+    MinWaste,
+    /// Smallest change output first; changeless selections rank ahead of any change.
+    MinChange,
+    /// Lowest absolute fee first.
+    MinFee,
+    /// Fewest real UTXOs first, then fewest [`OutputGroup`]s, then lowest waste.
+    #[default]
+    MinInputs,
+    /// Lowest total input weight first.
+    MinWeight,
 }
 
 /// Error Describing failure of a selection attempt, on any subset of inputs.
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SelectionError {
     /// The available UTXOs cannot cover the target and fees for spending the supplied inputs.
+    ///
+    /// `available` is the most that could be selected and `required` what spending all of it
+    /// would take; [`SelectionError::shortfall`] is the difference.
     InsufficientFunds {
         available: u64,
         required: u64,
     },
-    NoSolutionFound,
+    /// The inputs could cover the target, but `constraint` ruled out every selection.
+    NoSolutionFound {
+        constraint: Constraint,
+    },
     NonPositiveTarget,
     NonPositiveFeeRate,
     AbnormallyHighFeeRate,
+    /// The lower bound of a feerate band is above its upper bound, see
+    /// [`crate::selectcoin::select_coin_feerate_band`].
+    InvalidFeeRateBand,
+    /// [`ExcessStrategy::ToChange`] or [`ExcessStrategy::ToDrain`] is requested but the weight of
+    /// the excess output is zero, so it would be free.
+    ZeroChangeWeight,
+    /// [`ExcessStrategy::ToChange`] or [`ExcessStrategy::ToDrain`] is requested but the minimum
+    /// value of the excess output is zero, which would allow an output holding no value.
+    ZeroMinChangeValue,
+    /// An index in [`CoinSelectionOpt::preselected_inputs`] is past the end of the inputs.
+    PreselectedInputOutOfRange,
+    /// An index in [`CoinSelectionOpt::preselected_inputs`] points at a [`OutputGroup::frozen`]
+    /// group.
+    PreselectedInputFrozen,
+    /// An index in [`CoinSelectionOpt::preselected_inputs`] points at an immature
+    /// [`OutputGroup::coinbase`] group, or one whose [`OutputGroup::available_height`] is not
+    /// reached yet.
+    PreselectedInputLocked,
+    /// The selection was aborted through its [`CancellationToken`].
+    Cancelled,
+    /// The summed values, weights or fees of the inputs and options do not fit a `u64` (or the
+    /// waste an `i64`), so no selection can be computed exactly.
+    ArithmeticOverflow,
+    /// An [`OutputGroup::input_count`] is zero, although every group spends at least one UTXO.
+    ZeroInputCount,
+}
+
+impl SelectionError {
+    /// How many more sats the inputs would need to cover the target, for
+    /// [`SelectionError::InsufficientFunds`].
+    pub fn shortfall(&self) -> Option<u64> {
+        match self {
+            SelectionError::InsufficientFunds {
+                available,
+                required,
+            } => Some(required.saturating_sub(*available)),
+            _ => None,
+        }
+    }
+}
+
+/// The constraint that blocked a selection, see [`SelectionError::NoSolutionFound`].
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Constraint {
+    /// No combination of inputs matches the target closely enough to skip the change output.
+    Changeless,
+    /// The search budget ([`CoinSelectionOpt::max_tries`] or [`CoinSelectionOpt::max_duration`]) ran out before a
+    /// solution was found.
+    SearchLimit,
+    /// Every input is worth less than [`CoinSelectionOpt::dust_threshold`] once its spending fee is
+    /// paid.
+    DustFilter,
+    /// The payment does not fit the consolidation weight budget.
+    MaxWeight,
+    /// The target feerate is not below the consolidation threshold.
+    MaxFeeRate,
+    /// No algorithm was available to run.
+    NoAlgorithm,
+    /// The algorithm panicked.
+    AlgorithmPanicked,
+    /// The target cannot be covered within [`CoinSelectionOpt::max_input_count`] inputs.
+    MaxInputCount,
+    /// The algorithm was stopped because another one already found a changeless selection, see
+    /// [`CoinSelectionOpt::early_exit`].
+    EarlyExit,
+    /// The selection would create change outside [`CoinSelectionOpt::change_range`].
+    ChangeRange,
+    /// The selection would leave less unspent than [`CoinSelectionOpt::reserve`].
+    Reserve,
+    /// The selection would spend labels together that [`LabelPolicy::never_mix`] keeps apart.
+    MixedLabels,
+    /// The exhaustive search was skipped, as the pool holds more inputs than
+    /// [`CoinSelectionOpt::max_search_pool_size`].
+    PoolTooLarge,
 }
 
 /// Measures the efficiency of input selection in satoshis, helping evaluate algorithms based on current and long-term fee rates
@@ -109,19 +1213,69 @@ pub enum SelectionError {
 /// In low fee rate environments, selecting more inputs reduces overall fees.
 /// It compares various selection algorithms to find the most optimized solution, represented by the lowest [WasteMetric] value.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WasteMetric(pub i64);
 
-/// Identifies which selection algorithm produced a given [`SelectionOutput`].
+/// The waste of a selection broken down by cause, see [`crate::utils::calculate_waste`].
+///
+/// At most one of `excess` and `change_cost` is non-zero: the excess either pays for an output
+/// that receives it or is dropped to the fee (or the recipient).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WasteComponents {
+    /// Excess value dropped to the fee or the recipient because no output receives it.
+    pub excess: u64,
+    /// Fee of spending the selected inputs at the target feerate. Only its difference from the
+    /// long-term fee, `timing_cost`, counts as waste.
+    pub input_cost: u64,
+    /// Fee of spending the selected inputs now rather than later at the long-term feerate.
+    /// Negative when the target feerate is below the long-term one, which rewards consolidating.
+    pub timing_cost: i64,
+    /// Cost of creating, and for change also later spending, the output that receives the excess.
+    pub change_cost: u64,
+}
+
+impl WasteComponents {
+    /// The waste metric: `timing_cost + change_cost + excess`, or
+    /// [`SelectionError::ArithmeticOverflow`] if it does not fit an `i64`.
+    pub fn total(&self) -> Result<WasteMetric, SelectionError> {
+        let overflow = |_| SelectionError::ArithmeticOverflow;
+        let change_cost = i64::try_from(self.change_cost).map_err(overflow)?;
+        let excess = i64::try_from(self.excess).map_err(overflow)?;
+        self.timing_cost
+            .checked_add(change_cost)
+            .and_then(|waste| waste.checked_add(excess))
+            .map(WasteMetric)
+            .ok_or(SelectionError::ArithmeticOverflow)
+    }
+}
+
+/// Identifies a selection algorithm: which one produced a given [`SelectionOutput`], or which ones
+/// [`crate::selectcoin::select_coin_with`] should run.
+///
+/// `Genetic`, `Knapsack`, `MinimumLinkage` and `SingleRandomDraw` are opt-in and are not part of
+/// [`crate::selectcoin::DEFAULT_ALGORITHMS`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum SelectionAlgorithm {
+pub enum Algorithm {
+    Blackjack,
     BranchAndBound,
     CoinGrinder,
+    FewestInputs,
     Fifo,
+    Genetic,
+    Knapsack,
     LowestLarger,
+    MinimumLinkage,
+    SimulatedAnnealing,
+    SingleRandomDraw,
+    /// An algorithm implemented outside this crate, identified by its
+    /// [`crate::selectcoin::SelectionAlgorithm::name`].
+    Custom(&'static str),
 }
 
 /// The result of selection algorithm.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SelectionOutput {
     /// The selected input indices, refers to the indices of the inputs Slice Reference.
     pub selected_inputs: Vec<usize>,
@@ -129,6 +1283,93 @@ pub struct SelectionOutput {
     pub waste: WasteMetric,
     /// The transaction fee (in satoshis) for the above inputs.
     pub fee: u64,
+    /// Total value of the selected inputs, before fees.
+    pub selected_value: u64,
+    /// Total weight of the selected inputs.
+    pub selected_weight: u64,
+    /// The feerate `fee` pays for the transaction: its base weight, the selected inputs and, if
+    /// `fee` pays for it, the change output. CPFP bump fees make it exceed the target feerate.
+    pub effective_feerate: FeeRate,
+    /// Search statistics, reported by the tree-search algorithms (BnB, CoinGrinder, Fewest Inputs).
+    pub metrics: Option<SearchMetrics>,
+    /// Indices of the inputs left out because their effective value is not positive or below
+    /// [`CoinSelectionOpt::dust_threshold`].
+    pub dust_inputs: Vec<usize>,
+}
+
+/// Statistics of a bounded tree search, useful to tune [`CoinSelectionOpt::max_tries`] and
+/// [`CoinSelectionOpt::max_duration`] from real data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchMetrics {
+    /// Number of search nodes explored, out of the [`CoinSelectionOpt::max_tries`] budget.
+    pub tries: u32,
+    /// Number of branches cut because they could no longer reach the target or beat the best
+    /// selection found so far.
+    pub pruned: u32,
+}
+
+/// What a single algorithm returned during [`crate::selectcoin::select_coin_detailed`].
+#[derive(Debug)]
+pub struct AlgorithmOutcome {
+    /// The algorithm that ran.
+    pub algorithm: Algorithm,
+    /// Its selection, or the error it returned.
+    pub result: Result<SelectionOutput, SelectionError>,
+    /// Value of the change output the selection creates (`0` if changeless), `None` if it failed.
+    pub change_value: Option<u64>,
+    /// Privacy of the selection, next to its waste, `None` if it failed.
+    pub privacy: Option<PrivacyScore>,
+    /// Wall-clock time the algorithm took.
+    pub runtime: Duration,
+}
+
+/// Diagnostics of a [`crate::selectcoin::select_coin_detailed`] run.
+#[derive(Debug)]
+pub struct SelectionReport {
+    /// The algorithm whose selection ranked first.
+    pub winner: Algorithm,
+    /// One entry per algorithm that ran: the successful ones first, ordered best-first, then the
+    /// failed ones.
+    pub outcomes: Vec<AlgorithmOutcome>,
+}
+
+/// A selection of [`crate::selectcoin::select_coin_feerate_band`], priced at both ends of the band.
+#[derive(Debug)]
+pub struct FeeRateBandSelection {
+    /// The algorithm that made the selection.
+    pub algorithm: Algorithm,
+    /// The selection at the maximum feerate, where it was made.
+    pub at_max: SelectionOutput,
+    /// The same inputs at the minimum feerate, with the lower fee and the larger change or excess
+    /// they leave there.
+    pub at_min: SelectionOutput,
+}
+
+/// The result of sweeping every economically spendable input into a single recipient output.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepOutput {
+    /// The selected input indices, refers to the indices of the inputs Slice Reference.
+    pub selected_inputs: Vec<usize>,
+    /// The transaction fee (in satoshis) for the above inputs.
+    pub fee: u64,
+    /// The largest amount (in satoshis) the recipient can receive: total input value minus `fee`.
+    pub max_sendable: u64,
+}
+
+/// The transaction spending a selection, as predicted by
+/// [`crate::utils::estimate_transaction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TxEstimate {
+    /// Weight of the whole transaction, in weight units.
+    pub weight: u64,
+    /// Virtual size, the weight divided by four and rounded up, in vbytes.
+    pub vsize: u64,
+    /// Fee at the target feerate, never below the minimum absolute fee, plus the CPFP bump owed
+    /// for unconfirmed ancestors, in sats.
+    pub fee: u64,
 }
 
 /// EffectiveValue type alias
@@ -137,5 +1378,253 @@ pub type EffectiveValue = u64;
 /// Weight type alias
 pub type Weight = u64;
 
-/// Upper bound on explored nodes,the bounded-search policy used by BnB and Coingrinder.
+/// Confirmations a coinbase output needs before a transaction may spend it, see
+/// [`OutputGroup::coinbase`].
+pub const COINBASE_MATURITY: u32 = 100;
+
+/// Default upper bound on explored nodes, the bounded-search policy used by BnB, CoinGrinder and
+/// FewestInputs. See [`CoinSelectionOpt::max_tries`].
 pub const TOTAL_TRIES: u32 = 100_000;
+
+/// Default [`CoinSelectionOpt::max_search_pool_size`]. Above a few thousand inputs the exhaustive
+/// searches mostly burn their try budget, while the greedy algorithms still find a selection.
+pub const DEFAULT_MAX_SEARCH_POOL_SIZE: usize = 5_000;
+
+fn default_max_search_pool_size() -> Option<usize> {
+    Some(DEFAULT_MAX_SEARCH_POOL_SIZE)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        types::{
+            CoinSelectionOpt, ExcessStrategy, FeeRate, LongTermFeeRateProvider, OutputGroup,
+            ScriptType, SelectionError, DUST_RELAY_FEERATE,
+        },
+        utils::calculate_fee_and_waste,
+    };
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        use crate::types::{Constraint, RankStrategy};
+
+        let options = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_vb(2.0))
+            .cancellation(Default::default())
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&options).unwrap();
+        let replayed: CoinSelectionOpt = serde_json::from_str(&json).unwrap();
+        assert_eq!(replayed.target_value, options.target_value);
+        assert_eq!(replayed.target_feerate, options.target_feerate);
+        assert_eq!(replayed.change_cost, options.change_cost);
+        // The cancellation token is process-local and is not persisted.
+        assert!(replayed.cancellation.is_none());
+
+        // Options serialized before the optional fields existed take their defaults.
+        let mut json = serde_json::to_value(&options).unwrap();
+        for field in ["max_duration", "seed", "min_confirmations"] {
+            json.as_object_mut().unwrap().remove(field);
+        }
+        let replayed: CoinSelectionOpt = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            (
+                replayed.max_duration,
+                replayed.seed,
+                replayed.min_confirmations
+            ),
+            (None, None, 0)
+        );
+
+        // A minimal payload with only the required fields.
+        let replayed: CoinSelectionOpt = serde_json::from_str(
+            r#"{"target_value": 100000, "target_feerate": 500, "min_absolute_fee": 0,
+                "base_weight": 0, "change_weight": 0, "change_cost": 0, "min_change_value": 0}"#,
+        )
+        .unwrap();
+        assert_eq!(replayed.target_feerate, FeeRate::from_sat_per_kwu(500));
+        assert_eq!(replayed.excess_strategy, ExcessStrategy::ToChange);
+        assert_eq!(replayed.rank_strategy, RankStrategy::default());
+        assert!(replayed.algorithm_durations.is_empty());
+        let json = serde_json::to_string(&replayed).unwrap();
+        let round_tripped: CoinSelectionOpt = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped.target_value, 100_000);
+        assert_eq!(round_tripped.rank_strategy, RankStrategy::default());
+
+        // Missing fields of an `OutputGroup` take their defaults.
+        let group: OutputGroup = serde_json::from_str(r#"{"value": 5000, "weight": 272}"#).unwrap();
+        assert_eq!(
+            (group.value, group.weight, group.input_count),
+            (5_000, 272, 1)
+        );
+
+        let error = SelectionError::NoSolutionFound {
+            constraint: Constraint::DustFilter,
+        };
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SelectionError>(&json).unwrap(),
+            error
+        );
+    }
+
+    #[test]
+    fn test_script_type_input_weights() {
+        assert_eq!(ScriptType::P2pkh.input_weight(), 148 * 4);
+        assert_eq!(ScriptType::P2shP2wpkh.input_weight(), 364);
+        assert_eq!(ScriptType::P2wpkh.input_weight(), 68 * 4);
+        assert_eq!(ScriptType::P2tr.input_weight(), 230);
+        // 2-of-3 multisig: count (1), empty dummy (1), two signatures (2 * 73), script (1 + 105).
+        let multisig = ScriptType::P2wsh { witness_size: 254 };
+        assert_eq!(multisig.input_weight(), 164 + 254);
+
+        let group = OutputGroup::from_script_type(50_000, ScriptType::P2wpkh, 3);
+        assert_eq!(group.value, 50_000);
+        assert_eq!(group.weight, 3 * 272);
+        assert_eq!(group.input_count, 3);
+    }
+
+    #[test]
+    fn test_feerate_unit_conversions() {
+        let feerate = FeeRate::from_sat_per_vb(10.0);
+        assert_eq!(feerate, FeeRate::from_sat_per_wu(2.5));
+        assert_eq!(feerate, FeeRate::from_sat_per_kwu(2_500));
+        assert_eq!(feerate.as_sat_per_wu(), 2.5);
+        assert_eq!(feerate.as_sat_per_vb(), 10.0);
+        assert_eq!(feerate.as_sat_per_kwu(), 2_500);
+        assert!(FeeRate::from_sat_per_vb(1.0) > FeeRate::from_sat_per_kwu(249));
+        // Sub-sat/kWU precision rounds to the nearest representable rate.
+        assert_eq!(
+            FeeRate::from_sat_per_wu(0.1234),
+            FeeRate::from_sat_per_kwu(123)
+        );
+        assert_eq!(
+            FeeRate::from_sat_per_wu(f32::NAN),
+            FeeRate::from_sat_per_kwu(0)
+        );
+        assert_eq!(FeeRate::from_sat_per_wu(-1.0), FeeRate::from_sat_per_kwu(0));
+    }
+
+    #[test]
+    fn test_builder_defaults_and_derived_change_cost() {
+        let options = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_wu(2.0))
+            .long_term_feerate(FeeRate::from_sat_per_wu(1.0))
+            .build()
+            .unwrap();
+        assert_eq!(options.change_weight, 124);
+        assert_eq!(options.min_change_value, 294);
+        assert_eq!(options.excess_strategy, ExcessStrategy::ToChange);
+        // 124 WU of change output at 2 sat/WU, plus a 272 WU input at 1 sat/WU to spend it later.
+        assert_eq!(options.change_cost, 248 + 272);
+
+        let options = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_wu(2.0))
+            .change_cost(7)
+            .build()
+            .unwrap();
+        assert_eq!(options.change_cost, 7);
+    }
+
+    #[test]
+    fn test_long_term_feerate_provider() {
+        /// Averages the feerates of recent blocks, as a wallet might.
+        #[derive(Debug)]
+        struct MovingAverage(Vec<FeeRate>);
+
+        impl LongTermFeeRateProvider for MovingAverage {
+            fn long_term_feerate(&self) -> FeeRate {
+                let total: u64 = self.0.iter().map(|rate| rate.as_sat_per_kwu()).sum();
+                FeeRate::from_sat_per_kwu(total / self.0.len() as u64)
+            }
+        }
+
+        let recent_blocks = MovingAverage(vec![
+            FeeRate::from_sat_per_wu(0.5),
+            FeeRate::from_sat_per_wu(1.5),
+        ]);
+        let options = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_wu(2.0))
+            .long_term_feerate(FeeRate::from_sat_per_wu(3.0))
+            .long_term_feerate_provider(recent_blocks)
+            .build()
+            .unwrap();
+        // The provider's 1 sat/WU average takes precedence over the static 3 sat/WU.
+        assert_eq!(options.change_cost, 248 + 272);
+        let (_, waste) = calculate_fee_and_waste(&options, 0, 100).unwrap();
+        assert_eq!(waste, 200 - 100);
+
+        let zero_rate = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_wu(2.0))
+            .long_term_feerate_provider(FeeRate::default())
+            .build();
+        assert!(matches!(zero_rate, Err(SelectionError::NonPositiveFeeRate)));
+    }
+
+    #[test]
+    fn test_dust_limits_and_derived_min_change_value() {
+        assert_eq!(ScriptType::P2pkh.dust_limit(DUST_RELAY_FEERATE), 546);
+        assert_eq!(ScriptType::P2shP2wpkh.dust_limit(DUST_RELAY_FEERATE), 540);
+        assert_eq!(ScriptType::P2wpkh.dust_limit(DUST_RELAY_FEERATE), 294);
+        assert_eq!(ScriptType::P2tr.dust_limit(DUST_RELAY_FEERATE), 330);
+        let p2wsh = ScriptType::P2wsh { witness_size: 254 };
+        assert_eq!(p2wsh.dust_limit(DUST_RELAY_FEERATE), 330);
+
+        let options = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_wu(1.0))
+            .change_script_type(ScriptType::P2tr)
+            .build()
+            .unwrap();
+        assert_eq!(options.change_weight, 172);
+        assert_eq!(options.min_change_value, 330);
+        // 172 WU of change output plus a 230 WU key path spend, both at 1 sat/WU.
+        assert_eq!(options.change_cost, 172 + 230);
+
+        let options = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_wu(1.0))
+            .dust_relay_feerate(FeeRate::from_sat_per_vb(1.0))
+            .build()
+            .unwrap();
+        assert_eq!(options.min_change_value, 98);
+
+        let options = CoinSelectionOpt::builder()
+            .target_value(100_000)
+            .target_feerate(FeeRate::from_sat_per_wu(1.0))
+            .min_change_value(1_000)
+            .build()
+            .unwrap();
+        assert_eq!(options.min_change_value, 1_000);
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        let missing_target = CoinSelectionOpt::builder()
+            .target_feerate(FeeRate::from_sat_per_wu(1.0))
+            .build();
+        assert!(matches!(
+            missing_target,
+            Err(SelectionError::NonPositiveTarget)
+        ));
+
+        let missing_feerate = CoinSelectionOpt::builder().target_value(1_000).build();
+        assert!(matches!(
+            missing_feerate,
+            Err(SelectionError::NonPositiveFeeRate)
+        ));
+
+        let free_change = CoinSelectionOpt::builder()
+            .target_value(1_000)
+            .target_feerate(FeeRate::from_sat_per_wu(1.0))
+            .change_weight(0)
+            .build();
+        assert!(matches!(free_change, Err(SelectionError::ZeroChangeWeight)));
+    }
+}